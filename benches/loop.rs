@@ -1,11 +1,11 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use evm::backend::{MemoryAccount, MemoryBackend, MemoryVicinity};
-use evm::executor::{MemoryStackState, StackExecutor, StackSubstateMetadata};
+use evm::executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata};
 use evm::Config;
 use primitive_types::{H160, U256};
 use std::{collections::BTreeMap, str::FromStr};
 
-fn run_loop_contract() {
+fn run_loop_contract(block_validation: bool) {
 	let config = Config::istanbul();
 
 	let vicinity = MemoryVicinity {
@@ -19,6 +19,9 @@ fn run_loop_contract() {
 		block_gas_limit: Default::default(),
 		chain_id: U256::one(),
 		block_base_fee_per_gas: U256::zero(),
+		block_randomness: None,
+		block_blob_base_fee: U256::zero(),
+		blob_hashes: Vec::new(),
 	};
 
 	let mut state = BTreeMap::new();
@@ -46,6 +49,7 @@ fn run_loop_contract() {
 	let state = MemoryStackState::new(metadata, &backend);
 	let precompiles = BTreeMap::new();
 	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+	executor.set_block_validation(block_validation);
 
 	let _reason = executor.transact_call(
 		H160::from_str("0xf000000000000000000000000000000000000000").unwrap(),
@@ -60,7 +64,10 @@ fn run_loop_contract() {
 }
 
 fn criterion_benchmark(c: &mut Criterion) {
-	c.bench_function("loop contract", |b| b.iter(|| run_loop_contract()));
+	c.bench_function("loop contract", |b| b.iter(|| run_loop_contract(false)));
+	c.bench_function("loop contract with block validation", |b| {
+		b.iter(|| run_loop_contract(true))
+	});
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -0,0 +1,222 @@
+use crate::Opcode;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::fmt;
+use primitive_types::U256;
+
+/// Instruction of disassembled code.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Instruction<'a> {
+	/// Offset of the opcode in the code.
+	pub offset: usize,
+	/// Opcode.
+	pub opcode: Opcode,
+	/// Immediate argument of a push, shorter than the push size if the code
+	/// ends before it.
+	pub immediate: &'a [u8],
+}
+
+impl<'a> Instruction<'a> {
+	/// Whether the code ends before the end of the immediate argument.
+	pub fn is_truncated(&self) -> bool {
+		self.immediate.len() < self.opcode.is_push().unwrap_or(0) as usize
+	}
+}
+
+/// Prints the instruction in the syntax of `assemble`. Undefined opcodes and
+/// truncated pushes are printed as raw bytes.
+impl<'a> fmt::Display for Instruction<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.is_truncated() {
+			write!(f, "0x{:02x}", self.opcode.0)?;
+			for byte in self.immediate {
+				write!(f, "{:02x}", byte)?;
+			}
+			return Ok(());
+		}
+
+		write!(f, "{}", self.opcode)?;
+		if self.opcode.is_push().is_some() {
+			f.write_str(" 0x")?;
+			for byte in self.immediate {
+				write!(f, "{:02x}", byte)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Iterator over the instructions of code. Bytes following a push opcode are
+/// its immediate argument, as in `Valids::new`.
+#[derive(Clone, Debug)]
+pub struct Disassembler<'a> {
+	code: &'a [u8],
+	position: usize,
+}
+
+impl<'a> Iterator for Disassembler<'a> {
+	type Item = Instruction<'a>;
+
+	fn next(&mut self) -> Option<Instruction<'a>> {
+		let offset = self.position;
+		let opcode = Opcode(*self.code.get(offset)?);
+		self.position = offset + 1 + opcode.is_push().unwrap_or(0) as usize;
+
+		Some(Instruction {
+			offset,
+			opcode,
+			immediate: &self.code[offset + 1..self.position.min(self.code.len())],
+		})
+	}
+}
+
+/// Disassemble code into instructions.
+pub fn disassemble(code: &[u8]) -> Disassembler<'_> {
+	Disassembler { code, position: 0 }
+}
+
+/// Reason source code failed to assemble, with the line it failed at,
+/// starting at one.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AssembleError {
+	/// Unknown mnemonic, or invalid raw bytes.
+	InvalidToken(usize),
+	/// Push without an immediate argument, or with one that does not fit.
+	InvalidImmediate(usize),
+	/// Label defined more than once, or used without being defined.
+	InvalidLabel(usize),
+}
+
+/// Parse a hexadecimal or decimal value into big-endian bytes, without
+/// leading zeros.
+fn parse_value(token: &str) -> Option<Vec<u8>> {
+	let bytes = if let Some(digits) = token.strip_prefix("0x") {
+		if digits.is_empty() || digits.len() > 64 {
+			return None;
+		}
+		let value = U256::from_str_radix(digits, 16).ok()?;
+		let mut bytes = [0u8; 32];
+		value.to_big_endian(&mut bytes);
+		bytes
+	} else {
+		let value = U256::from_dec_str(token).ok()?;
+		let mut bytes = [0u8; 32];
+		value.to_big_endian(&mut bytes);
+		bytes
+	};
+	let start = bytes.iter().position(|byte| *byte != 0).unwrap_or(32);
+	Some(bytes[start..].to_vec())
+}
+
+/// Parse raw bytes written as `0x` followed by an even number of
+/// hexadecimal digits.
+fn parse_bytes(token: &str) -> Option<Vec<u8>> {
+	let digits = token.strip_prefix("0x")?.as_bytes();
+	if digits.is_empty() || digits.len() % 2 != 0 {
+		return None;
+	}
+	digits
+		.chunks(2)
+		.map(|pair| {
+			let high = (pair[0] as char).to_digit(16)?;
+			let low = (pair[1] as char).to_digit(16)?;
+			Some((high * 16 + low) as u8)
+		})
+		.collect()
+}
+
+/// Assemble source code into bytecode.
+///
+/// Source is a sequence of whitespace-separated tokens, where `;` starts a
+/// comment until the end of the line:
+///
+/// * A mnemonic, such as `ADD`, emits its opcode.
+/// * `PUSHn` is followed by its immediate argument, either a hexadecimal
+///   value `0x2a`, a decimal value `42` or a label `@name`, and `PUSH` emits
+///   the smallest push fitting a value. Labels are pushed with `PUSH2`
+///   unless a size is given.
+/// * `name:` defines a label, and emits a `JUMPDEST` at its offset.
+/// * `0x` followed by an even number of hexadecimal digits emits raw bytes.
+///
+/// Printing the instructions of `disassemble` on separate lines gives source
+/// assembling back to the same code.
+pub fn assemble(source: &str) -> Result<Vec<u8>, AssembleError> {
+	let mut code = Vec::new();
+	let mut labels = BTreeMap::new();
+	// Offsets and sizes of label immediates to fill in.
+	let mut references = Vec::new();
+
+	for (index, line) in source.lines().enumerate() {
+		let line_number = index + 1;
+		let line = line.split(';').next().unwrap_or_default();
+		let mut tokens = line.split_whitespace();
+
+		while let Some(token) = tokens.next() {
+			if let Some(label) = token.strip_suffix(':') {
+				if label.is_empty() || labels.insert(label, code.len()).is_some() {
+					return Err(AssembleError::InvalidLabel(line_number));
+				}
+				code.push(Opcode::JUMPDEST.as_u8());
+				continue;
+			}
+
+			if token.starts_with("0x") {
+				let bytes = parse_bytes(token).ok_or(AssembleError::InvalidToken(line_number))?;
+				code.extend(bytes);
+				continue;
+			}
+
+			let size = if token.eq_ignore_ascii_case("PUSH") {
+				None
+			} else {
+				let opcode = token
+					.parse::<Opcode>()
+					.map_err(|_| AssembleError::InvalidToken(line_number))?;
+				match opcode.is_push() {
+					Some(size) => Some(size as usize),
+					None => {
+						code.push(opcode.as_u8());
+						continue;
+					}
+				}
+			};
+
+			let argument = tokens
+				.next()
+				.ok_or(AssembleError::InvalidImmediate(line_number))?;
+			match argument.strip_prefix('@') {
+				Some(label) => {
+					let size = size.unwrap_or(2);
+					code.push(Opcode::PUSH1.as_u8() + size as u8 - 1);
+					references.push((label, code.len(), size, line_number));
+					code.resize(code.len() + size, 0);
+				}
+				None => {
+					let value = parse_value(argument)
+						.ok_or(AssembleError::InvalidImmediate(line_number))?;
+					let size = size.unwrap_or_else(|| value.len().max(1));
+					if value.len() > size {
+						return Err(AssembleError::InvalidImmediate(line_number));
+					}
+					code.push(Opcode::PUSH1.as_u8() + size as u8 - 1);
+					code.resize(code.len() + size - value.len(), 0);
+					code.extend(value);
+				}
+			}
+		}
+	}
+
+	for (label, offset, size, line_number) in references {
+		let target = *labels
+			.get(label)
+			.ok_or(AssembleError::InvalidLabel(line_number))?;
+		let target = (target as u64).to_be_bytes();
+		let start = target.iter().position(|byte| *byte != 0).unwrap_or(8);
+		if 8 - start > size {
+			return Err(AssembleError::InvalidImmediate(line_number));
+		}
+		code[offset + size - (8 - start)..offset + size].copy_from_slice(&target[start..]);
+	}
+
+	Ok(code)
+}
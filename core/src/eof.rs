@@ -0,0 +1,343 @@
+//! EVM Object Format (EOF) containers.
+
+use crate::Opcode;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// Magic bytes every EOF container starts with.
+pub const EOF_MAGIC: [u8; 2] = [0xef, 0x00];
+/// Supported EOF version.
+pub const EOF_VERSION: u8 = 0x01;
+/// Maximum depth of the return stack used by `CALLF` and `RETF`.
+pub const RETURN_STACK_LIMIT: usize = 1024;
+
+const KIND_TYPE: u8 = 0x01;
+const KIND_CODE: u8 = 0x02;
+const KIND_DATA: u8 = 0x04;
+const TERMINATOR: u8 = 0x00;
+
+const MAX_CODE_SECTIONS: usize = 1024;
+const MAX_SECTION_IO: u8 = 0x7f;
+const MAX_STACK_HEIGHT: usize = 1023;
+
+/// Reason an EOF container failed validation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EofError {
+	/// Code does not start with `0xEF00`.
+	InvalidMagic,
+	/// Unsupported container version.
+	InvalidVersion,
+	/// Header is malformed or truncated.
+	InvalidHeader,
+	/// Section sizes do not add up to the container size.
+	InvalidContainerSize,
+	/// Type section does not match the code sections, or has out of
+	/// range values.
+	InvalidTypeSection,
+	/// Opcode is undefined, or not allowed in EOF code.
+	UndefinedInstruction(Opcode),
+	/// Immediate argument runs past the end of its code section.
+	TruncatedImmediate,
+	/// Relative jump out of its code section or into an immediate argument.
+	InvalidJumpDestination,
+	/// `CALLF` to a code section that does not exist.
+	InvalidCodeSection,
+	/// Execution can run past the end of a code section.
+	MissingTerminatingInstruction,
+	/// Code section has instructions that can never be reached.
+	UnreachableCode,
+	/// Instruction needs more stack items than available.
+	StackUnderflow,
+	/// Stack height exceeds the limit.
+	StackOverflow,
+	/// Instruction is reached with different stack heights, or `RETF` is
+	/// reached with a stack height other than the section outputs.
+	StackHeightMismatch,
+	/// Maximum stack height differs from the one in the type section.
+	MaxStackHeightMismatch,
+}
+
+/// Type of an EOF code section.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EofTypeSection {
+	/// Number of stack items the section takes.
+	pub inputs: u8,
+	/// Number of stack items the section returns.
+	pub outputs: u8,
+	/// Maximum stack height reached in the section.
+	pub max_stack_height: u16,
+}
+
+/// Layout of an EOF container. Sections are ranges into the container
+/// bytes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EofContainer {
+	/// Types of the code sections.
+	pub types: Vec<EofTypeSection>,
+	/// Code sections. Execution starts at the first one.
+	pub code: Vec<Range<usize>>,
+	/// Data section.
+	pub data: Range<usize>,
+}
+
+struct HeaderReader<'a> {
+	code: &'a [u8],
+	position: usize,
+}
+
+impl<'a> HeaderReader<'a> {
+	fn u8(&mut self) -> Result<u8, EofError> {
+		let value = *self
+			.code
+			.get(self.position)
+			.ok_or(EofError::InvalidHeader)?;
+		self.position += 1;
+		Ok(value)
+	}
+
+	fn u16(&mut self) -> Result<u16, EofError> {
+		Ok(u16::from(self.u8()?) << 8 | u16::from(self.u8()?))
+	}
+
+	fn expect(&mut self, kind: u8) -> Result<(), EofError> {
+		if self.u8()? == kind {
+			Ok(())
+		} else {
+			Err(EofError::InvalidHeader)
+		}
+	}
+}
+
+/// Read a big-endian `u16` immediate argument.
+pub(crate) fn read_u16(code: &[u8], position: usize) -> u16 {
+	u16::from(code[position]) << 8 | u16::from(code[position + 1])
+}
+
+/// Target of a relative jump, whose `i16` offset is at `position`, from the
+/// instruction following it at `next`.
+pub(crate) fn relative_target(code: &[u8], position: usize, next: usize) -> isize {
+	next as isize + read_u16(code, position) as i16 as isize
+}
+
+/// Size of the immediate arguments of the instruction at `position`.
+fn immediate_size(code: &[u8], position: usize) -> Result<usize, EofError> {
+	Ok(match Opcode(code[position]) {
+		Opcode::RJUMPV => {
+			let max_index = *code.get(position + 1).ok_or(EofError::TruncatedImmediate)?;
+			1 + 2 * (max_index as usize + 1)
+		}
+		opcode => opcode.info().map_or(0, |info| info.immediate_size) as usize,
+	})
+}
+
+/// Stack items taken and returned by an opcode allowed in EOF code, except
+/// `CALLF` and `RETF` which depend on the code section types.
+fn stack_io(opcode: Opcode) -> Option<(usize, usize)> {
+	match opcode {
+		Opcode::JUMP
+		| Opcode::JUMPI
+		| Opcode::PC
+		| Opcode::CALLCODE
+		| Opcode::SUICIDE
+		| Opcode::CALLF
+		| Opcode::RETF => None,
+		opcode => opcode
+			.info()
+			.map(|info| (info.inputs as usize, info.outputs as usize)),
+	}
+}
+
+impl EofContainer {
+	/// Parse the header of an EOF container, without validating its code.
+	pub fn parse(code: &[u8]) -> Result<Self, EofError> {
+		if !code.starts_with(&EOF_MAGIC) {
+			return Err(EofError::InvalidMagic);
+		}
+		if code.get(2) != Some(&EOF_VERSION) {
+			return Err(EofError::InvalidVersion);
+		}
+
+		let mut reader = HeaderReader { code, position: 3 };
+		reader.expect(KIND_TYPE)?;
+		let types_size = reader.u16()? as usize;
+		reader.expect(KIND_CODE)?;
+		let num_code_sections = reader.u16()? as usize;
+		if num_code_sections == 0 || num_code_sections > MAX_CODE_SECTIONS {
+			return Err(EofError::InvalidHeader);
+		}
+		let mut code_sizes = Vec::with_capacity(num_code_sections);
+		for _ in 0..num_code_sections {
+			let size = reader.u16()? as usize;
+			if size == 0 {
+				return Err(EofError::InvalidHeader);
+			}
+			code_sizes.push(size);
+		}
+		reader.expect(KIND_DATA)?;
+		let data_size = reader.u16()? as usize;
+		reader.expect(TERMINATOR)?;
+
+		if types_size != num_code_sections * 4 {
+			return Err(EofError::InvalidTypeSection);
+		}
+		let body_size = types_size + code_sizes.iter().sum::<usize>() + data_size;
+		if code.len() != reader.position + body_size {
+			return Err(EofError::InvalidContainerSize);
+		}
+
+		let mut offset = reader.position;
+		let types = code[offset..(offset + types_size)]
+			.chunks(4)
+			.map(|entry| EofTypeSection {
+				inputs: entry[0],
+				outputs: entry[1],
+				max_stack_height: read_u16(entry, 2),
+			})
+			.collect();
+		offset += types_size;
+
+		let code_sections = code_sizes
+			.into_iter()
+			.map(|size| {
+				offset += size;
+				(offset - size)..offset
+			})
+			.collect();
+
+		Ok(Self {
+			types,
+			code: code_sections,
+			data: offset..code.len(),
+		})
+	}
+
+	/// Parse an EOF container and validate its code, as done before it is
+	/// deployed.
+	pub fn validate(code: &[u8]) -> Result<Self, EofError> {
+		let container = Self::parse(code)?;
+
+		if container.types[0].inputs != 0 || container.types[0].outputs != 0 {
+			return Err(EofError::InvalidTypeSection);
+		}
+		for section in &container.types {
+			if section.inputs > MAX_SECTION_IO
+				|| section.outputs > MAX_SECTION_IO
+				|| section.max_stack_height as usize > MAX_STACK_HEIGHT
+			{
+				return Err(EofError::InvalidTypeSection);
+			}
+		}
+
+		for (index, range) in container.code.iter().enumerate() {
+			container.validate_code_section(index, &code[range.clone()])?;
+		}
+
+		Ok(container)
+	}
+
+	/// Check the instructions of a code section, then follow every path
+	/// through it to check that the stack height is the same each time an
+	/// instruction is reached.
+	fn validate_code_section(&self, index: usize, code: &[u8]) -> Result<(), EofError> {
+		let mut instructions = vec![false; code.len()];
+		let mut position = 0;
+		while position < code.len() {
+			let opcode = Opcode(code[position]);
+			if stack_io(opcode).is_none() && opcode != Opcode::CALLF && opcode != Opcode::RETF {
+				return Err(EofError::UndefinedInstruction(opcode));
+			}
+			instructions[position] = true;
+
+			let next = position + 1 + immediate_size(code, position)?;
+			if next > code.len() {
+				return Err(EofError::TruncatedImmediate);
+			}
+			if opcode == Opcode::CALLF && read_u16(code, position + 1) as usize >= self.types.len()
+			{
+				return Err(EofError::InvalidCodeSection);
+			}
+			position = next;
+		}
+
+		let section = self.types[index];
+		let mut heights: Vec<Option<usize>> = vec![None; code.len()];
+		let mut max_height = section.inputs as usize;
+		let mut pending = vec![(0, section.inputs as usize)];
+		while let Some((position, height)) = pending.pop() {
+			match heights[position] {
+				Some(expected) if expected != height => return Err(EofError::StackHeightMismatch),
+				Some(_) => continue,
+				None => heights[position] = Some(height),
+			}
+
+			let opcode = Opcode(code[position]);
+			let (inputs, outputs) = match opcode {
+				Opcode::CALLF => {
+					let callee = self.types[read_u16(code, position + 1) as usize];
+					(callee.inputs as usize, callee.outputs as usize)
+				}
+				Opcode::RETF if height != section.outputs as usize => {
+					return Err(EofError::StackHeightMismatch)
+				}
+				Opcode::RETF => (0, 0),
+				_ => stack_io(opcode).unwrap_or((0, 0)),
+			};
+			if height < inputs {
+				return Err(EofError::StackUnderflow);
+			}
+			let height = height - inputs + outputs;
+			if height > MAX_STACK_HEIGHT {
+				return Err(EofError::StackOverflow);
+			}
+			if height > max_height {
+				max_height = height;
+			}
+
+			let next = position + 1 + immediate_size(code, position)?;
+			let mut successors = Vec::new();
+			match opcode {
+				Opcode::STOP | Opcode::RETURN | Opcode::REVERT | Opcode::INVALID | Opcode::RETF => {
+				}
+				Opcode::RJUMP => successors.push(relative_target(code, position + 1, next)),
+				Opcode::RJUMPI => {
+					successors.push(next as isize);
+					successors.push(relative_target(code, position + 1, next));
+				}
+				Opcode::RJUMPV => {
+					successors.push(next as isize);
+					for case in 0..=code[position + 1] as usize {
+						successors.push(relative_target(code, position + 2 + 2 * case, next));
+					}
+				}
+				_ => successors.push(next as isize),
+			}
+
+			for successor in successors {
+				if successor == next as isize && next >= code.len() {
+					return Err(EofError::MissingTerminatingInstruction);
+				}
+				if successor < 0
+					|| successor as usize >= code.len()
+					|| !instructions[successor as usize]
+				{
+					return Err(EofError::InvalidJumpDestination);
+				}
+				pending.push((successor as usize, height));
+			}
+		}
+
+		if instructions
+			.iter()
+			.zip(heights.iter())
+			.any(|(instruction, height)| *instruction && height.is_none())
+		{
+			return Err(EofError::UnreachableCode);
+		}
+		if max_height != section.max_stack_height as usize {
+			return Err(EofError::MaxStackHeightMismatch);
+		}
+
+		Ok(())
+	}
+}
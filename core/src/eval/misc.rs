@@ -1,6 +1,7 @@
 use super::Control;
-use crate::{ExitError, ExitFatal, ExitRevert, ExitSucceed, Machine};
-use core::cmp::min;
+use crate::eof::{read_u16, relative_target};
+use crate::{ExitError, ExitFatal, ExitRevert, ExitSucceed, Machine, Opcode, RETURN_STACK_LIMIT};
+use core::cmp::{max, min};
 use primitive_types::{H256, U256};
 
 #[inline]
@@ -70,6 +71,23 @@ pub fn calldatacopy(state: &mut Machine) -> Control {
 	}
 }
 
+#[inline]
+pub fn mcopy(state: &mut Machine) -> Control {
+	pop_u256!(state, dst, src, len);
+
+	try_or_fail!(state.memory.resize_offset(max(dst, src), len));
+	if len == U256::zero() {
+		return Control::Continue(1);
+	}
+
+	let src = as_usize_or_fail!(src);
+	let data = state.memory.get(src, as_usize_or_fail!(len));
+	match state.memory.copy_large(dst, U256::zero(), len, &data) {
+		Ok(()) => Control::Continue(1),
+		Err(e) => Control::Exit(e.into()),
+	}
+}
+
 #[inline]
 pub fn pop(state: &mut Machine) -> Control {
 	pop!(state, _val);
@@ -145,6 +163,107 @@ pub fn pc(state: &mut Machine, position: usize) -> Control {
 	Control::Continue(1)
 }
 
+/// Whether the immediate arguments of an instruction ending at `end` run
+/// past the code. Only EOF containers deployed without validation, such as
+/// the ones of a genesis state, can be truncated this way.
+fn truncated(state: &Machine, end: usize) -> bool {
+	end > state.code.len()
+}
+
+#[inline]
+pub fn rjump(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	if state.eof.is_none() {
+		return Control::Trap(opcode);
+	}
+	if truncated(state, position + 3) {
+		return Control::Exit(ExitError::InvalidCode.into());
+	}
+
+	Control::Jump(relative_target(&state.code, position + 1, position + 3) as usize)
+}
+
+#[inline]
+pub fn rjumpi(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	if state.eof.is_none() {
+		return Control::Trap(opcode);
+	}
+	if truncated(state, position + 3) {
+		return Control::Exit(ExitError::InvalidCode.into());
+	}
+	pop!(state, value);
+
+	if value != H256::zero() {
+		Control::Jump(relative_target(&state.code, position + 1, position + 3) as usize)
+	} else {
+		Control::Continue(3)
+	}
+}
+
+#[inline]
+pub fn rjumpv(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	if state.eof.is_none() {
+		return Control::Trap(opcode);
+	}
+	let max_index = match state.code.get(position + 1) {
+		Some(max_index) => *max_index as usize,
+		None => return Control::Exit(ExitError::InvalidCode.into()),
+	};
+	let size = 2 + 2 * (max_index + 1);
+	if truncated(state, position + size) {
+		return Control::Exit(ExitError::InvalidCode.into());
+	}
+	pop_u256!(state, case);
+
+	if case <= U256::from(max_index) {
+		let offset = position + 2 + 2 * case.as_usize();
+		Control::Jump(relative_target(&state.code, offset, position + size) as usize)
+	} else {
+		Control::Continue(size)
+	}
+}
+
+#[inline]
+pub fn callf(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	let eof = match &state.eof {
+		Some(eof) => eof,
+		None => return Control::Trap(opcode),
+	};
+	if truncated(state, position + 3) {
+		return Control::Exit(ExitError::InvalidCode.into());
+	}
+	let section = read_u16(&state.code, position + 1) as usize;
+	let (start, callee) = match (eof.code.get(section), eof.types.get(section)) {
+		(Some(range), Some(callee)) => (range.start, *callee),
+		_ => return Control::Exit(ExitError::InvalidCode.into()),
+	};
+
+	if state.return_stack.len() >= RETURN_STACK_LIMIT
+		|| state.stack.len() + callee.max_stack_height as usize
+			> state.stack.limit() + callee.inputs as usize
+	{
+		return Control::Exit(ExitError::StackOverflow.into());
+	}
+
+	state.return_stack.push((state.code_section, position + 3));
+	state.code_section = section;
+	Control::Jump(start)
+}
+
+#[inline]
+pub fn retf(state: &mut Machine, opcode: Opcode) -> Control {
+	if state.eof.is_none() {
+		return Control::Trap(opcode);
+	}
+
+	match state.return_stack.pop() {
+		Some((section, position)) => {
+			state.code_section = section;
+			Control::Jump(position)
+		}
+		None => Control::Exit(ExitSucceed::Stopped.into()),
+	}
+}
+
 #[inline]
 pub fn msize(state: &mut Machine) -> Control {
 	push_u256!(state, state.memory.effective_len());
@@ -3,6 +3,7 @@ mod macros;
 mod arithmetic;
 mod bitwise;
 mod misc;
+mod prevalidated;
 
 use crate::{ExitError, ExitReason, ExitSucceed, Machine, Opcode};
 use core::ops::{BitAnd, BitOr, BitXor};
@@ -168,6 +169,26 @@ fn eval_pc(state: &mut Machine, _opcode: Opcode, position: usize) -> Control {
 	self::misc::pc(state, position)
 }
 
+fn eval_rjump(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	self::misc::rjump(state, opcode, position)
+}
+
+fn eval_rjumpi(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	self::misc::rjumpi(state, opcode, position)
+}
+
+fn eval_rjumpv(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	self::misc::rjumpv(state, opcode, position)
+}
+
+fn eval_callf(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	self::misc::callf(state, opcode, position)
+}
+
+fn eval_retf(state: &mut Machine, opcode: Opcode, _position: usize) -> Control {
+	self::misc::retf(state, opcode)
+}
+
 fn eval_msize(state: &mut Machine, _opcode: Opcode, _position: usize) -> Control {
 	self::misc::msize(state)
 }
@@ -176,6 +197,14 @@ fn eval_jumpdest(_state: &mut Machine, _opcode: Opcode, _position: usize) -> Con
 	Control::Continue(1)
 }
 
+fn eval_mcopy(state: &mut Machine, _opcode: Opcode, _position: usize) -> Control {
+	self::misc::mcopy(state)
+}
+
+fn eval_push0(state: &mut Machine, _opcode: Opcode, position: usize) -> Control {
+	self::misc::push(state, 0, position)
+}
+
 fn eval_push1(state: &mut Machine, _opcode: Opcode, position: usize) -> Control {
 	self::misc::push(state, 1, position)
 }
@@ -448,125 +477,179 @@ fn eval_external(_state: &mut Machine, opcode: Opcode, _position: usize) -> Cont
 	Control::Trap(opcode)
 }
 
+type Eval = fn(state: &mut Machine, opcode: Opcode, position: usize) -> Control;
+
+const TABLE: [Eval; 256] = {
+	let mut table = [eval_external as _; 256];
+
+	table[Opcode::STOP.as_usize()] = eval_stop as _;
+	table[Opcode::ADD.as_usize()] = eval_add as _;
+	table[Opcode::MUL.as_usize()] = eval_mul as _;
+	table[Opcode::SUB.as_usize()] = eval_sub as _;
+	table[Opcode::DIV.as_usize()] = eval_div as _;
+	table[Opcode::SDIV.as_usize()] = eval_sdiv as _;
+	table[Opcode::MOD.as_usize()] = eval_mod as _;
+	table[Opcode::SMOD.as_usize()] = eval_smod as _;
+	table[Opcode::ADDMOD.as_usize()] = eval_addmod as _;
+	table[Opcode::MULMOD.as_usize()] = eval_mulmod as _;
+	table[Opcode::EXP.as_usize()] = eval_exp as _;
+	table[Opcode::SIGNEXTEND.as_usize()] = eval_signextend as _;
+	table[Opcode::LT.as_usize()] = eval_lt as _;
+	table[Opcode::GT.as_usize()] = eval_gt as _;
+	table[Opcode::SLT.as_usize()] = eval_slt as _;
+	table[Opcode::SGT.as_usize()] = eval_sgt as _;
+	table[Opcode::EQ.as_usize()] = eval_eq as _;
+	table[Opcode::ISZERO.as_usize()] = eval_iszero as _;
+	table[Opcode::AND.as_usize()] = eval_and as _;
+	table[Opcode::OR.as_usize()] = eval_or as _;
+	table[Opcode::XOR.as_usize()] = eval_xor as _;
+	table[Opcode::NOT.as_usize()] = eval_not as _;
+	table[Opcode::BYTE.as_usize()] = eval_byte as _;
+	table[Opcode::SHL.as_usize()] = eval_shl as _;
+	table[Opcode::SHR.as_usize()] = eval_shr as _;
+	table[Opcode::SAR.as_usize()] = eval_sar as _;
+	table[Opcode::CODESIZE.as_usize()] = eval_codesize as _;
+	table[Opcode::CODECOPY.as_usize()] = eval_codecopy as _;
+	table[Opcode::CALLDATALOAD.as_usize()] = eval_calldataload as _;
+	table[Opcode::CALLDATASIZE.as_usize()] = eval_calldatasize as _;
+	table[Opcode::CALLDATACOPY.as_usize()] = eval_calldatacopy as _;
+	table[Opcode::POP.as_usize()] = eval_pop as _;
+	table[Opcode::MLOAD.as_usize()] = eval_mload as _;
+	table[Opcode::MSTORE.as_usize()] = eval_mstore as _;
+	table[Opcode::MSTORE8.as_usize()] = eval_mstore8 as _;
+	table[Opcode::JUMP.as_usize()] = eval_jump as _;
+	table[Opcode::JUMPI.as_usize()] = eval_jumpi as _;
+	table[Opcode::PC.as_usize()] = eval_pc as _;
+	table[Opcode::MSIZE.as_usize()] = eval_msize as _;
+	table[Opcode::JUMPDEST.as_usize()] = eval_jumpdest as _;
+	table[Opcode::MCOPY.as_usize()] = eval_mcopy as _;
+
+	table[Opcode::PUSH0.as_usize()] = eval_push0 as _;
+
+	table[Opcode::PUSH1.as_usize()] = eval_push1 as _;
+	table[Opcode::PUSH2.as_usize()] = eval_push2 as _;
+	table[Opcode::PUSH3.as_usize()] = eval_push3 as _;
+	table[Opcode::PUSH4.as_usize()] = eval_push4 as _;
+	table[Opcode::PUSH5.as_usize()] = eval_push5 as _;
+	table[Opcode::PUSH6.as_usize()] = eval_push6 as _;
+	table[Opcode::PUSH7.as_usize()] = eval_push7 as _;
+	table[Opcode::PUSH8.as_usize()] = eval_push8 as _;
+	table[Opcode::PUSH9.as_usize()] = eval_push9 as _;
+	table[Opcode::PUSH10.as_usize()] = eval_push10 as _;
+	table[Opcode::PUSH11.as_usize()] = eval_push11 as _;
+	table[Opcode::PUSH12.as_usize()] = eval_push12 as _;
+	table[Opcode::PUSH13.as_usize()] = eval_push13 as _;
+	table[Opcode::PUSH14.as_usize()] = eval_push14 as _;
+	table[Opcode::PUSH15.as_usize()] = eval_push15 as _;
+	table[Opcode::PUSH16.as_usize()] = eval_push16 as _;
+	table[Opcode::PUSH17.as_usize()] = eval_push17 as _;
+	table[Opcode::PUSH18.as_usize()] = eval_push18 as _;
+	table[Opcode::PUSH19.as_usize()] = eval_push19 as _;
+	table[Opcode::PUSH20.as_usize()] = eval_push20 as _;
+	table[Opcode::PUSH21.as_usize()] = eval_push21 as _;
+	table[Opcode::PUSH22.as_usize()] = eval_push22 as _;
+	table[Opcode::PUSH23.as_usize()] = eval_push23 as _;
+	table[Opcode::PUSH24.as_usize()] = eval_push24 as _;
+	table[Opcode::PUSH25.as_usize()] = eval_push25 as _;
+	table[Opcode::PUSH26.as_usize()] = eval_push26 as _;
+	table[Opcode::PUSH27.as_usize()] = eval_push27 as _;
+	table[Opcode::PUSH28.as_usize()] = eval_push28 as _;
+	table[Opcode::PUSH29.as_usize()] = eval_push29 as _;
+	table[Opcode::PUSH30.as_usize()] = eval_push30 as _;
+	table[Opcode::PUSH31.as_usize()] = eval_push31 as _;
+	table[Opcode::PUSH32.as_usize()] = eval_push32 as _;
+
+	table[Opcode::DUP1.as_usize()] = eval_dup1 as _;
+	table[Opcode::DUP2.as_usize()] = eval_dup2 as _;
+	table[Opcode::DUP3.as_usize()] = eval_dup3 as _;
+	table[Opcode::DUP4.as_usize()] = eval_dup4 as _;
+	table[Opcode::DUP5.as_usize()] = eval_dup5 as _;
+	table[Opcode::DUP6.as_usize()] = eval_dup6 as _;
+	table[Opcode::DUP7.as_usize()] = eval_dup7 as _;
+	table[Opcode::DUP8.as_usize()] = eval_dup8 as _;
+	table[Opcode::DUP9.as_usize()] = eval_dup9 as _;
+	table[Opcode::DUP10.as_usize()] = eval_dup10 as _;
+	table[Opcode::DUP11.as_usize()] = eval_dup11 as _;
+	table[Opcode::DUP12.as_usize()] = eval_dup12 as _;
+	table[Opcode::DUP13.as_usize()] = eval_dup13 as _;
+	table[Opcode::DUP14.as_usize()] = eval_dup14 as _;
+	table[Opcode::DUP15.as_usize()] = eval_dup15 as _;
+	table[Opcode::DUP16.as_usize()] = eval_dup16 as _;
+
+	table[Opcode::SWAP1.as_usize()] = eval_swap1 as _;
+	table[Opcode::SWAP2.as_usize()] = eval_swap2 as _;
+	table[Opcode::SWAP3.as_usize()] = eval_swap3 as _;
+	table[Opcode::SWAP4.as_usize()] = eval_swap4 as _;
+	table[Opcode::SWAP5.as_usize()] = eval_swap5 as _;
+	table[Opcode::SWAP6.as_usize()] = eval_swap6 as _;
+	table[Opcode::SWAP7.as_usize()] = eval_swap7 as _;
+	table[Opcode::SWAP8.as_usize()] = eval_swap8 as _;
+	table[Opcode::SWAP9.as_usize()] = eval_swap9 as _;
+	table[Opcode::SWAP10.as_usize()] = eval_swap10 as _;
+	table[Opcode::SWAP11.as_usize()] = eval_swap11 as _;
+	table[Opcode::SWAP12.as_usize()] = eval_swap12 as _;
+	table[Opcode::SWAP13.as_usize()] = eval_swap13 as _;
+	table[Opcode::SWAP14.as_usize()] = eval_swap14 as _;
+	table[Opcode::SWAP15.as_usize()] = eval_swap15 as _;
+	table[Opcode::SWAP16.as_usize()] = eval_swap16 as _;
+
+	table[Opcode::RJUMP.as_usize()] = eval_rjump as _;
+	table[Opcode::RJUMPI.as_usize()] = eval_rjumpi as _;
+	table[Opcode::RJUMPV.as_usize()] = eval_rjumpv as _;
+	table[Opcode::CALLF.as_usize()] = eval_callf as _;
+	table[Opcode::RETF.as_usize()] = eval_retf as _;
+
+	table[Opcode::RETURN.as_usize()] = eval_return as _;
+	table[Opcode::REVERT.as_usize()] = eval_revert as _;
+	table[Opcode::INVALID.as_usize()] = eval_invalid as _;
+
+	table
+};
+
 #[inline]
 pub fn eval(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
-	static TABLE: [fn(state: &mut Machine, opcode: Opcode, position: usize) -> Control; 256] = {
-		let mut table = [eval_external as _; 256];
-
-		table[Opcode::STOP.as_usize()] = eval_stop as _;
-		table[Opcode::ADD.as_usize()] = eval_add as _;
-		table[Opcode::MUL.as_usize()] = eval_mul as _;
-		table[Opcode::SUB.as_usize()] = eval_sub as _;
-		table[Opcode::DIV.as_usize()] = eval_div as _;
-		table[Opcode::SDIV.as_usize()] = eval_sdiv as _;
-		table[Opcode::MOD.as_usize()] = eval_mod as _;
-		table[Opcode::SMOD.as_usize()] = eval_smod as _;
-		table[Opcode::ADDMOD.as_usize()] = eval_addmod as _;
-		table[Opcode::MULMOD.as_usize()] = eval_mulmod as _;
-		table[Opcode::EXP.as_usize()] = eval_exp as _;
-		table[Opcode::SIGNEXTEND.as_usize()] = eval_signextend as _;
-		table[Opcode::LT.as_usize()] = eval_lt as _;
-		table[Opcode::GT.as_usize()] = eval_gt as _;
-		table[Opcode::SLT.as_usize()] = eval_slt as _;
-		table[Opcode::SGT.as_usize()] = eval_sgt as _;
-		table[Opcode::EQ.as_usize()] = eval_eq as _;
-		table[Opcode::ISZERO.as_usize()] = eval_iszero as _;
-		table[Opcode::AND.as_usize()] = eval_and as _;
-		table[Opcode::OR.as_usize()] = eval_or as _;
-		table[Opcode::XOR.as_usize()] = eval_xor as _;
-		table[Opcode::NOT.as_usize()] = eval_not as _;
-		table[Opcode::BYTE.as_usize()] = eval_byte as _;
-		table[Opcode::SHL.as_usize()] = eval_shl as _;
-		table[Opcode::SHR.as_usize()] = eval_shr as _;
-		table[Opcode::SAR.as_usize()] = eval_sar as _;
-		table[Opcode::CODESIZE.as_usize()] = eval_codesize as _;
-		table[Opcode::CODECOPY.as_usize()] = eval_codecopy as _;
-		table[Opcode::CALLDATALOAD.as_usize()] = eval_calldataload as _;
-		table[Opcode::CALLDATASIZE.as_usize()] = eval_calldatasize as _;
-		table[Opcode::CALLDATACOPY.as_usize()] = eval_calldatacopy as _;
-		table[Opcode::POP.as_usize()] = eval_pop as _;
-		table[Opcode::MLOAD.as_usize()] = eval_mload as _;
-		table[Opcode::MSTORE.as_usize()] = eval_mstore as _;
-		table[Opcode::MSTORE8.as_usize()] = eval_mstore8 as _;
-		table[Opcode::JUMP.as_usize()] = eval_jump as _;
-		table[Opcode::JUMPI.as_usize()] = eval_jumpi as _;
-		table[Opcode::PC.as_usize()] = eval_pc as _;
-		table[Opcode::MSIZE.as_usize()] = eval_msize as _;
-		table[Opcode::JUMPDEST.as_usize()] = eval_jumpdest as _;
-
-		table[Opcode::PUSH1.as_usize()] = eval_push1 as _;
-		table[Opcode::PUSH2.as_usize()] = eval_push2 as _;
-		table[Opcode::PUSH3.as_usize()] = eval_push3 as _;
-		table[Opcode::PUSH4.as_usize()] = eval_push4 as _;
-		table[Opcode::PUSH5.as_usize()] = eval_push5 as _;
-		table[Opcode::PUSH6.as_usize()] = eval_push6 as _;
-		table[Opcode::PUSH7.as_usize()] = eval_push7 as _;
-		table[Opcode::PUSH8.as_usize()] = eval_push8 as _;
-		table[Opcode::PUSH9.as_usize()] = eval_push9 as _;
-		table[Opcode::PUSH10.as_usize()] = eval_push10 as _;
-		table[Opcode::PUSH11.as_usize()] = eval_push11 as _;
-		table[Opcode::PUSH12.as_usize()] = eval_push12 as _;
-		table[Opcode::PUSH13.as_usize()] = eval_push13 as _;
-		table[Opcode::PUSH14.as_usize()] = eval_push14 as _;
-		table[Opcode::PUSH15.as_usize()] = eval_push15 as _;
-		table[Opcode::PUSH16.as_usize()] = eval_push16 as _;
-		table[Opcode::PUSH17.as_usize()] = eval_push17 as _;
-		table[Opcode::PUSH18.as_usize()] = eval_push18 as _;
-		table[Opcode::PUSH19.as_usize()] = eval_push19 as _;
-		table[Opcode::PUSH20.as_usize()] = eval_push20 as _;
-		table[Opcode::PUSH21.as_usize()] = eval_push21 as _;
-		table[Opcode::PUSH22.as_usize()] = eval_push22 as _;
-		table[Opcode::PUSH23.as_usize()] = eval_push23 as _;
-		table[Opcode::PUSH24.as_usize()] = eval_push24 as _;
-		table[Opcode::PUSH25.as_usize()] = eval_push25 as _;
-		table[Opcode::PUSH26.as_usize()] = eval_push26 as _;
-		table[Opcode::PUSH27.as_usize()] = eval_push27 as _;
-		table[Opcode::PUSH28.as_usize()] = eval_push28 as _;
-		table[Opcode::PUSH29.as_usize()] = eval_push29 as _;
-		table[Opcode::PUSH30.as_usize()] = eval_push30 as _;
-		table[Opcode::PUSH31.as_usize()] = eval_push31 as _;
-		table[Opcode::PUSH32.as_usize()] = eval_push32 as _;
-
-		table[Opcode::DUP1.as_usize()] = eval_dup1 as _;
-		table[Opcode::DUP2.as_usize()] = eval_dup2 as _;
-		table[Opcode::DUP3.as_usize()] = eval_dup3 as _;
-		table[Opcode::DUP4.as_usize()] = eval_dup4 as _;
-		table[Opcode::DUP5.as_usize()] = eval_dup5 as _;
-		table[Opcode::DUP6.as_usize()] = eval_dup6 as _;
-		table[Opcode::DUP7.as_usize()] = eval_dup7 as _;
-		table[Opcode::DUP8.as_usize()] = eval_dup8 as _;
-		table[Opcode::DUP9.as_usize()] = eval_dup9 as _;
-		table[Opcode::DUP10.as_usize()] = eval_dup10 as _;
-		table[Opcode::DUP11.as_usize()] = eval_dup11 as _;
-		table[Opcode::DUP12.as_usize()] = eval_dup12 as _;
-		table[Opcode::DUP13.as_usize()] = eval_dup13 as _;
-		table[Opcode::DUP14.as_usize()] = eval_dup14 as _;
-		table[Opcode::DUP15.as_usize()] = eval_dup15 as _;
-		table[Opcode::DUP16.as_usize()] = eval_dup16 as _;
-
-		table[Opcode::SWAP1.as_usize()] = eval_swap1 as _;
-		table[Opcode::SWAP2.as_usize()] = eval_swap2 as _;
-		table[Opcode::SWAP3.as_usize()] = eval_swap3 as _;
-		table[Opcode::SWAP4.as_usize()] = eval_swap4 as _;
-		table[Opcode::SWAP5.as_usize()] = eval_swap5 as _;
-		table[Opcode::SWAP6.as_usize()] = eval_swap6 as _;
-		table[Opcode::SWAP7.as_usize()] = eval_swap7 as _;
-		table[Opcode::SWAP8.as_usize()] = eval_swap8 as _;
-		table[Opcode::SWAP9.as_usize()] = eval_swap9 as _;
-		table[Opcode::SWAP10.as_usize()] = eval_swap10 as _;
-		table[Opcode::SWAP11.as_usize()] = eval_swap11 as _;
-		table[Opcode::SWAP12.as_usize()] = eval_swap12 as _;
-		table[Opcode::SWAP13.as_usize()] = eval_swap13 as _;
-		table[Opcode::SWAP14.as_usize()] = eval_swap14 as _;
-		table[Opcode::SWAP15.as_usize()] = eval_swap15 as _;
-		table[Opcode::SWAP16.as_usize()] = eval_swap16 as _;
-
-		table[Opcode::RETURN.as_usize()] = eval_return as _;
-		table[Opcode::REVERT.as_usize()] = eval_revert as _;
-		table[Opcode::INVALID.as_usize()] = eval_invalid as _;
+	static CHECKED: [Eval; 256] = TABLE;
+
+	CHECKED[opcode.as_usize()](state, opcode, position)
+}
+
+/// Evaluate an instruction of a static block entered with a stack it fits.
+/// The instructions most common in such blocks skip their stack checks.
+#[inline]
+pub fn eval_prevalidated(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	static PREVALIDATED: [Eval; 256] = {
+		let mut table = TABLE;
+
+		table[Opcode::ADD.as_usize()] = self::prevalidated::add as _;
+		table[Opcode::SUB.as_usize()] = self::prevalidated::sub as _;
+		table[Opcode::LT.as_usize()] = self::prevalidated::lt as _;
+		table[Opcode::GT.as_usize()] = self::prevalidated::gt as _;
+		table[Opcode::EQ.as_usize()] = self::prevalidated::eq as _;
+		table[Opcode::ISZERO.as_usize()] = self::prevalidated::iszero as _;
+		table[Opcode::AND.as_usize()] = self::prevalidated::and as _;
+		table[Opcode::OR.as_usize()] = self::prevalidated::or as _;
+		table[Opcode::XOR.as_usize()] = self::prevalidated::xor as _;
+		table[Opcode::NOT.as_usize()] = self::prevalidated::not as _;
+		table[Opcode::POP.as_usize()] = self::prevalidated::pop as _;
+
+		let mut opcode = Opcode::PUSH1.as_usize();
+		while opcode <= Opcode::PUSH32.as_usize() {
+			table[opcode] = self::prevalidated::push as _;
+			opcode += 1;
+		}
+		let mut opcode = Opcode::DUP1.as_usize();
+		while opcode <= Opcode::DUP16.as_usize() {
+			table[opcode] = self::prevalidated::dup as _;
+			opcode += 1;
+		}
+		let mut opcode = Opcode::SWAP1.as_usize();
+		while opcode <= Opcode::SWAP16.as_usize() {
+			table[opcode] = self::prevalidated::swap as _;
+			opcode += 1;
+		}
 
 		table
 	};
 
-	TABLE[opcode.as_usize()](state, opcode, position)
+	PREVALIDATED[opcode.as_usize()](state, opcode, position)
 }
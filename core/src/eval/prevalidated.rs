@@ -0,0 +1,105 @@
+//! Instructions of a static block entered with a stack it fits, whose
+//! stack accesses are not checked again, see `Machine::step_prevalidated`.
+
+use super::Control;
+use crate::{Machine, Opcode};
+use core::ops::{BitAnd, BitOr, BitXor};
+use primitive_types::{H256, U256};
+
+macro_rules! pop_u256 {
+	( $machine:expr, $( $x:ident ),* ) => (
+		$(
+			let $x = U256::from_big_endian(&$machine.stack.pop_unchecked()[..]);
+		)*
+	);
+}
+
+macro_rules! push_u256 {
+	( $machine:expr, $x:expr ) => {{
+		let mut value = H256::default();
+		$x.to_big_endian(&mut value[..]);
+		$machine.stack.push_unchecked(value);
+	}};
+}
+
+macro_rules! op2_u256 {
+	( $machine:expr, $op1:ident, $op2:ident, $ret:expr ) => {{
+		pop_u256!($machine, $op1, $op2);
+		push_u256!($machine, $ret);
+
+		Control::Continue(1)
+	}};
+}
+
+pub fn add(state: &mut Machine, _opcode: Opcode, _position: usize) -> Control {
+	op2_u256!(state, op1, op2, op1.overflowing_add(op2).0)
+}
+
+pub fn sub(state: &mut Machine, _opcode: Opcode, _position: usize) -> Control {
+	op2_u256!(state, op1, op2, op1.overflowing_sub(op2).0)
+}
+
+pub fn lt(state: &mut Machine, _opcode: Opcode, _position: usize) -> Control {
+	op2_u256!(state, op1, op2, U256::from((op1 < op2) as u8))
+}
+
+pub fn gt(state: &mut Machine, _opcode: Opcode, _position: usize) -> Control {
+	op2_u256!(state, op1, op2, U256::from((op1 > op2) as u8))
+}
+
+pub fn eq(state: &mut Machine, _opcode: Opcode, _position: usize) -> Control {
+	op2_u256!(state, op1, op2, U256::from((op1 == op2) as u8))
+}
+
+pub fn and(state: &mut Machine, _opcode: Opcode, _position: usize) -> Control {
+	op2_u256!(state, op1, op2, op1.bitand(op2))
+}
+
+pub fn or(state: &mut Machine, _opcode: Opcode, _position: usize) -> Control {
+	op2_u256!(state, op1, op2, op1.bitor(op2))
+}
+
+pub fn xor(state: &mut Machine, _opcode: Opcode, _position: usize) -> Control {
+	op2_u256!(state, op1, op2, op1.bitxor(op2))
+}
+
+pub fn iszero(state: &mut Machine, _opcode: Opcode, _position: usize) -> Control {
+	pop_u256!(state, op1);
+	push_u256!(state, U256::from(op1.is_zero() as u8));
+	Control::Continue(1)
+}
+
+pub fn not(state: &mut Machine, _opcode: Opcode, _position: usize) -> Control {
+	pop_u256!(state, op1);
+	push_u256!(state, !op1);
+	Control::Continue(1)
+}
+
+pub fn pop(state: &mut Machine, _opcode: Opcode, _position: usize) -> Control {
+	state.stack.pop_unchecked();
+	Control::Continue(1)
+}
+
+pub fn push(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
+	let n = (opcode.0 - Opcode::PUSH0.0) as usize;
+	let end = core::cmp::min(position + 1 + n, state.code.len());
+	let slice = &state.code[(position + 1)..end];
+	let mut value = [0u8; 32];
+	value[(32 - slice.len())..32].copy_from_slice(slice);
+
+	state.stack.push_unchecked(H256(value));
+	Control::Continue(1 + n)
+}
+
+pub fn dup(state: &mut Machine, opcode: Opcode, _position: usize) -> Control {
+	let n = (opcode.0 - Opcode::DUP1.0) as usize;
+	let value = state.stack.peek_unchecked(n);
+	state.stack.push_unchecked(value);
+	Control::Continue(1)
+}
+
+pub fn swap(state: &mut Machine, opcode: Opcode, _position: usize) -> Control {
+	let n = (opcode.0 - Opcode::SWAP1.0) as usize + 1;
+	state.stack.swap_unchecked(n);
+	Control::Continue(1)
+}
@@ -7,6 +7,8 @@
 extern crate alloc;
 extern crate core;
 
+mod asm;
+mod eof;
 mod error;
 mod eval;
 mod memory;
@@ -15,17 +17,21 @@ mod stack;
 mod utils;
 mod valids;
 
+pub use crate::asm::{assemble, disassemble, AssembleError, Disassembler, Instruction};
+pub use crate::eof::{
+	EofContainer, EofError, EofTypeSection, EOF_MAGIC, EOF_VERSION, RETURN_STACK_LIMIT,
+};
 pub use crate::error::{Capture, ExitError, ExitFatal, ExitReason, ExitRevert, ExitSucceed, Trap};
 pub use crate::memory::Memory;
-pub use crate::opcode::Opcode;
+pub use crate::opcode::{Fork, GasTier, Opcode, OpcodeInfo, ParseOpcodeError};
 pub use crate::stack::Stack;
 pub use crate::valids::Valids;
 
-use crate::eval::{eval, Control};
+use crate::eval::{eval, eval_prevalidated, Control};
 use alloc::rc::Rc;
 use alloc::vec::Vec;
 use core::ops::Range;
-use primitive_types::U256;
+use primitive_types::{H256, U256};
 
 /// Core execution layer for EVM.
 pub struct Machine {
@@ -38,11 +44,53 @@ pub struct Machine {
 	/// Return value.
 	return_range: Range<U256>,
 	/// Code validity maps.
-	valids: Valids,
+	valids: Rc<Valids>,
 	/// Memory.
 	memory: Memory,
 	/// Stack.
 	stack: Stack,
+	/// EOF container layout, if the code is an EOF container.
+	eof: Option<Rc<EofContainer>>,
+	/// Currently executing EOF code section.
+	code_section: usize,
+	/// EOF return stack, of calling code section and return position.
+	return_stack: Vec<(usize, usize)>,
+}
+
+/// Snapshot of a machine.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "with-codec",
+	derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MachineSnapshot {
+	/// Program data.
+	pub data: Vec<u8>,
+	/// Program code.
+	pub code: Vec<u8>,
+	/// Program counter, or the exit reason.
+	pub position: Result<u64, ExitReason>,
+	/// Start of the return value range.
+	pub return_range_start: U256,
+	/// End of the return value range.
+	pub return_range_end: U256,
+	/// Memory data.
+	pub memory: Vec<u8>,
+	/// Effective memory length.
+	pub memory_effective_len: U256,
+	/// Memory limit.
+	pub memory_limit: u64,
+	/// Stack data.
+	pub stack: Vec<H256>,
+	/// Stack limit.
+	pub stack_limit: u64,
+	/// Whether the code is an EOF container.
+	pub eof: bool,
+	/// Currently executing EOF code section.
+	pub code_section: u64,
+	/// EOF return stack, of calling code section and return position.
+	pub return_stack: Vec<(u64, u64)>,
 }
 
 impl Machine {
@@ -66,6 +114,14 @@ impl Machine {
 	pub fn position(&self) -> &Result<usize, ExitReason> {
 		&self.position
 	}
+	/// EOF container layout, if the code is an EOF container.
+	pub fn eof(&self) -> Option<&EofContainer> {
+		self.eof.as_deref()
+	}
+	/// Currently executing EOF code section.
+	pub fn code_section(&self) -> usize {
+		self.code_section
+	}
 
 	/// Create a new machine with given code and data.
 	pub fn new(
@@ -74,8 +130,19 @@ impl Machine {
 		stack_limit: usize,
 		memory_limit: usize,
 	) -> Self {
-		let valids = Valids::new(&code[..]);
+		let valids = Rc::new(Valids::new(&code[..]));
+		Self::new_with_valids(code, valids, data, stack_limit, memory_limit)
+	}
 
+	/// Create a new machine with given code, its valid jump destinations
+	/// computed beforehand, and data.
+	pub fn new_with_valids(
+		code: Rc<Vec<u8>>,
+		valids: Rc<Valids>,
+		data: Rc<Vec<u8>>,
+		stack_limit: usize,
+		memory_limit: usize,
+	) -> Self {
 		Self {
 			data,
 			code,
@@ -84,9 +151,113 @@ impl Machine {
 			valids,
 			memory: Memory::new(memory_limit),
 			stack: Stack::new(stack_limit),
+			eof: None,
+			code_section: 0,
+			return_stack: Vec::new(),
 		}
 	}
 
+	/// Create a new machine with an EOF container as code. Execution starts
+	/// at the first code section. Only the header of the container is
+	/// parsed, its code is validated once when it is deployed. The machine
+	/// exits with `InvalidCode` if the header is not valid.
+	pub fn new_eof(
+		code: Rc<Vec<u8>>,
+		data: Rc<Vec<u8>>,
+		stack_limit: usize,
+		memory_limit: usize,
+	) -> Self {
+		let eof = EofContainer::parse(&code[..]).ok();
+		let position = match &eof {
+			Some(eof) => Ok(eof.code[0].start),
+			None => Err(ExitError::InvalidCode.into()),
+		};
+
+		Self {
+			data,
+			code,
+			position,
+			return_range: U256::zero()..U256::zero(),
+			valids: Rc::new(Valids::new(&[])),
+			memory: Memory::new(memory_limit),
+			stack: Stack::new(stack_limit),
+			eof: eof.map(Rc::new),
+			code_section: 0,
+			return_stack: Vec::new(),
+		}
+	}
+
+	/// Take a snapshot of the machine, which can be stored and turned back
+	/// into a machine with `from_snapshot`.
+	pub fn snapshot(&self) -> MachineSnapshot {
+		MachineSnapshot {
+			data: self.data.as_ref().clone(),
+			code: self.code.as_ref().clone(),
+			position: self.position.clone().map(|p| p as u64),
+			return_range_start: self.return_range.start,
+			return_range_end: self.return_range.end,
+			memory: self.memory.data().clone(),
+			memory_effective_len: self.memory.effective_len(),
+			memory_limit: self.memory.limit() as u64,
+			stack: self.stack.data().clone(),
+			stack_limit: self.stack.limit() as u64,
+			eof: self.eof.is_some(),
+			code_section: self.code_section as u64,
+			return_stack: self
+				.return_stack
+				.iter()
+				.map(|(section, position)| (*section as u64, *position as u64))
+				.collect(),
+		}
+	}
+
+	/// Restore a machine from a snapshot. As in `new_eof`, only the header of
+	/// an EOF container is parsed, and its code sections must match the ones
+	/// the snapshot is executing.
+	pub fn from_snapshot(snapshot: MachineSnapshot) -> Result<Self, EofError> {
+		let eof = if snapshot.eof {
+			let eof = EofContainer::parse(&snapshot.code[..])?;
+			let sections = eof.code.len() as u64;
+			if snapshot.code_section >= sections
+				|| snapshot
+					.return_stack
+					.iter()
+					.any(|(section, _)| *section >= sections)
+			{
+				return Err(EofError::InvalidCodeSection);
+			}
+			Some(Rc::new(eof))
+		} else {
+			None
+		};
+		let valids = if eof.is_some() {
+			Valids::new(&[])
+		} else {
+			Valids::new(&snapshot.code[..])
+		};
+
+		Ok(Self {
+			data: Rc::new(snapshot.data),
+			code: Rc::new(snapshot.code),
+			position: snapshot.position.map(|p| p as usize),
+			return_range: snapshot.return_range_start..snapshot.return_range_end,
+			valids: Rc::new(valids),
+			memory: Memory::from_parts(
+				snapshot.memory,
+				snapshot.memory_effective_len,
+				snapshot.memory_limit as usize,
+			),
+			stack: Stack::from_parts(snapshot.stack, snapshot.stack_limit as usize),
+			eof,
+			code_section: snapshot.code_section as usize,
+			return_stack: snapshot
+				.return_stack
+				.into_iter()
+				.map(|(section, position)| (section as usize, position as usize))
+				.collect(),
+		})
+	}
+
 	/// Explicit exit of the machine. Further step will return error.
 	pub fn exit(&mut self, reason: ExitReason) {
 		self.position = Err(reason);
@@ -140,6 +311,23 @@ impl Machine {
 	#[inline]
 	/// Step the machine, executing one opcode. It then returns.
 	pub fn step(&mut self) -> Result<(), Capture<ExitReason, Trap>> {
+		self.step_with(eval)
+	}
+
+	#[inline]
+	/// Step the machine like `step`, for an instruction of a static block
+	/// that was entered with a stack it fits. The stack accesses of the
+	/// instruction are not checked again, and panic if they are out of
+	/// bounds.
+	pub fn step_prevalidated(&mut self) -> Result<(), Capture<ExitReason, Trap>> {
+		self.step_with(eval_prevalidated)
+	}
+
+	#[inline]
+	fn step_with(
+		&mut self,
+		eval: fn(&mut Machine, Opcode, usize) -> Control,
+	) -> Result<(), Capture<ExitReason, Trap>> {
 		let position = *self
 			.position
 			.as_ref()
@@ -23,6 +23,15 @@ impl Memory {
 		}
 	}
 
+	/// Create a memory from its raw parts.
+	pub(crate) fn from_parts(data: Vec<u8>, effective_len: U256, limit: usize) -> Self {
+		Self {
+			data,
+			effective_len,
+			limit,
+		}
+	}
+
 	/// Memory limit.
 	pub fn limit(&self) -> usize {
 		self.limit
@@ -1,5 +1,8 @@
+use core::fmt;
+use core::str::FromStr;
+
 /// Opcode enum. One-to-one corresponding to an `u8` value.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Eq, PartialEq)]
 pub struct Opcode(pub u8);
 
 // Core opcodes.
@@ -88,6 +91,11 @@ impl Opcode {
 	pub const MSIZE: Opcode = Opcode(0x59);
 	/// `JUMPDEST`
 	pub const JUMPDEST: Opcode = Opcode(0x5b);
+	/// `MCOPY`
+	pub const MCOPY: Opcode = Opcode(0x5e);
+
+	/// `PUSH0`
+	pub const PUSH0: Opcode = Opcode(0x5f);
 
 	/// `PUSHn`
 	pub const PUSH1: Opcode = Opcode(0x60);
@@ -159,6 +167,17 @@ impl Opcode {
 	pub const SWAP15: Opcode = Opcode(0x9e);
 	pub const SWAP16: Opcode = Opcode(0x9f);
 
+	/// `RJUMP`
+	pub const RJUMP: Opcode = Opcode(0xe0);
+	/// `RJUMPI`
+	pub const RJUMPI: Opcode = Opcode(0xe1);
+	/// `RJUMPV`
+	pub const RJUMPV: Opcode = Opcode(0xe2);
+	/// `CALLF`
+	pub const CALLF: Opcode = Opcode(0xe3);
+	/// `RETF`
+	pub const RETF: Opcode = Opcode(0xe4);
+
 	/// `RETURN`
 	pub const RETURN: Opcode = Opcode(0xf3);
 	/// `REVERT`
@@ -208,12 +227,18 @@ impl Opcode {
 	pub const NUMBER: Opcode = Opcode(0x43);
 	/// `DIFFICULTY`
 	pub const DIFFICULTY: Opcode = Opcode(0x44);
+	/// `PREVRANDAO`, which replaces `DIFFICULTY` after the Merge.
+	pub const PREVRANDAO: Opcode = Opcode(0x44);
 	/// `GASLIMIT`
 	pub const GASLIMIT: Opcode = Opcode(0x45);
 	/// `SLOAD`
 	pub const SLOAD: Opcode = Opcode(0x54);
 	/// `SSTORE`
 	pub const SSTORE: Opcode = Opcode(0x55);
+	/// `TLOAD`
+	pub const TLOAD: Opcode = Opcode(0x5c);
+	/// `TSTORE`
+	pub const TSTORE: Opcode = Opcode(0x5d);
 	/// `GAS`
 	pub const GAS: Opcode = Opcode(0x5a);
 	/// `LOGn`
@@ -238,6 +263,10 @@ impl Opcode {
 	pub const SUICIDE: Opcode = Opcode(0xff);
 	/// `CHAINID`
 	pub const CHAINID: Opcode = Opcode(0x46);
+	/// `BLOBHASH`
+	pub const BLOBHASH: Opcode = Opcode(0x49);
+	/// `BLOBBASEFEE`
+	pub const BLOBBASEFEE: Opcode = Opcode(0x4a);
 }
 
 impl Opcode {
@@ -261,3 +290,360 @@ impl Opcode {
 		self.0 as usize
 	}
 }
+
+/// Hard fork of the Ethereum mainnet, in chronological order, followed by
+/// the EVM Object Format which is not part of any of them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(
+	feature = "with-codec",
+	derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fork {
+	Frontier,
+	Homestead,
+	Byzantium,
+	Constantinople,
+	Istanbul,
+	Berlin,
+	London,
+	Merge,
+	Shanghai,
+	Cancun,
+	/// EVM Object Format, enabled by `Config::cancun_with_eof`.
+	Eof,
+}
+
+/// Tier of the static gas cost of an opcode.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GasTier {
+	/// 0 gas.
+	Zero,
+	/// 2 gas.
+	Base,
+	/// 3 gas.
+	VeryLow,
+	/// 5 gas.
+	Low,
+	/// 8 gas.
+	Mid,
+	/// 10 gas.
+	High,
+	/// Cost depending on the fork, the operands, the memory or the state,
+	/// computed by the gasometer.
+	Special,
+}
+
+impl GasTier {
+	/// Gas cost of the tier, `None` for `Special`.
+	pub const fn cost(&self) -> Option<u64> {
+		match self {
+			GasTier::Zero => Some(0),
+			GasTier::Base => Some(2),
+			GasTier::VeryLow => Some(3),
+			GasTier::Low => Some(5),
+			GasTier::Mid => Some(8),
+			GasTier::High => Some(10),
+			GasTier::Special => None,
+		}
+	}
+}
+
+/// Static information about an opcode.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OpcodeInfo {
+	/// Mnemonic.
+	pub name: &'static str,
+	/// Size in bytes of the immediate arguments following the opcode. For
+	/// `RJUMPV`, only the size of its jump table length.
+	pub immediate_size: u8,
+	/// Stack items taken. `CALLF` and `RETF` take the ones given by the
+	/// types of their code sections.
+	pub inputs: u8,
+	/// Stack items returned.
+	pub outputs: u8,
+	/// Tier of the static gas cost.
+	pub gas: GasTier,
+	/// Fork the opcode is introduced in.
+	pub fork: Fork,
+}
+
+const fn info(
+	name: &'static str,
+	immediate_size: u8,
+	inputs: u8,
+	outputs: u8,
+	gas: GasTier,
+	fork: Fork,
+) -> Option<OpcodeInfo> {
+	Some(OpcodeInfo {
+		name,
+		immediate_size,
+		inputs,
+		outputs,
+		gas,
+		fork,
+	})
+}
+
+static INFOS: [Option<OpcodeInfo>; 256] = {
+	let mut table = [None; 256];
+
+	table[Opcode::STOP.as_usize()] = info("STOP", 0, 0, 0, GasTier::Zero, Fork::Frontier);
+	table[Opcode::ADD.as_usize()] = info("ADD", 0, 2, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::MUL.as_usize()] = info("MUL", 0, 2, 1, GasTier::Low, Fork::Frontier);
+	table[Opcode::SUB.as_usize()] = info("SUB", 0, 2, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::DIV.as_usize()] = info("DIV", 0, 2, 1, GasTier::Low, Fork::Frontier);
+	table[Opcode::SDIV.as_usize()] = info("SDIV", 0, 2, 1, GasTier::Low, Fork::Frontier);
+	table[Opcode::MOD.as_usize()] = info("MOD", 0, 2, 1, GasTier::Low, Fork::Frontier);
+	table[Opcode::SMOD.as_usize()] = info("SMOD", 0, 2, 1, GasTier::Low, Fork::Frontier);
+	table[Opcode::ADDMOD.as_usize()] = info("ADDMOD", 0, 3, 1, GasTier::Mid, Fork::Frontier);
+	table[Opcode::MULMOD.as_usize()] = info("MULMOD", 0, 3, 1, GasTier::Mid, Fork::Frontier);
+	table[Opcode::EXP.as_usize()] = info("EXP", 0, 2, 1, GasTier::Special, Fork::Frontier);
+	table[Opcode::SIGNEXTEND.as_usize()] =
+		info("SIGNEXTEND", 0, 2, 1, GasTier::Low, Fork::Frontier);
+
+	table[Opcode::LT.as_usize()] = info("LT", 0, 2, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::GT.as_usize()] = info("GT", 0, 2, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::SLT.as_usize()] = info("SLT", 0, 2, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::SGT.as_usize()] = info("SGT", 0, 2, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::EQ.as_usize()] = info("EQ", 0, 2, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::ISZERO.as_usize()] = info("ISZERO", 0, 1, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::AND.as_usize()] = info("AND", 0, 2, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::OR.as_usize()] = info("OR", 0, 2, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::XOR.as_usize()] = info("XOR", 0, 2, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::NOT.as_usize()] = info("NOT", 0, 1, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::BYTE.as_usize()] = info("BYTE", 0, 2, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::SHL.as_usize()] = info("SHL", 0, 2, 1, GasTier::VeryLow, Fork::Constantinople);
+	table[Opcode::SHR.as_usize()] = info("SHR", 0, 2, 1, GasTier::VeryLow, Fork::Constantinople);
+	table[Opcode::SAR.as_usize()] = info("SAR", 0, 2, 1, GasTier::VeryLow, Fork::Constantinople);
+
+	table[Opcode::SHA3.as_usize()] = info("KECCAK256", 0, 2, 1, GasTier::Special, Fork::Frontier);
+
+	table[Opcode::ADDRESS.as_usize()] = info("ADDRESS", 0, 0, 1, GasTier::Base, Fork::Frontier);
+	table[Opcode::BALANCE.as_usize()] = info("BALANCE", 0, 1, 1, GasTier::Special, Fork::Frontier);
+	table[Opcode::ORIGIN.as_usize()] = info("ORIGIN", 0, 0, 1, GasTier::Base, Fork::Frontier);
+	table[Opcode::CALLER.as_usize()] = info("CALLER", 0, 0, 1, GasTier::Base, Fork::Frontier);
+	table[Opcode::CALLVALUE.as_usize()] = info("CALLVALUE", 0, 0, 1, GasTier::Base, Fork::Frontier);
+	table[Opcode::CALLDATALOAD.as_usize()] =
+		info("CALLDATALOAD", 0, 1, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::CALLDATASIZE.as_usize()] =
+		info("CALLDATASIZE", 0, 0, 1, GasTier::Base, Fork::Frontier);
+	table[Opcode::CALLDATACOPY.as_usize()] =
+		info("CALLDATACOPY", 0, 3, 0, GasTier::Special, Fork::Frontier);
+	table[Opcode::CODESIZE.as_usize()] = info("CODESIZE", 0, 0, 1, GasTier::Base, Fork::Frontier);
+	table[Opcode::CODECOPY.as_usize()] =
+		info("CODECOPY", 0, 3, 0, GasTier::Special, Fork::Frontier);
+	table[Opcode::GASPRICE.as_usize()] = info("GASPRICE", 0, 0, 1, GasTier::Base, Fork::Frontier);
+	table[Opcode::EXTCODESIZE.as_usize()] =
+		info("EXTCODESIZE", 0, 1, 1, GasTier::Special, Fork::Frontier);
+	table[Opcode::EXTCODECOPY.as_usize()] =
+		info("EXTCODECOPY", 0, 4, 0, GasTier::Special, Fork::Frontier);
+	table[Opcode::RETURNDATASIZE.as_usize()] =
+		info("RETURNDATASIZE", 0, 0, 1, GasTier::Base, Fork::Byzantium);
+	table[Opcode::RETURNDATACOPY.as_usize()] =
+		info("RETURNDATACOPY", 0, 3, 0, GasTier::Special, Fork::Byzantium);
+	table[Opcode::EXTCODEHASH.as_usize()] = info(
+		"EXTCODEHASH",
+		0,
+		1,
+		1,
+		GasTier::Special,
+		Fork::Constantinople,
+	);
+
+	table[Opcode::BLOCKHASH.as_usize()] =
+		info("BLOCKHASH", 0, 1, 1, GasTier::Special, Fork::Frontier);
+	table[Opcode::COINBASE.as_usize()] = info("COINBASE", 0, 0, 1, GasTier::Base, Fork::Frontier);
+	table[Opcode::TIMESTAMP.as_usize()] = info("TIMESTAMP", 0, 0, 1, GasTier::Base, Fork::Frontier);
+	table[Opcode::NUMBER.as_usize()] = info("NUMBER", 0, 0, 1, GasTier::Base, Fork::Frontier);
+	table[Opcode::DIFFICULTY.as_usize()] =
+		info("DIFFICULTY", 0, 0, 1, GasTier::Base, Fork::Frontier);
+	table[Opcode::GASLIMIT.as_usize()] = info("GASLIMIT", 0, 0, 1, GasTier::Base, Fork::Frontier);
+	table[Opcode::CHAINID.as_usize()] = info("CHAINID", 0, 0, 1, GasTier::Base, Fork::Istanbul);
+	table[Opcode::SELFBALANCE.as_usize()] =
+		info("SELFBALANCE", 0, 0, 1, GasTier::Low, Fork::Istanbul);
+	table[Opcode::BASEFEE.as_usize()] = info("BASEFEE", 0, 0, 1, GasTier::Base, Fork::London);
+	table[Opcode::BLOBHASH.as_usize()] = info("BLOBHASH", 0, 1, 1, GasTier::VeryLow, Fork::Cancun);
+	table[Opcode::BLOBBASEFEE.as_usize()] =
+		info("BLOBBASEFEE", 0, 0, 1, GasTier::Base, Fork::Cancun);
+
+	table[Opcode::POP.as_usize()] = info("POP", 0, 1, 0, GasTier::Base, Fork::Frontier);
+	table[Opcode::MLOAD.as_usize()] = info("MLOAD", 0, 1, 1, GasTier::Special, Fork::Frontier);
+	table[Opcode::MSTORE.as_usize()] = info("MSTORE", 0, 2, 0, GasTier::Special, Fork::Frontier);
+	table[Opcode::MSTORE8.as_usize()] = info("MSTORE8", 0, 2, 0, GasTier::Special, Fork::Frontier);
+	table[Opcode::SLOAD.as_usize()] = info("SLOAD", 0, 1, 1, GasTier::Special, Fork::Frontier);
+	table[Opcode::SSTORE.as_usize()] = info("SSTORE", 0, 2, 0, GasTier::Special, Fork::Frontier);
+	table[Opcode::JUMP.as_usize()] = info("JUMP", 0, 1, 0, GasTier::Mid, Fork::Frontier);
+	table[Opcode::JUMPI.as_usize()] = info("JUMPI", 0, 2, 0, GasTier::High, Fork::Frontier);
+	table[Opcode::PC.as_usize()] = info("PC", 0, 0, 1, GasTier::Base, Fork::Frontier);
+	table[Opcode::MSIZE.as_usize()] = info("MSIZE", 0, 0, 1, GasTier::Base, Fork::Frontier);
+	table[Opcode::GAS.as_usize()] = info("GAS", 0, 0, 1, GasTier::Base, Fork::Frontier);
+	table[Opcode::JUMPDEST.as_usize()] =
+		info("JUMPDEST", 0, 0, 0, GasTier::Special, Fork::Frontier);
+	table[Opcode::TLOAD.as_usize()] = info("TLOAD", 0, 1, 1, GasTier::Special, Fork::Cancun);
+	table[Opcode::TSTORE.as_usize()] = info("TSTORE", 0, 2, 0, GasTier::Special, Fork::Cancun);
+	table[Opcode::MCOPY.as_usize()] = info("MCOPY", 0, 3, 0, GasTier::Special, Fork::Cancun);
+	table[Opcode::PUSH0.as_usize()] = info("PUSH0", 0, 0, 1, GasTier::Base, Fork::Shanghai);
+
+	table[Opcode::PUSH1.as_usize()] = info("PUSH1", 1, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH2.as_usize()] = info("PUSH2", 2, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH3.as_usize()] = info("PUSH3", 3, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH4.as_usize()] = info("PUSH4", 4, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH5.as_usize()] = info("PUSH5", 5, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH6.as_usize()] = info("PUSH6", 6, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH7.as_usize()] = info("PUSH7", 7, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH8.as_usize()] = info("PUSH8", 8, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH9.as_usize()] = info("PUSH9", 9, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH10.as_usize()] = info("PUSH10", 10, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH11.as_usize()] = info("PUSH11", 11, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH12.as_usize()] = info("PUSH12", 12, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH13.as_usize()] = info("PUSH13", 13, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH14.as_usize()] = info("PUSH14", 14, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH15.as_usize()] = info("PUSH15", 15, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH16.as_usize()] = info("PUSH16", 16, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH17.as_usize()] = info("PUSH17", 17, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH18.as_usize()] = info("PUSH18", 18, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH19.as_usize()] = info("PUSH19", 19, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH20.as_usize()] = info("PUSH20", 20, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH21.as_usize()] = info("PUSH21", 21, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH22.as_usize()] = info("PUSH22", 22, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH23.as_usize()] = info("PUSH23", 23, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH24.as_usize()] = info("PUSH24", 24, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH25.as_usize()] = info("PUSH25", 25, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH26.as_usize()] = info("PUSH26", 26, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH27.as_usize()] = info("PUSH27", 27, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH28.as_usize()] = info("PUSH28", 28, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH29.as_usize()] = info("PUSH29", 29, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH30.as_usize()] = info("PUSH30", 30, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH31.as_usize()] = info("PUSH31", 31, 0, 1, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::PUSH32.as_usize()] = info("PUSH32", 32, 0, 1, GasTier::VeryLow, Fork::Frontier);
+
+	table[Opcode::DUP1.as_usize()] = info("DUP1", 0, 1, 2, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::DUP2.as_usize()] = info("DUP2", 0, 2, 3, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::DUP3.as_usize()] = info("DUP3", 0, 3, 4, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::DUP4.as_usize()] = info("DUP4", 0, 4, 5, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::DUP5.as_usize()] = info("DUP5", 0, 5, 6, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::DUP6.as_usize()] = info("DUP6", 0, 6, 7, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::DUP7.as_usize()] = info("DUP7", 0, 7, 8, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::DUP8.as_usize()] = info("DUP8", 0, 8, 9, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::DUP9.as_usize()] = info("DUP9", 0, 9, 10, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::DUP10.as_usize()] = info("DUP10", 0, 10, 11, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::DUP11.as_usize()] = info("DUP11", 0, 11, 12, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::DUP12.as_usize()] = info("DUP12", 0, 12, 13, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::DUP13.as_usize()] = info("DUP13", 0, 13, 14, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::DUP14.as_usize()] = info("DUP14", 0, 14, 15, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::DUP15.as_usize()] = info("DUP15", 0, 15, 16, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::DUP16.as_usize()] = info("DUP16", 0, 16, 17, GasTier::VeryLow, Fork::Frontier);
+
+	table[Opcode::SWAP1.as_usize()] = info("SWAP1", 0, 2, 2, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::SWAP2.as_usize()] = info("SWAP2", 0, 3, 3, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::SWAP3.as_usize()] = info("SWAP3", 0, 4, 4, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::SWAP4.as_usize()] = info("SWAP4", 0, 5, 5, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::SWAP5.as_usize()] = info("SWAP5", 0, 6, 6, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::SWAP6.as_usize()] = info("SWAP6", 0, 7, 7, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::SWAP7.as_usize()] = info("SWAP7", 0, 8, 8, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::SWAP8.as_usize()] = info("SWAP8", 0, 9, 9, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::SWAP9.as_usize()] = info("SWAP9", 0, 10, 10, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::SWAP10.as_usize()] = info("SWAP10", 0, 11, 11, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::SWAP11.as_usize()] = info("SWAP11", 0, 12, 12, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::SWAP12.as_usize()] = info("SWAP12", 0, 13, 13, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::SWAP13.as_usize()] = info("SWAP13", 0, 14, 14, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::SWAP14.as_usize()] = info("SWAP14", 0, 15, 15, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::SWAP15.as_usize()] = info("SWAP15", 0, 16, 16, GasTier::VeryLow, Fork::Frontier);
+	table[Opcode::SWAP16.as_usize()] = info("SWAP16", 0, 17, 17, GasTier::VeryLow, Fork::Frontier);
+
+	table[Opcode::LOG0.as_usize()] = info("LOG0", 0, 2, 0, GasTier::Special, Fork::Frontier);
+	table[Opcode::LOG1.as_usize()] = info("LOG1", 0, 3, 0, GasTier::Special, Fork::Frontier);
+	table[Opcode::LOG2.as_usize()] = info("LOG2", 0, 4, 0, GasTier::Special, Fork::Frontier);
+	table[Opcode::LOG3.as_usize()] = info("LOG3", 0, 5, 0, GasTier::Special, Fork::Frontier);
+	table[Opcode::LOG4.as_usize()] = info("LOG4", 0, 6, 0, GasTier::Special, Fork::Frontier);
+
+	table[Opcode::RJUMP.as_usize()] = info("RJUMP", 2, 0, 0, GasTier::Base, Fork::Eof);
+	table[Opcode::RJUMPI.as_usize()] = info("RJUMPI", 2, 1, 0, GasTier::Special, Fork::Eof);
+	table[Opcode::RJUMPV.as_usize()] = info("RJUMPV", 1, 1, 0, GasTier::Special, Fork::Eof);
+	table[Opcode::CALLF.as_usize()] = info("CALLF", 2, 0, 0, GasTier::Low, Fork::Eof);
+	table[Opcode::RETF.as_usize()] = info("RETF", 0, 0, 0, GasTier::VeryLow, Fork::Eof);
+
+	table[Opcode::CREATE.as_usize()] = info("CREATE", 0, 3, 1, GasTier::Special, Fork::Frontier);
+	table[Opcode::CALL.as_usize()] = info("CALL", 0, 7, 1, GasTier::Special, Fork::Frontier);
+	table[Opcode::CALLCODE.as_usize()] =
+		info("CALLCODE", 0, 7, 1, GasTier::Special, Fork::Frontier);
+	table[Opcode::RETURN.as_usize()] = info("RETURN", 0, 2, 0, GasTier::Special, Fork::Frontier);
+	table[Opcode::DELEGATECALL.as_usize()] =
+		info("DELEGATECALL", 0, 6, 1, GasTier::Special, Fork::Homestead);
+	table[Opcode::CREATE2.as_usize()] =
+		info("CREATE2", 0, 4, 1, GasTier::Special, Fork::Constantinople);
+	table[Opcode::STATICCALL.as_usize()] =
+		info("STATICCALL", 0, 6, 1, GasTier::Special, Fork::Byzantium);
+	table[Opcode::REVERT.as_usize()] = info("REVERT", 0, 2, 0, GasTier::Special, Fork::Byzantium);
+	table[Opcode::INVALID.as_usize()] = info("INVALID", 0, 0, 0, GasTier::Special, Fork::Frontier);
+	table[Opcode::SUICIDE.as_usize()] =
+		info("SELFDESTRUCT", 0, 1, 0, GasTier::Special, Fork::Frontier);
+
+	table
+};
+
+impl Opcode {
+	/// Static information about the opcode, `None` if it is undefined.
+	#[inline]
+	pub fn info(&self) -> Option<&'static OpcodeInfo> {
+		INFOS[self.as_usize()].as_ref()
+	}
+
+	/// Mnemonic of the opcode, `None` if it is undefined.
+	#[inline]
+	pub fn name(&self) -> Option<&'static str> {
+		self.info().map(|info| info.name)
+	}
+}
+
+impl fmt::Debug for Opcode {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self.name() {
+			Some(name) => f.write_str(name),
+			None => write!(f, "Opcode({})", self.0),
+		}
+	}
+}
+
+/// Prints the mnemonic of the opcode, or its hexadecimal value if it is
+/// undefined.
+impl fmt::Display for Opcode {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self.name() {
+			Some(name) => f.write_str(name),
+			None => write!(f, "{:#04x}", self.0),
+		}
+	}
+}
+
+/// Error parsing an unknown opcode mnemonic.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseOpcodeError;
+
+/// Parses an opcode mnemonic, case-insensitively. The former names `SHA3`,
+/// `SUICIDE` and `PREVRANDAO` are accepted as well.
+impl FromStr for Opcode {
+	type Err = ParseOpcodeError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		if s.eq_ignore_ascii_case("SHA3") {
+			return Ok(Opcode::SHA3);
+		}
+		if s.eq_ignore_ascii_case("SUICIDE") {
+			return Ok(Opcode::SUICIDE);
+		}
+		if s.eq_ignore_ascii_case("PREVRANDAO") {
+			return Ok(Opcode::PREVRANDAO);
+		}
+
+		(0..=255u8)
+			.map(Opcode)
+			.find(|opcode| match opcode.name() {
+				Some(name) => name.eq_ignore_ascii_case(s),
+				None => false,
+			})
+			.ok_or(ParseOpcodeError)
+	}
+}
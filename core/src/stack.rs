@@ -18,6 +18,11 @@ impl Stack {
 		}
 	}
 
+	/// Create a stack from its raw parts.
+	pub(crate) fn from_parts(data: Vec<H256>, limit: usize) -> Self {
+		Self { data, limit }
+	}
+
 	#[inline]
 	/// Stack limit.
 	pub fn limit(&self) -> usize {
@@ -60,6 +65,37 @@ impl Stack {
 		Ok(())
 	}
 
+	#[inline]
+	/// Pop a value from a stack whose bounds are checked beforehand. Panics
+	/// if the stack is empty.
+	pub(crate) fn pop_unchecked(&mut self) -> H256 {
+		self.data
+			.pop()
+			.expect("stack bounds are checked beforehand")
+	}
+
+	#[inline]
+	/// Push a new value into a stack whose bounds are checked beforehand,
+	/// without comparing its length with the limit.
+	pub(crate) fn push_unchecked(&mut self, value: H256) {
+		self.data.push(value);
+	}
+
+	#[inline]
+	/// Peek a value of a stack whose bounds are checked beforehand. Panics
+	/// if the index is too large.
+	pub(crate) fn peek_unchecked(&self, no_from_top: usize) -> H256 {
+		self.data[self.data.len() - no_from_top - 1]
+	}
+
+	#[inline]
+	/// Swap the top value of a stack whose bounds are checked beforehand
+	/// with the one at given index. Panics if the index is too large.
+	pub(crate) fn swap_unchecked(&mut self, no_from_top: usize) {
+		let len = self.data.len();
+		self.data.swap(len - 1, len - no_from_top - 1);
+	}
+
 	#[inline]
 	/// Peek a value at given index for the stack, where the top of
 	/// the stack is at index `0`. If the index is too large,
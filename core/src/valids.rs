@@ -1,4 +1,4 @@
-use crate::Opcode;
+use crate::{disassemble, Opcode};
 use alloc::vec::Vec;
 
 /// Mapping of valid jump destination from code.
@@ -11,16 +11,9 @@ impl Valids {
 		let mut valids: Vec<bool> = Vec::with_capacity(code.len());
 		valids.resize(code.len(), false);
 
-		let mut i = 0;
-		while i < code.len() {
-			let opcode = Opcode(code[i]);
-			if opcode == Opcode::JUMPDEST {
-				valids[i] = true;
-				i += 1;
-			} else if let Some(v) = opcode.is_push() {
-				i += v as usize + 1;
-			} else {
-				i += 1;
+		for instruction in disassemble(code) {
+			if instruction.opcode == Opcode::JUMPDEST {
+				valids[instruction.offset] = true;
 			}
 		}
 
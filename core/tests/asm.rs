@@ -0,0 +1,79 @@
+use evm_core::{assemble, disassemble, AssembleError, Capture, ExitSucceed, Machine, Opcode};
+use std::rc::Rc;
+
+fn source(code: &[u8]) -> String {
+	disassemble(code)
+		.map(|instruction| instruction.to_string())
+		.collect::<Vec<_>>()
+		.join("\n")
+}
+
+#[test]
+fn disassembles_code() {
+	// PUSH2 0x0102, JUMPDEST, an undefined opcode, then a truncated PUSH3.
+	let code = hex::decode("6101025b0c620102").unwrap();
+	let instructions = disassemble(&code).collect::<Vec<_>>();
+
+	assert_eq!(instructions.len(), 4);
+	assert_eq!(instructions[0].opcode, Opcode::PUSH2);
+	assert_eq!(instructions[0].immediate, &[1, 2]);
+	assert_eq!(instructions[1].offset, 3);
+	assert!(instructions[3].is_truncated());
+	assert_eq!(
+		source(&code),
+		"PUSH2 0x0102\nJUMPDEST\n0x0c\n0x620102".to_string()
+	);
+}
+
+#[test]
+fn round_trips() {
+	// fibonacci(10), as in the performance tests.
+	let code = hex::decode("60e060020a6000350480632839e92814601e57806361047ff414603457005b602a6004356024356047565b8060005260206000f35b603d6004356099565b8060005260206000f35b600082600014605457605e565b8160010190506093565b81600014606957607b565b60756001840360016047565b90506093565b609060018403608c85600186036047565b6047565b90505b92915050565b6000816000148060a95750816001145b60b05760b7565b81905060cf565b60c1600283036099565b60cb600184036099565b0190505b91905056").unwrap();
+	assert_eq!(assemble(&source(&code)), Ok(code));
+
+	for value in 0..=255u8 {
+		let code = vec![value, 0xff];
+		assert_eq!(assemble(&source(&code)), Ok(code));
+	}
+}
+
+#[test]
+fn assembles_labels() {
+	// Sum of the numbers from 1 to 10.
+	let code = assemble(
+		"
+		PUSH1 0         ; sum
+		PUSH1 10        ; counter
+		loop:
+			DUP1 ISZERO PUSH @end JUMPI
+			SWAP1 DUP2 ADD SWAP1
+			PUSH1 1 SWAP1 SUB
+			PUSH @loop JUMP
+		end:
+			POP PUSH 0 MSTORE
+			PUSH 32 PUSH 0 RETURN
+		",
+	)
+	.unwrap();
+	assert_eq!(&code[..5], &hex::decode("6000600a5b").unwrap()[..]);
+
+	let mut vm = Machine::new(Rc::new(code), Rc::new(Vec::new()), 1024, 10000);
+	assert_eq!(vm.run(), Capture::Exit(ExitSucceed::Returned.into()));
+	assert_eq!(vm.return_value()[31], 55);
+}
+
+#[test]
+fn reports_errors() {
+	assert_eq!(assemble("ADD\nFOO"), Err(AssembleError::InvalidToken(2)));
+	assert_eq!(
+		assemble("PUSH1 0x0100"),
+		Err(AssembleError::InvalidImmediate(1))
+	);
+	assert_eq!(assemble("PUSH2"), Err(AssembleError::InvalidImmediate(1)));
+	assert_eq!(
+		assemble("PUSH @nowhere"),
+		Err(AssembleError::InvalidLabel(1))
+	);
+	assert_eq!(assemble("a:\na:"), Err(AssembleError::InvalidLabel(2)));
+	assert_eq!(assemble("0x123"), Err(AssembleError::InvalidToken(1)));
+}
@@ -0,0 +1,48 @@
+use evm_core::{Fork, GasTier, Opcode, ParseOpcodeError};
+
+#[test]
+fn names_round_trip() {
+	for value in 0..=255u8 {
+		let opcode = Opcode(value);
+		match opcode.info() {
+			Some(info) => {
+				assert_eq!(info.name.parse(), Ok(opcode));
+				assert_eq!(format!("{:?}", opcode), info.name);
+				if let Some(size) = opcode.is_push() {
+					assert_eq!(info.immediate_size, size);
+				}
+			}
+			None => assert_eq!(format!("{}", opcode), format!("{:#04x}", value)),
+		}
+	}
+
+	assert_eq!(format!("{:?}", Opcode::SSTORE), "SSTORE");
+	assert_eq!(format!("{:?}", Opcode(0x0c)), "Opcode(12)");
+	assert_eq!(Opcode::SHA3.to_string(), "KECCAK256");
+	assert_eq!("sha3".parse(), Ok(Opcode::SHA3));
+	assert_eq!("selfdestruct".parse(), Ok(Opcode::SUICIDE));
+	assert_eq!("PUSH33".parse::<Opcode>(), Err(ParseOpcodeError));
+}
+
+#[test]
+fn describes_opcodes() {
+	let info = Opcode::ADDMOD.info().unwrap();
+	assert_eq!((info.inputs, info.outputs), (3, 1));
+	assert_eq!(info.gas.cost(), Some(8));
+
+	let info = Opcode::PUSH32.info().unwrap();
+	assert_eq!(info.immediate_size, 32);
+
+	let info = Opcode::SWAP16.info().unwrap();
+	assert_eq!((info.inputs, info.outputs), (17, 17));
+
+	let info = Opcode::CALL.info().unwrap();
+	assert_eq!((info.inputs, info.outputs), (7, 1));
+	assert_eq!(info.gas, GasTier::Special);
+
+	assert_eq!(Opcode::DELEGATECALL.info().unwrap().fork, Fork::Homestead);
+	assert_eq!(Opcode::PUSH0.info().unwrap().fork, Fork::Shanghai);
+	assert_eq!(Opcode::MCOPY.info().unwrap().fork, Fork::Cancun);
+	assert!(Opcode::TSTORE.info().unwrap().fork > Fork::London);
+	assert!(Opcode(0xef).info().is_none());
+}
@@ -0,0 +1,34 @@
+use evm_core::{Capture, EofError, ExitSucceed, Machine};
+use std::rc::Rc;
+
+#[test]
+fn resume_from_snapshot() {
+	// fibonacci(10), as in the performance tests.
+	let code = hex::decode("60e060020a6000350480632839e92814601e57806361047ff414603457005b602a6004356024356047565b8060005260206000f35b603d6004356099565b8060005260206000f35b600082600014605457605e565b8160010190506093565b81600014606957607b565b60756001840360016047565b90506093565b609060018403608c85600186036047565b6047565b90505b92915050565b6000816000148060a95750816001145b60b05760b7565b81905060cf565b60c1600283036099565b60cb600184036099565b0190505b91905056").unwrap();
+	let data =
+		hex::decode("61047ff4000000000000000000000000000000000000000000000000000000000000000a")
+			.unwrap();
+
+	let mut vm = Machine::new(Rc::new(code), Rc::new(data), 1024, 10000);
+	for _ in 0..100 {
+		assert!(vm.step().is_ok());
+	}
+
+	let mut restored = Machine::from_snapshot(vm.snapshot()).unwrap();
+	assert_eq!(restored.run(), Capture::Exit(ExitSucceed::Returned.into()));
+	assert_eq!(
+		restored.return_value(),
+		hex::decode("0000000000000000000000000000000000000000000000000000000000000037").unwrap()
+	);
+}
+
+#[test]
+fn rejects_invalid_eof_snapshot() {
+	let vm = Machine::new(Rc::new(vec![0x00]), Rc::new(Vec::new()), 1024, 10000);
+	let mut snapshot = vm.snapshot();
+	snapshot.eof = true;
+	assert_eq!(
+		Machine::from_snapshot(snapshot).err(),
+		Some(EofError::InvalidMagic)
+	);
+}
@@ -0,0 +1,250 @@
+use crate::static_opcode_cost;
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use evm_core::{disassemble, Opcode, Valids};
+use evm_runtime::{StaticBlock, StaticBlocks};
+
+/// Straight-line sequence of instructions, only entered at its first one and
+/// only left after its last one.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BasicBlock {
+	/// Offset of the first instruction.
+	pub start: usize,
+	/// Offset following the last instruction.
+	pub end: usize,
+	/// Sum of the static costs of the instructions.
+	pub static_gas: u64,
+	/// Whether an instruction has a cost only known when it is executed,
+	/// not included in `static_gas`.
+	pub has_dynamic_gas: bool,
+	/// Stack items the block takes from the stack on entry.
+	pub stack_inputs: usize,
+	/// Most items the block pushes above its entry stack height.
+	pub stack_growth: usize,
+	/// Change of the stack height after the block.
+	pub stack_delta: isize,
+	/// Indexes of the blocks that can follow this one, besides the targets of
+	/// a dynamic jump.
+	pub successors: Vec<usize>,
+	/// Whether the block ends with a jump whose target is not a constant
+	/// pushed just before it.
+	pub dynamic_jump: bool,
+	/// Whether the block can be reached from the start of the code.
+	pub reachable: bool,
+}
+
+/// Control-flow graph of legacy (non-EOF) code.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ControlFlowGraph {
+	/// Basic blocks, ordered by offset.
+	pub blocks: Vec<BasicBlock>,
+	/// Offsets of the jumps to a constant target which is not a `JUMPDEST`.
+	pub invalid_jumps: Vec<usize>,
+}
+
+/// Whether the opcode ends the execution.
+fn is_halt(opcode: Opcode) -> bool {
+	matches!(
+		opcode,
+		Opcode::STOP | Opcode::RETURN | Opcode::REVERT | Opcode::INVALID | Opcode::SUICIDE
+	) || opcode.info().is_none()
+}
+
+/// Big-endian value of a push immediate, saturated to `usize::MAX`.
+fn constant(immediate: &[u8]) -> usize {
+	immediate
+		.iter()
+		.try_fold(0usize, |value, byte| {
+			value.checked_mul(256).map(|value| value + *byte as usize)
+		})
+		.unwrap_or(usize::MAX)
+}
+
+impl ControlFlowGraph {
+	/// Split code into basic blocks and link them. A jump to a constant
+	/// pushed just before it is linked to its target, and other jumps can
+	/// go to any `JUMPDEST`.
+	pub fn new(code: &[u8]) -> Self {
+		let valids = Valids::new(code);
+		let instructions = disassemble(code).collect::<Vec<_>>();
+
+		let mut blocks = Vec::new();
+		let mut invalid_jumps = Vec::new();
+		// Targets of each block, as offsets.
+		let mut targets = Vec::new();
+
+		let mut index = 0;
+		while index < instructions.len() {
+			let mut block = BasicBlock {
+				start: instructions[index].offset,
+				end: instructions[index].offset,
+				static_gas: 0,
+				has_dynamic_gas: false,
+				stack_inputs: 0,
+				stack_growth: 0,
+				stack_delta: 0,
+				successors: Vec::new(),
+				dynamic_jump: false,
+				reachable: false,
+			};
+			let mut block_targets = Vec::new();
+			let mut height = 0isize;
+
+			loop {
+				let instruction = &instructions[index];
+				let opcode = instruction.opcode;
+				index += 1;
+				block.end = instruction.offset + 1 + instruction.immediate.len();
+
+				match static_opcode_cost(opcode) {
+					Some(cost) => block.static_gas = block.static_gas.saturating_add(cost),
+					None => block.has_dynamic_gas = true,
+				}
+				if let Some(info) = opcode.info() {
+					height -= info.inputs as isize;
+					block.stack_inputs = block.stack_inputs.max((-height).max(0) as usize);
+					height += info.outputs as isize;
+					block.stack_growth = block.stack_growth.max(height.max(0) as usize);
+				}
+
+				if opcode == Opcode::JUMP || opcode == Opcode::JUMPI {
+					let previous = index.checked_sub(2).map(|previous| &instructions[previous]);
+					let target = match previous {
+						Some(previous) if previous.offset >= block.start => {
+							if previous.opcode == Opcode::PUSH0 {
+								Some(0)
+							} else if previous.opcode.is_push().is_some()
+								&& !previous.is_truncated()
+							{
+								Some(constant(previous.immediate))
+							} else {
+								None
+							}
+						}
+						_ => None,
+					};
+					match target {
+						Some(target) if valids.is_valid(target) => block_targets.push(target),
+						Some(_) => invalid_jumps.push(instruction.offset),
+						None => block.dynamic_jump = true,
+					}
+					if opcode == Opcode::JUMPI && block.end < code.len() {
+						block_targets.push(block.end);
+					}
+					break;
+				}
+				if is_halt(opcode) {
+					break;
+				}
+				if index >= instructions.len() {
+					break;
+				}
+				if instructions[index].opcode == Opcode::JUMPDEST {
+					block_targets.push(instructions[index].offset);
+					break;
+				}
+			}
+
+			block.stack_delta = height;
+			blocks.push(block);
+			targets.push(block_targets);
+		}
+
+		for (i, block_targets) in targets.into_iter().enumerate() {
+			blocks[i].successors = block_targets
+				.into_iter()
+				.filter_map(|target| {
+					blocks
+						.binary_search_by_key(&target, |block| block.start)
+						.ok()
+				})
+				.collect();
+		}
+
+		// Blocks reachable from the start of the code, where a dynamic jump
+		// can go to any `JUMPDEST`.
+		let mut pending = Vec::new();
+		if !blocks.is_empty() {
+			pending.push(0);
+		}
+		let mut visited = BTreeSet::new();
+		let mut dynamic_jumps = false;
+		while let Some(i) = pending.pop() {
+			if !visited.insert(i) {
+				continue;
+			}
+			pending.extend(blocks[i].successors.iter().cloned());
+			if blocks[i].dynamic_jump && !dynamic_jumps {
+				dynamic_jumps = true;
+				pending.extend(
+					(0..blocks.len())
+						.filter(|j| code[blocks[*j].start] == Opcode::JUMPDEST.as_u8()),
+				);
+			}
+		}
+		for i in visited {
+			blocks[i].reachable = true;
+		}
+
+		Self {
+			blocks,
+			invalid_jumps,
+		}
+	}
+
+	/// Index of the block starting at an offset.
+	pub fn block_at(&self, offset: usize) -> Option<usize> {
+		self.blocks
+			.binary_search_by_key(&offset, |block| block.start)
+			.ok()
+	}
+
+	/// Blocks that can not be reached from the start of the code.
+	pub fn unreachable_blocks(&self) -> impl Iterator<Item = &BasicBlock> {
+		self.blocks.iter().filter(|block| !block.reachable)
+	}
+
+	/// Runs of at least two instructions with static costs inside the basic
+	/// blocks of `code`. `GAS` ends a run, as it reads the gas left.
+	pub fn static_blocks(&self, code: &[u8]) -> StaticBlocks {
+		let mut static_blocks = Vec::new();
+
+		for block in &self.blocks {
+			let mut current: Option<StaticBlock> = None;
+			let mut height = 0isize;
+
+			for instruction in disassemble(&code[block.start..block.end]) {
+				let opcode = instruction.opcode;
+				let cost = static_opcode_cost(opcode).filter(|_| opcode != Opcode::GAS);
+				let (cost, info) = match (cost, opcode.info()) {
+					(Some(cost), Some(info)) => (cost, info),
+					_ => {
+						static_blocks.extend(current.take().filter(|run| run.instructions > 1));
+						continue;
+					}
+				};
+
+				if current.is_none() {
+					height = 0;
+				}
+				let run = current.get_or_insert(StaticBlock {
+					start: block.start + instruction.offset,
+					instructions: 0,
+					gas: 0,
+					stack_inputs: 0,
+					stack_growth: 0,
+				});
+				run.instructions += 1;
+				run.gas = run.gas.saturating_add(cost);
+				height -= info.inputs as isize;
+				run.stack_inputs = run.stack_inputs.max((-height).max(0) as usize);
+				height += info.outputs as isize;
+				run.stack_growth = run.stack_growth.max(height.max(0) as usize);
+			}
+
+			static_blocks.extend(current.filter(|run| run.instructions > 1));
+		}
+
+		StaticBlocks::new(static_blocks)
+	}
+}
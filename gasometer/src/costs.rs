@@ -58,8 +58,30 @@ pub fn sstore_refund(original: H256, current: H256, new: H256, config: &Config)
 	}
 }
 
-pub fn create2_cost(len: U256) -> Result<u64, ExitError> {
-	let base = U256::from(G_CREATE);
+/// Cost of init code metering, failing if the init code is larger than the
+/// limit. See EIP-3860.
+fn initcode_cost(len: U256, config: &Config) -> Result<U256, ExitError> {
+	match config.max_initcode_size {
+		Some(limit) => {
+			if len > U256::from(limit) {
+				return Err(ExitError::CreateContractLimit);
+			}
+
+			let words = (len + U256::from(31)) / U256::from(32);
+			Ok(U256::from(G_INITCODE_WORD) * words)
+		}
+		None => Ok(U256::zero()),
+	}
+}
+
+pub fn create_cost(len: U256, config: &Config) -> Result<u64, ExitError> {
+	let gas = U256::from(G_CREATE) + initcode_cost(len, config)?;
+
+	Ok(gas.as_u64())
+}
+
+pub fn create2_cost(len: U256, config: &Config) -> Result<u64, ExitError> {
+	let base = U256::from(G_CREATE) + initcode_cost(len, config)?;
 	// ceil(len / 32.0)
 	let sha_addup_base = len / U256::from(32)
 		+ if len % U256::from(32) == U256::zero() {
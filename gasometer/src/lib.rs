@@ -22,11 +22,14 @@ macro_rules! event {
 	($x:expr) => {};
 }
 
+mod analysis;
 mod consts;
 mod costs;
 mod memory;
 mod utils;
 
+pub use crate::analysis::{BasicBlock, ControlFlowGraph};
+
 use alloc::vec::Vec;
 use core::cmp::max;
 use evm_core::{ExitError, Opcode, Stack};
@@ -45,8 +48,13 @@ macro_rules! try_or_fail {
 	};
 }
 
-#[cfg(feature = "tracing")]
-#[derive(Debug, Copy, Clone)]
+/// Snapshot of a gasometer.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "with-codec",
+	derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Snapshot {
 	pub gas_limit: u64,
 	pub memory_gas: u64,
@@ -238,9 +246,16 @@ impl<'config> Gasometer<'config> {
 				non_zero_data_len,
 				access_list_address_len,
 				access_list_storage_len,
+				initcode_words,
 			} => {
+				let initcode_cost = if self.config.max_initcode_size.is_some() {
+					initcode_words as u64 * consts::G_INITCODE_WORD
+				} else {
+					0
+				};
+
 				self.config.gas_transaction_create
-					+ zero_data_len as u64 * self.config.gas_transaction_zero_data
+					+ initcode_cost + zero_data_len as u64 * self.config.gas_transaction_zero_data
 					+ non_zero_data_len as u64 * self.config.gas_transaction_non_zero_data
 					+ access_list_address_len as u64 * self.config.gas_access_list_address
 					+ access_list_storage_len as u64 * self.config.gas_access_list_storage_key
@@ -261,14 +276,46 @@ impl<'config> Gasometer<'config> {
 		Ok(())
 	}
 
-	#[cfg(feature = "tracing")]
+	/// Gas limit.
+	pub fn gas_limit(&self) -> u64 {
+		self.gas_limit
+	}
+
+	/// Snapshot of the gasometer, or `None` if it has failed.
 	pub fn snapshot(&self) -> Option<Snapshot> {
-		self.inner.as_ref().ok().map(|inner| Snapshot {
-			gas_limit: self.gas_limit,
-			memory_gas: inner.memory_gas,
-			used_gas: inner.used_gas,
-			refunded_gas: inner.refunded_gas,
-		})
+		self.try_snapshot().ok()
+	}
+
+	/// Snapshot of the gasometer, or the error it has failed with.
+	pub fn try_snapshot(&self) -> Result<Snapshot, ExitError> {
+		self.inner
+			.as_ref()
+			.map(|inner| Snapshot {
+				gas_limit: self.gas_limit,
+				memory_gas: inner.memory_gas,
+				used_gas: inner.used_gas,
+				refunded_gas: inner.refunded_gas,
+			})
+			.map_err(|e| e.clone())
+	}
+
+	/// Restore a gasometer from a snapshot, or failed with the error it had
+	/// failed with.
+	pub fn from_snapshot(
+		gas_limit: u64,
+		snapshot: Result<Snapshot, ExitError>,
+		config: &'config Config,
+	) -> Self {
+		Self {
+			gas_limit,
+			config,
+			inner: snapshot.map(|snapshot| Inner {
+				memory_gas: snapshot.memory_gas,
+				used_gas: snapshot.used_gas,
+				refunded_gas: snapshot.refunded_gas,
+				config,
+			}),
+		}
 	}
 }
 
@@ -299,6 +346,7 @@ pub fn create_transaction_cost(data: &[u8], access_list: &[(H160, Vec<H256>)]) -
 		non_zero_data_len,
 		access_list_address_len,
 		access_list_storage_len,
+		initcode_words: (data.len() + 31) / 32,
 	}
 }
 
@@ -464,6 +512,28 @@ pub fn dynamic_opcode_cost<H: Handler>(
 		Opcode::BASEFEE if config.has_base_fee => GasCost::Base,
 		Opcode::BASEFEE => GasCost::Invalid,
 
+		Opcode::PUSH0 if config.has_push0 => GasCost::Base,
+		Opcode::PUSH0 => GasCost::Invalid,
+
+		Opcode::BLOBHASH if config.has_blob_hash => GasCost::VeryLow,
+		Opcode::BLOBHASH => GasCost::Invalid,
+
+		Opcode::BLOBBASEFEE if config.has_blob_base_fee => GasCost::Base,
+		Opcode::BLOBBASEFEE => GasCost::Invalid,
+
+		Opcode::MCOPY if config.has_mcopy => GasCost::VeryLowCopy {
+			len: U256::from_big_endian(&stack.peek(2)?[..]),
+		},
+		Opcode::MCOPY => GasCost::Invalid,
+
+		Opcode::RJUMP if config.has_eof => GasCost::Base,
+		Opcode::RJUMPI | Opcode::RJUMPV if config.has_eof => GasCost::RJumpI,
+		Opcode::CALLF if config.has_eof => GasCost::Low,
+		Opcode::RETF if config.has_eof => GasCost::VeryLow,
+
+		Opcode::TLOAD if config.has_transient_storage => GasCost::WarmStorageRead,
+		Opcode::TSTORE if !is_static && config.has_transient_storage => GasCost::WarmStorageRead,
+
 		Opcode::EXTCODESIZE => {
 			let target = stack.peek(0)?.into();
 			storage_target = StorageTarget::Address(target);
@@ -582,7 +652,9 @@ pub fn dynamic_opcode_cost<H: Handler>(
 			n: 4,
 			len: U256::from_big_endian(&stack.peek(1)?[..]),
 		},
-		Opcode::CREATE if !is_static => GasCost::Create,
+		Opcode::CREATE if !is_static => GasCost::Create {
+			len: U256::from_big_endian(&stack.peek(2)?[..]),
+		},
 		Opcode::CREATE2 if !is_static && config.has_create2 => GasCost::Create2 {
 			len: U256::from_big_endian(&stack.peek(2)?[..]),
 		},
@@ -641,6 +713,14 @@ pub fn dynamic_opcode_cost<H: Handler>(
 			len: U256::from(32),
 		}),
 
+		Opcode::MCOPY => Some(MemoryCost {
+			offset: max(
+				U256::from_big_endian(&stack.peek(0)?[..]),
+				U256::from_big_endian(&stack.peek(1)?[..]),
+			),
+			len: U256::from_big_endian(&stack.peek(2)?[..]),
+		}),
+
 		Opcode::MSTORE8 => Some(MemoryCost {
 			offset: U256::from_big_endian(&stack.peek(0)?[..]),
 			len: U256::from(1),
@@ -794,14 +874,16 @@ impl<'config> Inner<'config> {
 			GasCost::Log { n, len } => costs::log_cost(n, len)?,
 			GasCost::VeryLowCopy { len } => costs::verylowcopy_cost(len)?,
 			GasCost::Exp { power } => costs::exp_cost(power, self.config)?,
-			GasCost::Create => consts::G_CREATE,
-			GasCost::Create2 { len } => costs::create2_cost(len)?,
+			GasCost::Create { len } => costs::create_cost(len, self.config)?,
+			GasCost::Create2 { len } => costs::create2_cost(len, self.config)?,
 			GasCost::SLoad { target_is_cold } => costs::sload_cost(target_is_cold, self.config),
 
 			GasCost::Zero => consts::G_ZERO,
 			GasCost::Base => consts::G_BASE,
 			GasCost::VeryLow => consts::G_VERYLOW,
 			GasCost::Low => consts::G_LOW,
+			GasCost::RJumpI => consts::G_RJUMPI,
+			GasCost::WarmStorageRead => self.config.gas_storage_read_warm,
 			GasCost::Invalid => return Err(ExitError::OutOfGas),
 
 			GasCost::ExtCodeSize { target_is_cold } => {
@@ -852,6 +934,10 @@ pub enum GasCost {
 	VeryLow,
 	/// Low gas cost.
 	Low,
+	/// Gas cost for `RJUMPI` and `RJUMPV`.
+	RJumpI,
+	/// Gas cost of a warm storage read, as for `TLOAD` and `TSTORE`.
+	WarmStorageRead,
 	/// Fail the gasometer.
 	Invalid,
 
@@ -965,7 +1051,10 @@ pub enum GasCost {
 		power: U256,
 	},
 	/// Gas cost for `CREATE`.
-	Create,
+	Create {
+		/// Length of the init code.
+		len: U256,
+	},
 	/// Gas cost for `CREATE2`.
 	Create2 {
 		/// Length.
@@ -1022,6 +1111,9 @@ pub enum TransactionCost {
 		access_list_address_len: usize,
 		/// Total number of storage keys in transaction access list (see EIP-2930)
 		access_list_storage_len: usize,
+		/// Number of 32-byte words of init code, metered after Shanghai (see
+		/// EIP-3860)
+		initcode_words: usize,
 	},
 }
 
@@ -0,0 +1,120 @@
+//! Runner for the `ethereum/tests` JSON fixtures.
+//!
+//! Usage: `jsontests [--verbose] <PATH>...`, where each path is a
+//! GeneralStateTests fixture file or a directory searched for them.
+
+mod state;
+mod utils;
+
+use crate::state::{fork_config, StateTest};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Pass and fail counts of a fork.
+#[derive(Clone, Copy, Debug, Default)]
+struct Summary {
+	passed: usize,
+	failed: usize,
+}
+
+/// JSON files under a path, in order.
+fn fixture_files(path: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+	if path.is_dir() {
+		let mut entries = fs::read_dir(path)?
+			.map(|entry| entry.map(|entry| entry.path()))
+			.collect::<Result<Vec<_>, _>>()?;
+		entries.sort();
+		for entry in entries {
+			fixture_files(&entry, files)?;
+		}
+	} else if path.extension().and_then(|extension| extension.to_str()) == Some("json") {
+		files.push(path.to_path_buf());
+	}
+	Ok(())
+}
+
+fn main() {
+	let mut verbose = false;
+	let mut paths = Vec::new();
+	for arg in std::env::args().skip(1) {
+		match arg.as_str() {
+			"-v" | "--verbose" => verbose = true,
+			_ => paths.push(PathBuf::from(arg)),
+		}
+	}
+	if paths.is_empty() {
+		eprintln!("Usage: jsontests [--verbose] <PATH>...");
+		process::exit(2);
+	}
+
+	let mut files = Vec::new();
+	for path in &paths {
+		if let Err(error) = fixture_files(path, &mut files) {
+			eprintln!("{}: {}", path.display(), error);
+			process::exit(2);
+		}
+	}
+
+	let mut summaries = BTreeMap::<String, Summary>::new();
+	let mut skipped = BTreeMap::<String, usize>::new();
+	let mut unreadable = 0;
+	for file in &files {
+		let tests = match fs::read(file)
+			.map_err(|error| error.to_string())
+			.and_then(|content| {
+				serde_json::from_slice::<BTreeMap<String, StateTest>>(&content)
+					.map_err(|error| error.to_string())
+			}) {
+			Ok(tests) => tests,
+			Err(error) => {
+				eprintln!("{}: {}", file.display(), error);
+				unreadable += 1;
+				continue;
+			}
+		};
+
+		for (name, test) in &tests {
+			for (fork, posts) in &test.post {
+				let config = match fork_config(fork) {
+					Some(config) => config,
+					None => {
+						*skipped.entry(fork.clone()).or_default() += posts.len();
+						continue;
+					}
+				};
+				let summary = summaries.entry(fork.clone()).or_default();
+				for (index, post) in posts.iter().enumerate() {
+					match test.run(&config, post) {
+						Ok(()) => summary.passed += 1,
+						Err(error) => {
+							summary.failed += 1;
+							if verbose {
+								println!("FAIL {} {} #{}: {}", name, fork, index, error);
+							}
+						}
+					}
+				}
+			}
+		}
+	}
+
+	println!("{:<12} {:>8} {:>8}", "Fork", "Passed", "Failed");
+	for (fork, summary) in &summaries {
+		println!("{:<12} {:>8} {:>8}", fork, summary.passed, summary.failed);
+	}
+	for (fork, count) in &skipped {
+		println!("{:<12} {:>8} skipped", fork, count);
+	}
+	if unreadable > 0 {
+		println!("{} unreadable fixture files", unreadable);
+	}
+	if summaries.contains_key("Cancun") {
+		println!("Cancun precompiles are incomplete: KZG point evaluation (0x0a) is not supported");
+	}
+
+	if unreadable > 0 || summaries.values().any(|summary| summary.failed > 0) {
+		process::exit(1);
+	}
+}
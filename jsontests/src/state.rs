@@ -0,0 +1,315 @@
+//! GeneralStateTests: a transaction run on top of a pre-state, checked
+//! against the post-state root and logs hash of each fork.
+
+use crate::utils::{keccak256, Address, Bytes, Hash, Number, To};
+use evm::backend::{Apply, ApplyBackend, Log, MemoryAccount, MemoryBackend, MemoryVicinity};
+use evm::executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata, Transaction};
+use evm::precompiles::StandardPrecompileSet;
+use evm::Config;
+use primitive_types::{H160, H256, U256};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Account of the pre-state.
+#[derive(Clone, Debug, Deserialize)]
+pub struct StateAccount {
+	pub balance: Number,
+	pub code: Bytes,
+	pub nonce: Number,
+	pub storage: BTreeMap<Number, Number>,
+}
+
+/// Block environment of the test.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateEnv {
+	pub current_coinbase: Address,
+	pub current_difficulty: Number,
+	pub current_gas_limit: Number,
+	pub current_number: Number,
+	pub current_timestamp: Number,
+	pub current_base_fee: Option<Number>,
+	pub current_random: Option<Hash>,
+	pub current_excess_blob_gas: Option<Number>,
+}
+
+/// Entry of a transaction access list.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessListItem {
+	pub address: Address,
+	pub storage_keys: Vec<Hash>,
+}
+
+/// Transaction of the test, with all its data, gas limit and value
+/// variants.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateTransaction {
+	pub data: Vec<Bytes>,
+	pub gas_limit: Vec<Number>,
+	pub gas_price: Option<Number>,
+	pub max_fee_per_gas: Option<Number>,
+	pub max_priority_fee_per_gas: Option<Number>,
+	pub nonce: Number,
+	pub secret_key: Hash,
+	pub sender: Option<Address>,
+	pub to: To,
+	pub value: Vec<Number>,
+	#[serde(default)]
+	pub access_lists: Vec<Option<Vec<AccessListItem>>>,
+	#[serde(default)]
+	pub blob_versioned_hashes: Vec<Hash>,
+}
+
+/// Indexes of the transaction variant a post-state is for.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct PostIndexes {
+	pub data: usize,
+	pub gas: usize,
+	pub value: usize,
+}
+
+/// Expected post-state of a transaction variant.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostState {
+	pub hash: Hash,
+	pub logs: Hash,
+	pub indexes: PostIndexes,
+	pub expect_exception: Option<String>,
+}
+
+/// A GeneralStateTests test case.
+#[derive(Clone, Debug, Deserialize)]
+pub struct StateTest {
+	pub env: StateEnv,
+	pub pre: BTreeMap<Address, StateAccount>,
+	pub transaction: StateTransaction,
+	pub post: BTreeMap<String, Vec<PostState>>,
+}
+
+/// Configuration of a fork, as named in the fixtures. `None` for forks
+/// without a matching `Config`.
+pub fn fork_config(fork: &str) -> Option<Config> {
+	match fork {
+		"Frontier" => Some(Config::frontier()),
+		"Istanbul" => Some(Config::istanbul()),
+		"Berlin" => Some(Config::berlin()),
+		"London" => Some(Config::london()),
+		"Merge" | "Paris" => Some(Config::merge()),
+		"Shanghai" => Some(Config::shanghai()),
+		"Cancun" => Some(Config::cancun()),
+		_ => None,
+	}
+}
+
+/// Address of the account of a secret key.
+fn secret_key_address(secret_key: H256) -> Result<H160, String> {
+	let secret_key = libsecp256k1::SecretKey::parse(&secret_key.0)
+		.map_err(|_| String::from("invalid secret key"))?;
+	let public_key = libsecp256k1::PublicKey::from_secret_key(&secret_key).serialize();
+	Ok(H160::from_slice(&keccak256(&public_key[1..])[12..]))
+}
+
+/// Hash of the RLP encoded list of logs.
+fn logs_hash(logs: &[Log]) -> H256 {
+	let mut stream = rlp::RlpStream::new_list(logs.len());
+	for log in logs {
+		stream.begin_list(3);
+		stream.append(&log.address);
+		stream.append_list(&log.topics);
+		stream.append(&log.data);
+	}
+	keccak256(&stream.out())
+}
+
+/// Blob base fee for an excess blob gas. See EIP-4844.
+fn blob_base_fee(excess_blob_gas: U256) -> U256 {
+	const MIN_BLOB_GASPRICE: u64 = 1;
+	const BLOB_BASE_FEE_UPDATE_FRACTION: u64 = 3_338_477;
+
+	let denominator = U256::from(BLOB_BASE_FEE_UPDATE_FRACTION);
+	let mut i = U256::one();
+	let mut output = U256::zero();
+	let mut accum = U256::from(MIN_BLOB_GASPRICE) * denominator;
+	while !accum.is_zero() {
+		output = output.saturating_add(accum);
+		accum = accum.saturating_mul(excess_blob_gas) / (denominator * i);
+		i += U256::one();
+	}
+	output / denominator
+}
+
+impl StateTest {
+	/// Run the transaction variant of a post-state with the configuration of
+	/// a fork, and check the resulting state root and logs hash.
+	pub fn run(&self, config: &Config, post: &PostState) -> Result<(), String> {
+		let transaction = &self.transaction;
+		let caller = match transaction.sender {
+			Some(sender) => sender.0,
+			None => secret_key_address(transaction.secret_key.0)?,
+		};
+
+		let indexes = post.indexes;
+		let data = transaction
+			.data
+			.get(indexes.data)
+			.ok_or("data index out of bounds")?;
+		let gas_limit = transaction
+			.gas_limit
+			.get(indexes.gas)
+			.ok_or("gas index out of bounds")?;
+		let value = transaction
+			.value
+			.get(indexes.value)
+			.ok_or("value index out of bounds")?;
+		let access_list = transaction
+			.access_lists
+			.get(indexes.data)
+			.cloned()
+			.flatten()
+			.unwrap_or_default()
+			.into_iter()
+			.map(|item| {
+				(
+					item.address.0,
+					item.storage_keys.into_iter().map(|key| key.0).collect(),
+				)
+			})
+			.collect();
+		let (max_fee_per_gas, max_priority_fee_per_gas) = match transaction.gas_price {
+			Some(gas_price) => (gas_price.0, gas_price.0),
+			None => (
+				transaction.max_fee_per_gas.unwrap_or_default().0,
+				transaction.max_priority_fee_per_gas.unwrap_or_default().0,
+			),
+		};
+		let transaction = Transaction {
+			caller,
+			chain_id: None,
+			nonce: transaction.nonce.0,
+			gas_limit: gas_limit.0,
+			max_fee_per_gas,
+			max_priority_fee_per_gas,
+			address: transaction.to.0,
+			value: value.0,
+			data: data.0.clone(),
+			access_list,
+		};
+
+		let base_fee_per_gas = self.env.current_base_fee.unwrap_or_default().0;
+		let vicinity = MemoryVicinity {
+			gas_price: transaction.effective_gas_price(if config.has_base_fee {
+				base_fee_per_gas
+			} else {
+				U256::zero()
+			}),
+			origin: caller,
+			chain_id: U256::one(),
+			block_hashes: Vec::new(),
+			block_number: self.env.current_number.0,
+			block_coinbase: self.env.current_coinbase.0,
+			block_timestamp: self.env.current_timestamp.0,
+			block_difficulty: self.env.current_difficulty.0,
+			block_gas_limit: self.env.current_gas_limit.0,
+			block_base_fee_per_gas: base_fee_per_gas,
+			block_randomness: self.env.current_random.map(|random| random.0),
+			block_blob_base_fee: blob_base_fee(
+				self.env.current_excess_blob_gas.unwrap_or_default().0,
+			),
+			blob_hashes: self
+				.transaction
+				.blob_versioned_hashes
+				.iter()
+				.map(|hash| hash.0)
+				.collect(),
+		};
+		let state = self
+			.pre
+			.iter()
+			.map(|(address, account)| {
+				let storage = account
+					.storage
+					.iter()
+					.map(|(index, value)| {
+						let mut index_bytes = H256::default();
+						let mut value_bytes = H256::default();
+						index.0.to_big_endian(&mut index_bytes[..]);
+						value.0.to_big_endian(&mut value_bytes[..]);
+						(index_bytes, value_bytes)
+					})
+					.collect();
+				(
+					address.0,
+					MemoryAccount {
+						nonce: account.nonce.0,
+						balance: account.balance.0,
+						storage,
+						code: account.code.0.clone(),
+					},
+				)
+			})
+			.collect();
+		let mut backend = MemoryBackend::new(&vicinity, state);
+
+		let result = {
+			let metadata = StackSubstateMetadata::new(transaction.gas_limit.low_u64(), config);
+			let state = MemoryStackState::new(metadata, &backend);
+			let precompiles = StandardPrecompileSet::new(config);
+			let mut executor = StackExecutor::new_with_precompiles(state, config, &precompiles);
+			executor.transact(transaction).map(|_| {
+				let (values, logs) = executor.into_state().deconstruct();
+				let values = values
+					.into_iter()
+					.map(|apply| match apply {
+						Apply::Modify {
+							address,
+							basic,
+							code,
+							storage,
+							reset_storage,
+						} => Apply::Modify {
+							address,
+							basic,
+							code,
+							storage: storage.into_iter().collect::<Vec<_>>(),
+							reset_storage,
+						},
+						Apply::Delete { address } => Apply::Delete { address },
+					})
+					.collect::<Vec<_>>();
+				(values, logs.into_iter().collect::<Vec<_>>())
+			})
+		};
+
+		let logs = match (result, &post.expect_exception) {
+			(Ok(_), Some(exception)) => {
+				return Err(format!("expected exception {}", exception));
+			}
+			(Err(error), None) => return Err(format!("invalid transaction: {:?}", error)),
+			(Ok((values, logs)), None) => {
+				backend.apply(values, logs.clone(), !config.empty_considered_exists);
+				logs
+			}
+			(Err(_), Some(_)) => Vec::new(),
+		};
+
+		let state_root = backend.state_root();
+		if state_root != post.hash.0 {
+			return Err(format!(
+				"state root {:?}, expected {:?}",
+				state_root, post.hash.0
+			));
+		}
+		let logs_hash = logs_hash(&logs);
+		if logs_hash != post.logs.0 {
+			return Err(format!(
+				"logs hash {:?}, expected {:?}",
+				logs_hash, post.logs.0
+			));
+		}
+
+		Ok(())
+	}
+}
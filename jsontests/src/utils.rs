@@ -0,0 +1,104 @@
+//! Hex values of the JSON fixtures.
+
+use primitive_types::{H160, H256, U256};
+use serde::de::{Deserialize, Deserializer, Error};
+use sha3::{Digest, Keccak256};
+
+fn strip_hex(value: &str) -> &str {
+	let value = value.trim_start_matches("0x:bigint ");
+	value
+		.strip_prefix("0x")
+		.or_else(|| value.strip_prefix("0X"))
+		.unwrap_or(value)
+}
+
+fn decode<E: Error>(value: &str) -> Result<Vec<u8>, E> {
+	let value = strip_hex(value);
+	if value.len() % 2 == 1 {
+		hex::decode(["0", value].concat())
+	} else {
+		hex::decode(value)
+	}
+	.map_err(E::custom)
+}
+
+/// Hex encoded number, of at most 256 bits.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Number(pub U256);
+
+impl<'de> Deserialize<'de> for Number {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let value = String::deserialize(deserializer)?;
+		let bytes = decode::<D::Error>(&value)?;
+		let bytes = &bytes[bytes.iter().take_while(|byte| **byte == 0).count()..];
+		if bytes.len() > 32 {
+			return Err(D::Error::custom("number over 256 bits"));
+		}
+		Ok(Number(U256::from_big_endian(bytes)))
+	}
+}
+
+/// Hex encoded byte string.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Bytes(pub Vec<u8>);
+
+impl<'de> Deserialize<'de> for Bytes {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let value = String::deserialize(deserializer)?;
+		Ok(Bytes(decode(&value)?))
+	}
+}
+
+/// Hex encoded address.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Address(pub H160);
+
+impl<'de> Deserialize<'de> for Address {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let value = String::deserialize(deserializer)?;
+		let bytes = decode::<D::Error>(&value)?;
+		if bytes.len() != 20 {
+			return Err(D::Error::custom("address is not 20 bytes"));
+		}
+		Ok(Address(H160::from_slice(&bytes)))
+	}
+}
+
+/// Hex encoded recipient of a transaction, empty for a create
+/// transaction.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct To(pub Option<H160>);
+
+impl<'de> Deserialize<'de> for To {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let value = String::deserialize(deserializer)?;
+		let bytes = decode::<D::Error>(&value)?;
+		match bytes.len() {
+			0 => Ok(To(None)),
+			20 => Ok(To(Some(H160::from_slice(&bytes)))),
+			_ => Err(D::Error::custom("address is not 20 bytes")),
+		}
+	}
+}
+
+/// Hex encoded hash.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Hash(pub H256);
+
+impl<'de> Deserialize<'de> for Hash {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let value = String::deserialize(deserializer)?;
+		let bytes = decode::<D::Error>(&value)?;
+		if bytes.len() > 32 {
+			return Err(D::Error::custom("hash over 32 bytes"));
+		}
+		let mut hash = H256::default();
+		hash[32 - bytes.len()..].copy_from_slice(&bytes);
+		Ok(Hash(hash))
+	}
+}
+
+/// Keccak-256 hash.
+pub fn keccak256(data: &[u8]) -> H256 {
+	H256::from_slice(&Keccak256::digest(data))
+}
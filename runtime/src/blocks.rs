@@ -0,0 +1,67 @@
+use crate::Stack;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Run of instructions whose costs are all static, executed one after the
+/// other once the first one is entered.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StaticBlock {
+	/// Offset of the first instruction.
+	pub start: usize,
+	/// Number of instructions.
+	pub instructions: usize,
+	/// Sum of the static costs of the instructions.
+	pub gas: u64,
+	/// Stack items the run takes from the stack on entry.
+	pub stack_inputs: usize,
+	/// Most items the run pushes above its entry stack height.
+	pub stack_growth: usize,
+}
+
+impl StaticBlock {
+	/// Whether the run can be executed on a stack without underflow or
+	/// overflow.
+	pub fn fits(&self, stack: &Stack) -> bool {
+		stack.len() >= self.stack_inputs && stack.len() + self.stack_growth <= stack.limit()
+	}
+}
+
+/// Static blocks of some code, ordered by offset.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StaticBlocks {
+	blocks: Vec<StaticBlock>,
+	/// Bitmap of the offsets a run starts at, so that most offsets are
+	/// ruled out without searching the runs.
+	starts: Vec<u64>,
+}
+
+impl StaticBlocks {
+	/// Create from a list of non-overlapping runs.
+	pub fn new(mut blocks: Vec<StaticBlock>) -> Self {
+		blocks.sort_by_key(|block| block.start);
+		let len = blocks.last().map_or(0, |block| block.start / 64 + 1);
+		let mut starts = vec![0u64; len];
+		for block in &blocks {
+			starts[block.start / 64] |= 1 << (block.start % 64);
+		}
+		Self { blocks, starts }
+	}
+
+	/// Run starting at an offset.
+	#[inline]
+	pub fn get(&self, offset: usize) -> Option<&StaticBlock> {
+		match self.starts.get(offset / 64) {
+			Some(bits) if bits & (1 << (offset % 64)) != 0 => self
+				.blocks
+				.binary_search_by_key(&offset, |block| block.start)
+				.ok()
+				.map(|index| &self.blocks[index]),
+			_ => None,
+		}
+	}
+
+	/// All runs, ordered by offset.
+	pub fn blocks(&self) -> &[StaticBlock] {
+		&self.blocks
+	}
+}
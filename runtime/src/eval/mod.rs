@@ -3,6 +3,9 @@ mod macros;
 mod system;
 
 use crate::{CallScheme, ExitReason, Handler, Opcode, Runtime};
+use alloc::vec::Vec;
+use core::cmp::min;
+use primitive_types::{H160, H256, U256};
 
 pub enum Control<H: Handler> {
 	Continue,
@@ -41,6 +44,8 @@ pub fn eval<H: Handler>(state: &mut Runtime, opcode: Opcode, handler: &mut H) ->
 		Opcode::GASLIMIT => system::gaslimit(state, handler),
 		Opcode::SLOAD => system::sload(state, handler),
 		Opcode::SSTORE => system::sstore(state, handler),
+		Opcode::TLOAD => system::tload(state, handler),
+		Opcode::TSTORE => system::tstore(state, handler),
 		Opcode::GAS => system::gas(state, handler),
 		Opcode::LOG0 => system::log(state, 0, handler),
 		Opcode::LOG1 => system::log(state, 1, handler),
@@ -56,6 +61,87 @@ pub fn eval<H: Handler>(state: &mut Runtime, opcode: Opcode, handler: &mut H) ->
 		Opcode::STATICCALL => system::call(state, CallScheme::StaticCall, handler),
 		Opcode::CHAINID => system::chainid(state, handler),
 		Opcode::BASEFEE => system::base_fee(state, handler),
+		Opcode::BLOBHASH => system::blob_hash(state, handler),
+		Opcode::BLOBBASEFEE => system::blob_base_fee(state, handler),
 		_ => handle_other(state, opcode, handler),
 	}
 }
+
+pub fn finish_create(
+	runtime: &mut Runtime,
+	reason: ExitReason,
+	address: Option<H160>,
+	return_data: Vec<u8>,
+) -> Result<(), ExitReason> {
+	runtime.return_data_buffer = return_data;
+	let create_address: H256 = address.map(|a| a.into()).unwrap_or_default();
+
+	match reason {
+		ExitReason::Succeed(_) => {
+			runtime.machine.stack_mut().push(create_address)?;
+			Ok(())
+		}
+		ExitReason::Revert(_) | ExitReason::Error(_) => {
+			runtime.machine.stack_mut().push(H256::default())?;
+			Ok(())
+		}
+		ExitReason::Fatal(e) => {
+			runtime.machine.stack_mut().push(H256::default())?;
+			Err(e.into())
+		}
+	}
+}
+
+pub fn finish_call(
+	runtime: &mut Runtime,
+	out_len: U256,
+	out_offset: U256,
+	reason: ExitReason,
+	return_data: Vec<u8>,
+) -> Result<(), ExitReason> {
+	runtime.return_data_buffer = return_data;
+	let target_len = min(out_len, U256::from(runtime.return_data_buffer.len()));
+
+	match reason {
+		ExitReason::Succeed(_) => {
+			match runtime.machine.memory_mut().copy_large(
+				out_offset,
+				U256::zero(),
+				target_len,
+				&runtime.return_data_buffer[..],
+			) {
+				Ok(()) => {
+					let mut value = H256::default();
+					U256::one().to_big_endian(&mut value[..]);
+					runtime.machine.stack_mut().push(value)?;
+				}
+				Err(_) => {
+					runtime.machine.stack_mut().push(H256::default())?;
+				}
+			}
+			Ok(())
+		}
+		ExitReason::Revert(_) => {
+			runtime.machine.stack_mut().push(H256::default())?;
+
+			let _ = runtime.machine.memory_mut().copy_large(
+				out_offset,
+				U256::zero(),
+				target_len,
+				&runtime.return_data_buffer[..],
+			);
+
+			Ok(())
+		}
+		ExitReason::Error(_) => {
+			runtime.machine.stack_mut().push(H256::default())?;
+
+			Ok(())
+		}
+		ExitReason::Fatal(e) => {
+			runtime.machine.stack_mut().push(H256::default())?;
+
+			Err(e.into())
+		}
+	}
+}
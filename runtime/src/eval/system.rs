@@ -1,10 +1,9 @@
 use super::Control;
 use crate::{
-	CallScheme, Capture, Context, CreateScheme, ExitError, ExitFatal, ExitReason, ExitSucceed,
-	Handler, Runtime, Transfer,
+	CallScheme, Capture, Context, CreateScheme, ExitError, ExitFatal, ExitSucceed, Handler,
+	Runtime, Transfer,
 };
 use alloc::vec::Vec;
-use core::cmp::min;
 use primitive_types::{H256, U256};
 use sha3::{Digest, Keccak256};
 
@@ -91,6 +90,24 @@ pub fn base_fee<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
 	Control::Continue
 }
 
+pub fn blob_hash<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
+	pop_u256!(runtime, index);
+	let hash = if index <= U256::from(usize::MAX) {
+		handler.blob_hash(index.as_usize())
+	} else {
+		None
+	};
+	push!(runtime, hash.unwrap_or_default());
+
+	Control::Continue
+}
+
+pub fn blob_base_fee<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
+	push_u256!(runtime, handler.block_blob_base_fee());
+
+	Control::Continue
+}
+
 pub fn extcodesize<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
 	pop!(runtime, address);
 	push_u256!(runtime, handler.code_size(address.into()));
@@ -182,7 +199,11 @@ pub fn number<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
 }
 
 pub fn difficulty<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
-	push_u256!(runtime, handler.block_difficulty());
+	if runtime.config.has_prevrandao {
+		push!(runtime, handler.block_randomness().unwrap_or_default());
+	} else {
+		push_u256!(runtime, handler.block_difficulty());
+	}
 	Control::Continue
 }
 
@@ -220,6 +241,35 @@ pub fn sstore<H: Handler>(runtime: &mut Runtime, handler: &mut H) -> Control<H>
 	}
 }
 
+pub fn tload<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
+	pop!(runtime, index);
+	let value = handler.transient_storage(runtime.context.address, index);
+	push!(runtime, value);
+
+	event!(TLoad {
+		address: runtime.context.address,
+		index,
+		value
+	});
+
+	Control::Continue
+}
+
+pub fn tstore<H: Handler>(runtime: &mut Runtime, handler: &mut H) -> Control<H> {
+	pop!(runtime, index, value);
+
+	event!(TStore {
+		address: runtime.context.address,
+		index,
+		value
+	});
+
+	match handler.set_transient_storage(runtime.context.address, index, value) {
+		Ok(()) => Control::Continue,
+		Err(e) => Control::Exit(e.into()),
+	}
+}
+
 pub fn gas<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
 	push_u256!(runtime, handler.gas_left());
 
@@ -297,32 +347,12 @@ pub fn create<H: Handler>(runtime: &mut Runtime, is_create2: bool, handler: &mut
 
 	match handler.create(runtime.context.address, scheme, value, code, None) {
 		Capture::Exit((reason, address, return_data)) => {
-			runtime.return_data_buffer = return_data;
-			let create_address: H256 = address.map(|a| a.into()).unwrap_or_default();
-
-			match reason {
-				ExitReason::Succeed(_) => {
-					push!(runtime, create_address);
-					Control::Continue
-				}
-				ExitReason::Revert(_) => {
-					push!(runtime, H256::default());
-					Control::Continue
-				}
-				ExitReason::Error(_) => {
-					push!(runtime, H256::default());
-					Control::Continue
-				}
-				ExitReason::Fatal(e) => {
-					push!(runtime, H256::default());
-					Control::Exit(e.into())
-				}
+			match super::finish_create(runtime, reason, address, return_data) {
+				Ok(()) => Control::Continue,
+				Err(e) => Control::Exit(e),
 			}
 		}
-		Capture::Trap(interrupt) => {
-			push!(runtime, H256::default());
-			Control::CreateInterrupt(interrupt)
-		}
+		Capture::Trap(interrupt) => Control::CreateInterrupt(interrupt),
 	}
 }
 
@@ -408,53 +438,14 @@ pub fn call<H: Handler>(runtime: &mut Runtime, scheme: CallScheme, handler: &mut
 		context,
 	) {
 		Capture::Exit((reason, return_data)) => {
-			runtime.return_data_buffer = return_data;
-			let target_len = min(out_len, U256::from(runtime.return_data_buffer.len()));
-
-			match reason {
-				ExitReason::Succeed(_) => {
-					match runtime.machine.memory_mut().copy_large(
-						out_offset,
-						U256::zero(),
-						target_len,
-						&runtime.return_data_buffer[..],
-					) {
-						Ok(()) => {
-							push_u256!(runtime, U256::one());
-							Control::Continue
-						}
-						Err(_) => {
-							push_u256!(runtime, U256::zero());
-							Control::Continue
-						}
-					}
-				}
-				ExitReason::Revert(_) => {
-					push_u256!(runtime, U256::zero());
-
-					let _ = runtime.machine.memory_mut().copy_large(
-						out_offset,
-						U256::zero(),
-						target_len,
-						&runtime.return_data_buffer[..],
-					);
-
-					Control::Continue
-				}
-				ExitReason::Error(_) => {
-					push_u256!(runtime, U256::zero());
-
-					Control::Continue
-				}
-				ExitReason::Fatal(e) => {
-					push_u256!(runtime, U256::zero());
-
-					Control::Exit(e.into())
-				}
+			match super::finish_call(runtime, out_len, out_offset, reason, return_data) {
+				Ok(()) => Control::Continue,
+				Err(e) => Control::Exit(e),
 			}
 		}
 		Capture::Trap(interrupt) => {
-			push!(runtime, H256::default());
+			runtime.return_data_len = out_len;
+			runtime.return_data_offset = out_offset;
 			Control::CallInterrupt(interrupt)
 		}
 	}
@@ -37,6 +37,11 @@ pub trait Handler {
 	fn storage(&self, address: H160, index: H256) -> H256;
 	/// Get original storage value of address at index.
 	fn original_storage(&self, address: H160, index: H256) -> H256;
+	/// Get transient storage value of address at index. See EIP-1153. Zero
+	/// for handlers without transient storage.
+	fn transient_storage(&self, _address: H160, _index: H256) -> H256 {
+		H256::default()
+	}
 
 	/// Get the gas left value.
 	fn gas_left(&self) -> U256;
@@ -58,6 +63,19 @@ pub trait Handler {
 	fn block_gas_limit(&self) -> U256;
 	/// Environmental block base fee.
 	fn block_base_fee_per_gas(&self) -> U256;
+	/// Get environmental block randomness (`PREVRANDAO`), `None` before the
+	/// Merge.
+	fn block_randomness(&self) -> Option<H256> {
+		None
+	}
+	/// Get environmental blob base fee.
+	fn block_blob_base_fee(&self) -> U256 {
+		U256::zero()
+	}
+	/// Get versioned hash of the transaction blob at index.
+	fn blob_hash(&self, _index: usize) -> Option<H256> {
+		None
+	}
 	/// Get environmental chain ID.
 	fn chain_id(&self) -> U256;
 
@@ -75,6 +93,18 @@ pub trait Handler {
 
 	/// Set storage value of address at index.
 	fn set_storage(&mut self, address: H160, index: H256, value: H256) -> Result<(), ExitError>;
+	/// Set transient storage value of address at index. See EIP-1153.
+	/// Handlers without transient storage fail with `Other`.
+	fn set_transient_storage(
+		&mut self,
+		_address: H160,
+		_index: H256,
+		_value: H256,
+	) -> Result<(), ExitError> {
+		Err(ExitError::Other(
+			"transient storage is not supported".into(),
+		))
+	}
 	/// Create a log owned by address with given topics and data.
 	fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError>;
 	/// Mark an address to be deleted, with funds transferred to target.
@@ -114,6 +144,12 @@ pub trait Handler {
 		opcode: Opcode,
 		stack: &Stack,
 	) -> Result<(), ExitError>;
+	/// Pre-validation of a static block, charging the total `gas` of its
+	/// instructions at once. Returns `false` to pre-validate them one by one
+	/// instead, which must be done when they would run out of gas.
+	fn pre_validate_static(&mut self, _context: &Context, _gas: u64) -> bool {
+		false
+	}
 	/// Handle other unknown external opcodes.
 	fn other(&mut self, _opcode: Opcode, _stack: &mut Machine) -> Result<(), ExitError> {
 		Err(ExitError::OutOfGas)
@@ -1,4 +1,6 @@
-use crate::{ExitFatal, Handler, Runtime};
+use crate::{ExitFatal, ExitReason, Handler, Runtime};
+use alloc::vec::Vec;
+use primitive_types::H160;
 
 /// Interrupt resolution.
 pub enum Resolve<'a, 'config, H: Handler> {
@@ -8,42 +10,87 @@ pub enum Resolve<'a, 'config, H: Handler> {
 	Call(H::CallInterrupt, ResolveCall<'a, 'config>),
 }
 
-/// Create interrupt resolution.
+/// Create interrupt resolution. Dropping it without calling `finish` or
+/// `detach` exits the runtime with `ExitFatal::UnhandledInterrupt`.
 pub struct ResolveCreate<'a, 'config> {
 	runtime: &'a mut Runtime<'config>,
+	resolved: bool,
 }
 
 impl<'a, 'config> ResolveCreate<'a, 'config> {
 	pub(crate) fn new(runtime: &'a mut Runtime<'config>) -> Self {
-		Self { runtime }
+		Self {
+			runtime,
+			resolved: false,
+		}
+	}
+
+	/// Resolve the interrupt with the result of the create. See
+	/// `Runtime::finish_create`.
+	pub fn finish(
+		mut self,
+		reason: ExitReason,
+		address: Option<H160>,
+		return_data: Vec<u8>,
+	) -> Result<(), ExitReason> {
+		self.resolved = true;
+		self.runtime.finish_create(reason, address, return_data)
+	}
+
+	/// Release the runtime, leaving the interrupt to be resolved later with
+	/// `Runtime::finish_create`.
+	pub fn detach(mut self) {
+		self.resolved = true;
 	}
 }
 
 impl<'a, 'config> Drop for ResolveCreate<'a, 'config> {
 	fn drop(&mut self) {
-		self.runtime.status = Err(ExitFatal::UnhandledInterrupt.into());
-		self.runtime
-			.machine
-			.exit(ExitFatal::UnhandledInterrupt.into());
+		if !self.resolved {
+			self.runtime.status = Err(ExitFatal::UnhandledInterrupt.into());
+			self.runtime
+				.machine
+				.exit(ExitFatal::UnhandledInterrupt.into());
+		}
 	}
 }
 
-/// Call interrupt resolution.
+/// Call interrupt resolution. Dropping it without calling `finish` or
+/// `detach` exits the runtime with `ExitFatal::UnhandledInterrupt`.
 pub struct ResolveCall<'a, 'config> {
 	runtime: &'a mut Runtime<'config>,
+	resolved: bool,
 }
 
 impl<'a, 'config> ResolveCall<'a, 'config> {
 	pub(crate) fn new(runtime: &'a mut Runtime<'config>) -> Self {
-		Self { runtime }
+		Self {
+			runtime,
+			resolved: false,
+		}
+	}
+
+	/// Resolve the interrupt with the result of the call. See
+	/// `Runtime::finish_call`.
+	pub fn finish(mut self, reason: ExitReason, return_data: Vec<u8>) -> Result<(), ExitReason> {
+		self.resolved = true;
+		self.runtime.finish_call(reason, return_data)
+	}
+
+	/// Release the runtime, leaving the interrupt to be resolved later with
+	/// `Runtime::finish_call`.
+	pub fn detach(mut self) {
+		self.resolved = true;
 	}
 }
 
 impl<'a, 'config> Drop for ResolveCall<'a, 'config> {
 	fn drop(&mut self) {
-		self.runtime.status = Err(ExitFatal::UnhandledInterrupt.into());
-		self.runtime
-			.machine
-			.exit(ExitFatal::UnhandledInterrupt.into());
+		if !self.resolved {
+			self.runtime.status = Err(ExitFatal::UnhandledInterrupt.into());
+			self.runtime
+				.machine
+				.exit(ExitFatal::UnhandledInterrupt.into());
+		}
 	}
 }
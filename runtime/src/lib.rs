@@ -22,6 +22,7 @@ macro_rules! event {
 	($x:expr) => {};
 }
 
+mod blocks;
 mod context;
 mod eval;
 mod handler;
@@ -29,15 +30,18 @@ mod interrupt;
 
 pub use evm_core::*;
 
+pub use crate::blocks::{StaticBlock, StaticBlocks};
 pub use crate::context::{CallScheme, Context, CreateScheme};
 pub use crate::handler::{Handler, Transfer};
 pub use crate::interrupt::{Resolve, ResolveCall, ResolveCreate};
 
 use alloc::rc::Rc;
 use alloc::vec::Vec;
+use primitive_types::{H160, U256};
 
 macro_rules! step {
 	( $self:expr, $handler:expr, $return:tt $($err:path)?; $($ok:path)? ) => ({
+		let mut prevalidated = false;
 		if let Some((opcode, stack)) = $self.machine.inspect() {
 			event!(Step {
 				context: &$self.context,
@@ -47,12 +51,32 @@ macro_rules! step {
 				memory: $self.machine.memory()
 			});
 
-			match $handler.pre_validate(&$self.context, opcode, stack) {
-				Ok(()) => (),
-				Err(e) => {
-					$self.machine.exit(e.clone().into());
-					$self.status = Err(e.into());
-				},
+			if $self.prevalidated > 0 {
+				$self.prevalidated -= 1;
+				prevalidated = true;
+			} else {
+				let block = match (&$self.static_blocks, $self.machine.position()) {
+					(Some(blocks), Ok(position)) => {
+						blocks.get(*position).filter(|block| block.fits(stack))
+					},
+					_ => None,
+				};
+				let result = match block {
+					Some(block) if $handler.pre_validate_static(&$self.context, block.gas) => {
+						$self.prevalidated = block.instructions - 1;
+						prevalidated = true;
+						Ok(())
+					},
+					_ => $handler.pre_validate(&$self.context, opcode, stack),
+				};
+
+				match result {
+					Ok(()) => (),
+					Err(e) => {
+						$self.machine.exit(e.clone().into());
+						$self.status = Err(e.into());
+					},
+				}
 			}
 		}
 
@@ -64,7 +88,11 @@ macro_rules! step {
 			},
 		}
 
-		let result = $self.machine.step();
+		let result = if prevalidated {
+			$self.machine.step_prevalidated()
+		} else {
+			$self.machine.step()
+		};
 
 		event!(StepResult {
 			result: &result,
@@ -110,24 +138,94 @@ pub struct Runtime<'config> {
 	machine: Machine,
 	status: Result<(), ExitReason>,
 	return_data_buffer: Vec<u8>,
+	return_data_len: U256,
+	return_data_offset: U256,
 	context: Context,
-	_config: &'config Config,
+	config: &'config Config,
+	static_blocks: Option<Rc<StaticBlocks>>,
+	prevalidated: usize,
+}
+
+/// Snapshot of a runtime.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+	feature = "with-codec",
+	derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RuntimeSnapshot {
+	/// Snapshot of the machine.
+	pub machine: MachineSnapshot,
+	/// Runtime status.
+	pub status: Result<(), ExitReason>,
+	/// Return data of the last sub-call.
+	pub return_data_buffer: Vec<u8>,
+	/// Length of the output area of a pending call.
+	pub return_data_len: U256,
+	/// Offset of the output area of a pending call.
+	pub return_data_offset: U256,
+	/// Execution context.
+	pub context: Context,
+	/// Instructions left in a static block whose costs are already paid.
+	pub prevalidated: u64,
 }
 
 impl<'config> Runtime<'config> {
-	/// Create a new runtime with given code and data.
+	/// Create a new runtime with given code and data. With `has_eof`, code
+	/// starting with `0xEF00` is run as an EOF container.
 	pub fn new(
 		code: Rc<Vec<u8>>,
 		data: Rc<Vec<u8>>,
 		context: Context,
 		config: &'config Config,
 	) -> Self {
+		let machine = if config.has_eof && code.starts_with(&EOF_MAGIC) {
+			Machine::new_eof(code, data, config.stack_limit, config.memory_limit)
+		} else {
+			Machine::new(code, data, config.stack_limit, config.memory_limit)
+		};
+
+		Self::new_with_machine(machine, context, config)
+	}
+
+	/// Create a new runtime with given code, its valid jump destinations
+	/// computed beforehand, and data. They are ignored for EOF containers.
+	pub fn new_with_valids(
+		code: Rc<Vec<u8>>,
+		valids: Rc<Valids>,
+		data: Rc<Vec<u8>>,
+		context: Context,
+		config: &'config Config,
+	) -> Self {
+		let machine = if config.has_eof && code.starts_with(&EOF_MAGIC) {
+			Machine::new_eof(code, data, config.stack_limit, config.memory_limit)
+		} else {
+			Machine::new_with_valids(code, valids, data, config.stack_limit, config.memory_limit)
+		};
+
+		Self::new_with_machine(machine, context, config)
+	}
+
+	fn new_with_machine(machine: Machine, context: Context, config: &'config Config) -> Self {
 		Self {
-			machine: Machine::new(code, data, config.stack_limit, config.memory_limit),
+			machine,
 			status: Ok(()),
 			return_data_buffer: Vec::new(),
+			return_data_len: U256::zero(),
+			return_data_offset: U256::zero(),
 			context,
-			_config: config,
+			config,
+			static_blocks: None,
+			prevalidated: 0,
+		}
+	}
+
+	/// Pre-validate each of `blocks` at once when it is entered with a
+	/// stack it fits, see `Handler::pre_validate_static`. Other instructions
+	/// are still pre-validated one by one. Ignored for EOF containers.
+	pub fn set_static_blocks(&mut self, blocks: Rc<StaticBlocks>) {
+		if self.machine.eof().is_none() {
+			self.static_blocks = Some(blocks);
 		}
 	}
 
@@ -141,6 +239,39 @@ impl<'config> Runtime<'config> {
 		&self.context
 	}
 
+	/// Take a snapshot of the runtime, which can be stored and turned back
+	/// into a runtime with `from_snapshot`.
+	pub fn snapshot(&self) -> RuntimeSnapshot {
+		RuntimeSnapshot {
+			machine: self.machine.snapshot(),
+			status: self.status.clone(),
+			return_data_buffer: self.return_data_buffer.clone(),
+			return_data_len: self.return_data_len,
+			return_data_offset: self.return_data_offset,
+			context: self.context.clone(),
+			prevalidated: self.prevalidated as u64,
+		}
+	}
+
+	/// Restore a runtime from a snapshot. Fails if the snapshot code is an
+	/// invalid EOF container, see `Machine::from_snapshot`.
+	pub fn from_snapshot(
+		snapshot: RuntimeSnapshot,
+		config: &'config Config,
+	) -> Result<Self, EofError> {
+		Ok(Self {
+			machine: Machine::from_snapshot(snapshot.machine)?,
+			status: snapshot.status,
+			return_data_buffer: snapshot.return_data_buffer,
+			return_data_len: snapshot.return_data_len,
+			return_data_offset: snapshot.return_data_offset,
+			context: snapshot.context,
+			config,
+			static_blocks: None,
+			prevalidated: snapshot.prevalidated as usize,
+		})
+	}
+
 	/// Step the runtime.
 	pub fn step<'a, H: Handler>(
 		&'a mut self,
@@ -158,6 +289,44 @@ impl<'config> Runtime<'config> {
 			step!(self, handler, return;)
 		}
 	}
+
+	/// Feed back the result of a create interrupt, pushing the created
+	/// address onto the stack. A fatal result exits the runtime.
+	pub fn finish_create(
+		&mut self,
+		reason: ExitReason,
+		address: Option<H160>,
+		return_data: Vec<u8>,
+	) -> Result<(), ExitReason> {
+		let result = eval::finish_create(self, reason, address, return_data);
+		self.exit_on_err(result)
+	}
+
+	/// Feed back the result of a call interrupt, copying the return data
+	/// into memory and pushing the call status onto the stack. A fatal
+	/// result exits the runtime.
+	pub fn finish_call(
+		&mut self,
+		reason: ExitReason,
+		return_data: Vec<u8>,
+	) -> Result<(), ExitReason> {
+		let result = eval::finish_call(
+			self,
+			self.return_data_len,
+			self.return_data_offset,
+			reason,
+			return_data,
+		);
+		self.exit_on_err(result)
+	}
+
+	fn exit_on_err(&mut self, result: Result<(), ExitReason>) -> Result<(), ExitReason> {
+		if let Err(e) = &result {
+			self.machine.exit(e.clone());
+			self.status = Err(e.clone());
+		}
+		result
+	}
 }
 
 /// Runtime configuration.
@@ -253,6 +422,34 @@ pub struct Config {
 	pub has_ext_code_hash: bool,
 	/// Has ext block fee. See [EIP-3198](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-3198.md)
 	pub has_base_fee: bool,
+	/// Has PREVRANDAO in place of DIFFICULTY. See [EIP-4399](https://eips.ethereum.org/EIPS/eip-4399)
+	pub has_prevrandao: bool,
+	/// Has PUSH0. See [EIP-3855](https://eips.ethereum.org/EIPS/eip-3855)
+	pub has_push0: bool,
+	/// Whether the coinbase is warm at the start of a transaction. See
+	/// [EIP-3651](https://eips.ethereum.org/EIPS/eip-3651)
+	pub warm_coinbase_address: bool,
+	/// Init code size limit, with init code metering. See
+	/// [EIP-3860](https://eips.ethereum.org/EIPS/eip-3860)
+	pub max_initcode_size: Option<usize>,
+	/// Has transient storage. See [EIP-1153](https://eips.ethereum.org/EIPS/eip-1153)
+	pub has_transient_storage: bool,
+	/// Has MCOPY. See [EIP-5656](https://eips.ethereum.org/EIPS/eip-5656)
+	pub has_mcopy: bool,
+	/// Has BLOBHASH. See [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844)
+	pub has_blob_hash: bool,
+	/// Has BLOBBASEFEE. See [EIP-7516](https://eips.ethereum.org/EIPS/eip-7516)
+	pub has_blob_base_fee: bool,
+	/// Whether SUICIDE only deletes accounts created in the same transaction.
+	/// See [EIP-6780](https://eips.ethereum.org/EIPS/eip-6780)
+	pub suicide_only_in_same_tx: bool,
+	/// Whether code starting with `0xEF00` is an EVM Object Format container,
+	/// validated at deploy time. See [EIP-3540](https://eips.ethereum.org/EIPS/eip-3540),
+	/// [EIP-3670](https://eips.ethereum.org/EIPS/eip-3670),
+	/// [EIP-4200](https://eips.ethereum.org/EIPS/eip-4200),
+	/// [EIP-4750](https://eips.ethereum.org/EIPS/eip-4750) and
+	/// [EIP-5450](https://eips.ethereum.org/EIPS/eip-5450)
+	pub has_eof: bool,
 	/// Whether the gasometer is running in estimate mode.
 	pub estimate: bool,
 }
@@ -305,6 +502,16 @@ impl Config {
 			has_self_balance: false,
 			has_ext_code_hash: false,
 			has_base_fee: false,
+			has_prevrandao: false,
+			has_push0: false,
+			warm_coinbase_address: false,
+			max_initcode_size: None,
+			has_transient_storage: false,
+			has_mcopy: false,
+			has_blob_hash: false,
+			has_blob_base_fee: false,
+			suicide_only_in_same_tx: false,
+			has_eof: false,
 			estimate: false,
 		}
 	}
@@ -356,6 +563,16 @@ impl Config {
 			has_self_balance: true,
 			has_ext_code_hash: true,
 			has_base_fee: false,
+			has_prevrandao: false,
+			has_push0: false,
+			warm_coinbase_address: false,
+			max_initcode_size: None,
+			has_transient_storage: false,
+			has_mcopy: false,
+			has_blob_hash: false,
+			has_blob_base_fee: false,
+			suicide_only_in_same_tx: false,
+			has_eof: false,
 			estimate: false,
 		}
 	}
@@ -370,6 +587,30 @@ impl Config {
 		Self::config_with_derived_values(DerivedConfigInputs::london())
 	}
 
+	/// The Merge (Paris) hard fork configuration.
+	pub const fn merge() -> Config {
+		Self::config_with_derived_values(DerivedConfigInputs::merge())
+	}
+
+	/// Shanghai hard fork configuration.
+	pub const fn shanghai() -> Config {
+		Self::config_with_derived_values(DerivedConfigInputs::shanghai())
+	}
+
+	/// Cancun hard fork configuration.
+	///
+	/// The standard precompile set for Cancun is incomplete: the KZG point
+	/// evaluation at `0x0a` (EIP-4844) exits with `ExitFatal::NotSupported`.
+	pub const fn cancun() -> Config {
+		Self::config_with_derived_values(DerivedConfigInputs::cancun())
+	}
+
+	/// Cancun hard fork configuration, with the EVM Object Format enabled.
+	/// No mainnet hard fork schedules this version of the format.
+	pub const fn cancun_with_eof() -> Config {
+		Self::config_with_derived_values(DerivedConfigInputs::cancun_with_eof())
+	}
+
 	const fn config_with_derived_values(inputs: DerivedConfigInputs) -> Config {
 		let DerivedConfigInputs {
 			gas_storage_read_warm,
@@ -378,6 +619,16 @@ impl Config {
 			decrease_clears_refund,
 			has_base_fee,
 			disallow_executable_format,
+			has_prevrandao,
+			has_push0,
+			warm_coinbase_address,
+			max_initcode_size,
+			has_transient_storage,
+			has_mcopy,
+			has_blob_hash,
+			has_blob_base_fee,
+			suicide_only_in_same_tx,
+			has_eof,
 		} = inputs;
 
 		// See https://eips.ethereum.org/EIPS/eip-2929
@@ -437,6 +688,16 @@ impl Config {
 			has_self_balance: true,
 			has_ext_code_hash: true,
 			has_base_fee,
+			has_prevrandao,
+			has_push0,
+			warm_coinbase_address,
+			max_initcode_size,
+			has_transient_storage,
+			has_mcopy,
+			has_blob_hash,
+			has_blob_base_fee,
+			suicide_only_in_same_tx,
+			has_eof,
 			estimate: false,
 		}
 	}
@@ -451,6 +712,16 @@ struct DerivedConfigInputs {
 	decrease_clears_refund: bool,
 	has_base_fee: bool,
 	disallow_executable_format: bool,
+	has_prevrandao: bool,
+	has_push0: bool,
+	warm_coinbase_address: bool,
+	max_initcode_size: Option<usize>,
+	has_transient_storage: bool,
+	has_mcopy: bool,
+	has_blob_hash: bool,
+	has_blob_base_fee: bool,
+	suicide_only_in_same_tx: bool,
+	has_eof: bool,
 }
 
 impl DerivedConfigInputs {
@@ -462,6 +733,16 @@ impl DerivedConfigInputs {
 			decrease_clears_refund: false,
 			has_base_fee: false,
 			disallow_executable_format: false,
+			has_prevrandao: false,
+			has_push0: false,
+			warm_coinbase_address: false,
+			max_initcode_size: None,
+			has_transient_storage: false,
+			has_mcopy: false,
+			has_blob_hash: false,
+			has_blob_base_fee: false,
+			suicide_only_in_same_tx: false,
+			has_eof: false,
 		}
 	}
 
@@ -473,6 +754,47 @@ impl DerivedConfigInputs {
 			decrease_clears_refund: true,
 			has_base_fee: true,
 			disallow_executable_format: true,
+			has_prevrandao: false,
+			has_push0: false,
+			warm_coinbase_address: false,
+			max_initcode_size: None,
+			has_transient_storage: false,
+			has_mcopy: false,
+			has_blob_hash: false,
+			has_blob_base_fee: false,
+			suicide_only_in_same_tx: false,
+			has_eof: false,
 		}
 	}
+
+	const fn merge() -> Self {
+		let mut config = Self::london();
+		config.has_prevrandao = true;
+		config
+	}
+
+	const fn shanghai() -> Self {
+		let mut config = Self::merge();
+		config.has_push0 = true;
+		config.warm_coinbase_address = true;
+		// 2 * 24576 as per EIP-3860
+		config.max_initcode_size = Some(0xC000);
+		config
+	}
+
+	const fn cancun() -> Self {
+		let mut config = Self::shanghai();
+		config.has_transient_storage = true;
+		config.has_mcopy = true;
+		config.has_blob_hash = true;
+		config.has_blob_base_fee = true;
+		config.suicide_only_in_same_tx = true;
+		config
+	}
+
+	const fn cancun_with_eof() -> Self {
+		let mut config = Self::cancun();
+		config.has_eof = true;
+		config
+	}
 }
@@ -0,0 +1,247 @@
+use super::{recent_block_hash, Backend, Basic, MemoryBackend, MemoryVicinity};
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use primitive_types::{H160, H256, U256};
+
+/// Boxed future returned by an `AsyncStateProvider`.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Source of state that is fetched asynchronously, such as a database or a
+/// node queried over the network.
+pub trait AsyncStateProvider: Sync {
+	/// Basic account information.
+	fn basic(&self, address: H160) -> BoxFuture<'_, Basic>;
+	/// Account code.
+	fn code(&self, address: H160) -> BoxFuture<'_, Vec<u8>>;
+	/// Storage value of address at index.
+	fn storage(&self, address: H160, index: H256) -> BoxFuture<'_, H256>;
+}
+
+impl<'vicinity> AsyncStateProvider for MemoryBackend<'vicinity> {
+	fn basic(&self, address: H160) -> BoxFuture<'_, Basic> {
+		Box::pin(core::future::ready(Backend::basic(self, address)))
+	}
+	fn code(&self, address: H160) -> BoxFuture<'_, Vec<u8>> {
+		Box::pin(core::future::ready(Backend::code(self, address)))
+	}
+	fn storage(&self, address: H160, index: H256) -> BoxFuture<'_, H256> {
+		Box::pin(core::future::ready(Backend::storage(self, address, index)))
+	}
+}
+
+/// Value read by the EVM that is not yet fetched.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub(crate) enum PrefetchKey {
+	Basic(H160),
+	Code(H160),
+	Storage(H160, H256),
+}
+
+/// Values fetched from an `AsyncStateProvider`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PrefetchCache {
+	basic: BTreeMap<H160, Basic>,
+	code: BTreeMap<H160, Vec<u8>>,
+	storage: BTreeMap<(H160, H256), H256>,
+}
+
+impl PrefetchCache {
+	/// Fetch the missing values from the provider. All the requests are
+	/// sent at once and awaited together.
+	pub(crate) fn fetch<'a, A: AsyncStateProvider + ?Sized>(
+		&'a mut self,
+		provider: &'a A,
+		missing: BTreeSet<PrefetchKey>,
+	) -> Fetch<'a> {
+		let requests = missing
+			.into_iter()
+			.map(|key| {
+				Some(match key {
+					PrefetchKey::Basic(address) => Request::Basic(address, provider.basic(address)),
+					PrefetchKey::Code(address) => Request::Code(address, provider.code(address)),
+					PrefetchKey::Storage(address, index) => {
+						Request::Storage(address, index, provider.storage(address, index))
+					}
+				})
+			})
+			.collect();
+
+		Fetch {
+			cache: self,
+			requests,
+		}
+	}
+}
+
+/// Pending request to an `AsyncStateProvider`.
+enum Request<'a> {
+	Basic(H160, BoxFuture<'a, Basic>),
+	Code(H160, BoxFuture<'a, Vec<u8>>),
+	Storage(H160, H256, BoxFuture<'a, H256>),
+}
+
+/// Future of `PrefetchCache::fetch`, polling every request still pending
+/// each time it is polled, and storing the values in the cache as they
+/// arrive.
+pub(crate) struct Fetch<'a> {
+	cache: &'a mut PrefetchCache,
+	requests: Vec<Option<Request<'a>>>,
+}
+
+impl<'a> Future for Fetch<'a> {
+	type Output = ();
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+		let this = self.get_mut();
+		let cache = &mut *this.cache;
+		for slot in this.requests.iter_mut() {
+			let done = match slot {
+				Some(Request::Basic(address, future)) => match future.as_mut().poll(cx) {
+					Poll::Ready(basic) => {
+						cache.basic.insert(*address, basic);
+						true
+					}
+					Poll::Pending => false,
+				},
+				Some(Request::Code(address, future)) => match future.as_mut().poll(cx) {
+					Poll::Ready(code) => {
+						cache.code.insert(*address, code);
+						true
+					}
+					Poll::Pending => false,
+				},
+				Some(Request::Storage(address, index, future)) => match future.as_mut().poll(cx) {
+					Poll::Ready(value) => {
+						cache.storage.insert((*address, *index), value);
+						true
+					}
+					Poll::Pending => false,
+				},
+				None => false,
+			};
+			if done {
+				*slot = None;
+			}
+		}
+
+		if this.requests.iter().all(Option::is_none) {
+			Poll::Ready(())
+		} else {
+			Poll::Pending
+		}
+	}
+}
+
+/// Backend over the values already fetched from an `AsyncStateProvider`.
+///
+/// Values that are not fetched yet read as empty, and are recorded so that
+/// the execution can be run again once they are available. Block hashes are
+/// only the ones of the vicinity.
+#[derive(Debug)]
+pub struct PrefetchBackend<'a> {
+	vicinity: &'a MemoryVicinity,
+	cache: &'a PrefetchCache,
+	missing: RefCell<BTreeSet<PrefetchKey>>,
+}
+
+impl<'a> PrefetchBackend<'a> {
+	pub(crate) fn new(vicinity: &'a MemoryVicinity, cache: &'a PrefetchCache) -> Self {
+		Self {
+			vicinity,
+			cache,
+			missing: RefCell::new(BTreeSet::new()),
+		}
+	}
+
+	pub(crate) fn into_missing(self) -> BTreeSet<PrefetchKey> {
+		self.missing.into_inner()
+	}
+
+	fn get<T: Clone + Default>(&self, value: Option<&T>, key: PrefetchKey) -> T {
+		match value {
+			Some(value) => value.clone(),
+			None => {
+				self.missing.borrow_mut().insert(key);
+				T::default()
+			}
+		}
+	}
+}
+
+impl<'a> Backend for PrefetchBackend<'a> {
+	fn gas_price(&self) -> U256 {
+		self.vicinity.gas_price
+	}
+	fn origin(&self) -> H160 {
+		self.vicinity.origin
+	}
+	fn block_hash(&self, number: U256) -> H256 {
+		recent_block_hash(
+			&self.vicinity.block_hashes,
+			self.vicinity.block_number,
+			number,
+		)
+		.unwrap_or_default()
+	}
+	fn block_number(&self) -> U256 {
+		self.vicinity.block_number
+	}
+	fn block_coinbase(&self) -> H160 {
+		self.vicinity.block_coinbase
+	}
+	fn block_timestamp(&self) -> U256 {
+		self.vicinity.block_timestamp
+	}
+	fn block_difficulty(&self) -> U256 {
+		self.vicinity.block_difficulty
+	}
+	fn block_gas_limit(&self) -> U256 {
+		self.vicinity.block_gas_limit
+	}
+	fn block_base_fee_per_gas(&self) -> U256 {
+		self.vicinity.block_base_fee_per_gas
+	}
+	fn block_randomness(&self) -> Option<H256> {
+		self.vicinity.block_randomness
+	}
+	fn block_blob_base_fee(&self) -> U256 {
+		self.vicinity.block_blob_base_fee
+	}
+	fn blob_hash(&self, index: usize) -> Option<H256> {
+		self.vicinity.blob_hashes.get(index).copied()
+	}
+
+	fn chain_id(&self) -> U256 {
+		self.vicinity.chain_id
+	}
+
+	fn exists(&self, address: H160) -> bool {
+		let basic = self.basic(address);
+		let code = self.code(address);
+		basic.balance != U256::zero() || basic.nonce != U256::zero() || !code.is_empty()
+	}
+
+	fn basic(&self, address: H160) -> Basic {
+		self.get(self.cache.basic.get(&address), PrefetchKey::Basic(address))
+	}
+
+	fn code(&self, address: H160) -> Vec<u8> {
+		self.get(self.cache.code.get(&address), PrefetchKey::Code(address))
+	}
+
+	fn storage(&self, address: H160, index: H256) -> H256 {
+		self.get(
+			self.cache.storage.get(&(address, index)),
+			PrefetchKey::Storage(address, index),
+		)
+	}
+
+	fn original_storage(&self, address: H160, index: H256) -> Option<H256> {
+		Some(self.storage(address, index))
+	}
+}
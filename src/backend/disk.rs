@@ -0,0 +1,516 @@
+use super::{
+	recent_block_hash, Apply, ApplyBackend, Backend, Basic, Log, MemoryVicinity, EMPTY_CODE_HASH,
+};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use primitive_types::{H160, H256, U256};
+use sha3::{Digest, Keccak256};
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Batch of changes to a key-value store. A `None` value removes the key.
+pub type Batch = Vec<(Vec<u8>, Option<Vec<u8>>)>;
+
+/// Key-value store persisting the state of a `DiskBackend`.
+pub trait KeyValueStore {
+	/// Get the value stored at key.
+	fn get(&self, key: &[u8]) -> io::Result<Option<Vec<u8>>>;
+	/// Write a batch of changes at once: either all of them are persisted,
+	/// or none.
+	fn write(&mut self, batch: Batch) -> io::Result<()>;
+}
+
+impl KeyValueStore for BTreeMap<Vec<u8>, Vec<u8>> {
+	fn get(&self, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+		Ok(BTreeMap::get(self, key).cloned())
+	}
+
+	fn write(&mut self, batch: Batch) -> io::Result<()> {
+		for (key, value) in batch {
+			match value {
+				Some(value) => self.insert(key, value),
+				None => self.remove(&key),
+			};
+		}
+		Ok(())
+	}
+}
+
+const JOURNAL: &str = "journal";
+const JOURNAL_TMP: &str = "journal.tmp";
+
+/// Key-value store keeping each value in a file of a directory, named after
+/// its hex encoded key.
+///
+/// Batches are first written to a journal, which is replayed when the store
+/// is opened again after an interrupted write.
+#[derive(Clone, Debug)]
+pub struct FileStore {
+	path: PathBuf,
+}
+
+impl FileStore {
+	/// Open the store in a directory, creating it if needed.
+	pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+		let store = Self {
+			path: path.as_ref().to_path_buf(),
+		};
+		fs::create_dir_all(&store.path)?;
+
+		match fs::remove_file(store.path.join(JOURNAL_TMP)) {
+			Err(error) if error.kind() != ErrorKind::NotFound => return Err(error),
+			_ => (),
+		}
+		match fs::read(store.path.join(JOURNAL)) {
+			Ok(journal) => {
+				let batch = decode_batch(&journal)?;
+				store.commit(batch)?;
+			}
+			Err(error) if error.kind() != ErrorKind::NotFound => return Err(error),
+			Err(_) => (),
+		}
+
+		Ok(store)
+	}
+
+	fn key_path(&self, key: &[u8]) -> PathBuf {
+		let mut name = String::with_capacity(key.len() * 2 + 4);
+		for byte in key {
+			name.push(char::from(b"0123456789abcdef"[usize::from(byte >> 4)]));
+			name.push(char::from(b"0123456789abcdef"[usize::from(byte & 0x0f)]));
+		}
+		name.push_str(".val");
+		self.path.join(name)
+	}
+
+	/// Flush the entries of the store directory to disk.
+	fn sync_dir(&self) -> io::Result<()> {
+		#[cfg(unix)]
+		File::open(&self.path)?.sync_all()?;
+		Ok(())
+	}
+
+	/// Apply a journaled batch to the value files, then drop the journal.
+	/// Each value is synced to a temporary file renamed in place, and the
+	/// journal is only dropped once the directory is synced, so that a crash
+	/// leaves either the old or the new value and the journal to replay.
+	fn commit(&self, batch: Batch) -> io::Result<()> {
+		for (key, value) in batch {
+			let path = self.key_path(&key);
+			match value {
+				Some(value) => {
+					let tmp = path.with_extension("tmp");
+					let mut file = File::create(&tmp)?;
+					file.write_all(&value)?;
+					file.sync_all()?;
+					fs::rename(tmp, path)?;
+				}
+				None => match fs::remove_file(path) {
+					Err(error) if error.kind() != ErrorKind::NotFound => return Err(error),
+					_ => (),
+				},
+			}
+		}
+		self.sync_dir()?;
+		fs::remove_file(self.path.join(JOURNAL))
+	}
+}
+
+impl KeyValueStore for FileStore {
+	fn get(&self, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+		match File::open(self.key_path(key)) {
+			Ok(mut file) => {
+				let mut value = Vec::new();
+				file.read_to_end(&mut value)?;
+				Ok(Some(value))
+			}
+			Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+			Err(error) => Err(error),
+		}
+	}
+
+	fn write(&mut self, batch: Batch) -> io::Result<()> {
+		// The batch is committed once the journal is renamed in place.
+		let mut journal = File::create(self.path.join(JOURNAL_TMP))?;
+		journal.write_all(&encode_batch(&batch))?;
+		journal.sync_all()?;
+		fs::rename(self.path.join(JOURNAL_TMP), self.path.join(JOURNAL))?;
+		self.sync_dir()?;
+
+		self.commit(batch)
+	}
+}
+
+fn encode_batch(batch: &[(Vec<u8>, Option<Vec<u8>>)]) -> Vec<u8> {
+	let mut encoded = Vec::new();
+	for (key, value) in batch {
+		encoded.extend_from_slice(&(key.len() as u32).to_be_bytes());
+		encoded.extend_from_slice(key);
+		match value {
+			Some(value) => {
+				encoded.push(1);
+				encoded.extend_from_slice(&(value.len() as u32).to_be_bytes());
+				encoded.extend_from_slice(value);
+			}
+			None => encoded.push(0),
+		}
+	}
+	encoded
+}
+
+fn decode_batch(mut encoded: &[u8]) -> io::Result<Batch> {
+	fn take<'a>(encoded: &mut &'a [u8], len: usize) -> io::Result<&'a [u8]> {
+		if encoded.len() < len {
+			return Err(io::Error::new(ErrorKind::InvalidData, "truncated journal"));
+		}
+		let (taken, rest) = encoded.split_at(len);
+		*encoded = rest;
+		Ok(taken)
+	}
+	fn take_len(encoded: &mut &[u8]) -> io::Result<usize> {
+		let mut len = [0u8; 4];
+		len.copy_from_slice(take(encoded, 4)?);
+		Ok(u32::from_be_bytes(len) as usize)
+	}
+
+	let mut batch = Vec::new();
+	while !encoded.is_empty() {
+		let len = take_len(&mut encoded)?;
+		let key = take(&mut encoded, len)?.to_vec();
+		let value = match take(&mut encoded, 1)?[0] {
+			0 => None,
+			_ => {
+				let len = take_len(&mut encoded)?;
+				Some(take(&mut encoded, len)?.to_vec())
+			}
+		};
+		batch.push((key, value));
+	}
+	Ok(batch)
+}
+
+const ACCOUNT_PREFIX: u8 = b'a';
+const CODE_PREFIX: u8 = b'c';
+const GENERATION_PREFIX: u8 = b'g';
+const STORAGE_PREFIX: u8 = b's';
+
+fn account_key(address: H160) -> Vec<u8> {
+	[&[ACCOUNT_PREFIX][..], address.as_bytes()].concat()
+}
+
+fn code_key(code_hash: H256) -> Vec<u8> {
+	[&[CODE_PREFIX][..], code_hash.as_bytes()].concat()
+}
+
+fn generation_key(address: H160) -> Vec<u8> {
+	[&[GENERATION_PREFIX][..], address.as_bytes()].concat()
+}
+
+fn storage_key(address: H160, generation: u64, index: H256) -> Vec<u8> {
+	[
+		&[STORAGE_PREFIX][..],
+		address.as_bytes(),
+		&generation.to_be_bytes(),
+		index.as_bytes(),
+	]
+	.concat()
+}
+
+/// Account as stored by a `DiskBackend`, referring to its code by hash.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct DiskAccount {
+	nonce: U256,
+	balance: U256,
+	code_hash: H256,
+}
+
+impl DiskAccount {
+	fn encode(&self) -> Vec<u8> {
+		let mut encoded = [0u8; 96];
+		self.nonce.to_big_endian(&mut encoded[..32]);
+		self.balance.to_big_endian(&mut encoded[32..64]);
+		encoded[64..].copy_from_slice(self.code_hash.as_bytes());
+		encoded.to_vec()
+	}
+
+	fn decode(encoded: &[u8]) -> io::Result<Self> {
+		if encoded.len() != 96 {
+			return Err(corrupted("account"));
+		}
+		Ok(Self {
+			nonce: U256::from_big_endian(&encoded[..32]),
+			balance: U256::from_big_endian(&encoded[32..64]),
+			code_hash: H256::from_slice(&encoded[64..]),
+		})
+	}
+}
+
+fn corrupted(record: &str) -> io::Error {
+	io::Error::new(
+		ErrorKind::InvalidData,
+		format!("corrupted {} record", record),
+	)
+}
+
+/// Persistent backend, loading accounts, code and storage from a key-value
+/// store when they are accessed.
+///
+/// Code is stored once per code hash. Wiping the storage of an account bumps
+/// its storage generation instead of removing every slot, and the slots of
+/// earlier generations are left unreachable in the store. Logs are not
+/// stored.
+///
+/// Changes should be written with `try_apply`, which returns the errors of
+/// the store. As the `Backend` and `ApplyBackend` traits cannot return
+/// errors, their methods panic if the store fails or holds a corrupted
+/// record.
+#[derive(Clone, Debug)]
+pub struct DiskBackend<'vicinity, S> {
+	vicinity: &'vicinity MemoryVicinity,
+	store: S,
+}
+
+impl<'vicinity, S: KeyValueStore> DiskBackend<'vicinity, S> {
+	/// Create a new backend on top of a key-value store.
+	pub fn new(vicinity: &'vicinity MemoryVicinity, store: S) -> Self {
+		Self { vicinity, store }
+	}
+
+	/// Get the underlying key-value store.
+	pub fn store(&self) -> &S {
+		&self.store
+	}
+
+	/// Consume the backend, returning the underlying key-value store.
+	pub fn into_store(self) -> S {
+		self.store
+	}
+
+	/// Get the result of a read for the `Backend` methods, which cannot
+	/// return errors.
+	fn get<T>(result: io::Result<T>) -> T {
+		result.unwrap_or_else(|error| panic!("failed to read the backend store: {}", error))
+	}
+
+	fn read_account(&self, address: H160) -> io::Result<Option<DiskAccount>> {
+		self.store
+			.get(&account_key(address))?
+			.map(|encoded| DiskAccount::decode(&encoded))
+			.transpose()
+	}
+
+	fn read_generation(&self, address: H160) -> io::Result<u64> {
+		match self.store.get(&generation_key(address))? {
+			Some(encoded) if encoded.len() == 8 => {
+				let mut generation = [0u8; 8];
+				generation.copy_from_slice(&encoded);
+				Ok(u64::from_be_bytes(generation))
+			}
+			Some(_) => Err(corrupted("storage generation")),
+			None => Ok(0),
+		}
+	}
+
+	fn read_code(&self, address: H160) -> io::Result<Vec<u8>> {
+		match self.read_account(address)? {
+			Some(account) if account.code_hash != EMPTY_CODE_HASH => self
+				.store
+				.get(&code_key(account.code_hash))?
+				.ok_or_else(|| corrupted("code")),
+			_ => Ok(Vec::new()),
+		}
+	}
+
+	fn read_storage(&self, address: H160, index: H256) -> io::Result<H256> {
+		let key = storage_key(address, self.read_generation(address)?, index);
+		match self.store.get(&key)? {
+			Some(value) if value.len() == 32 => Ok(H256::from_slice(&value)),
+			Some(_) => Err(corrupted("storage")),
+			None => Ok(H256::default()),
+		}
+	}
+
+	/// Apply changes to the store in a single batch, so that either all of
+	/// them are persisted or none. Returns the error of the store if reading
+	/// or writing failed, or if it holds a corrupted record.
+	pub fn try_apply<A, I>(&mut self, values: A, delete_empty: bool) -> io::Result<()>
+	where
+		A: IntoIterator<Item = Apply<I>>,
+		I: IntoIterator<Item = (H256, H256)>,
+	{
+		let mut batch = BTreeMap::<Vec<u8>, Option<Vec<u8>>>::new();
+		let mut generations = BTreeMap::<H160, u64>::new();
+
+		for apply in values {
+			match apply {
+				Apply::Modify {
+					address,
+					basic,
+					code,
+					storage,
+					reset_storage,
+				} => {
+					let code_hash = match code {
+						Some(code) => {
+							let code_hash = H256::from_slice(&Keccak256::digest(&code));
+							if code_hash != EMPTY_CODE_HASH {
+								batch.insert(code_key(code_hash), Some(code));
+							}
+							code_hash
+						}
+						None => match batch.get(&account_key(address)) {
+							Some(account) => account
+								.as_ref()
+								.map(|encoded| DiskAccount::decode(encoded))
+								.transpose()?,
+							None => self.read_account(address)?,
+						}
+						.map(|account| account.code_hash)
+						.unwrap_or(EMPTY_CODE_HASH),
+					};
+
+					let mut generation = match generations.get(&address) {
+						Some(generation) => *generation,
+						None => self.read_generation(address)?,
+					};
+					if reset_storage {
+						generation += 1;
+						generations.insert(address, generation);
+					}
+					for (index, value) in storage {
+						let key = storage_key(address, generation, index);
+						if value == H256::default() {
+							batch.insert(key, None);
+						} else {
+							batch.insert(key, Some(value.as_bytes().to_vec()));
+						}
+					}
+
+					let is_empty = basic.balance == U256::zero()
+						&& basic.nonce == U256::zero()
+						&& code_hash == EMPTY_CODE_HASH;
+					if is_empty && delete_empty {
+						batch.insert(account_key(address), None);
+						generations.insert(address, generation + 1);
+					} else {
+						let account = DiskAccount {
+							nonce: basic.nonce,
+							balance: basic.balance,
+							code_hash,
+						};
+						batch.insert(account_key(address), Some(account.encode()));
+					}
+				}
+				Apply::Delete { address } => {
+					let generation = match generations.get(&address) {
+						Some(generation) => *generation,
+						None => self.read_generation(address)?,
+					};
+					batch.insert(account_key(address), None);
+					generations.insert(address, generation + 1);
+				}
+			}
+		}
+
+		for (address, generation) in generations {
+			batch.insert(
+				generation_key(address),
+				Some(generation.to_be_bytes().to_vec()),
+			);
+		}
+
+		self.store.write(batch.into_iter().collect())
+	}
+}
+
+impl<'vicinity, S: KeyValueStore> Backend for DiskBackend<'vicinity, S> {
+	fn gas_price(&self) -> U256 {
+		self.vicinity.gas_price
+	}
+	fn origin(&self) -> H160 {
+		self.vicinity.origin
+	}
+	fn block_hash(&self, number: U256) -> H256 {
+		recent_block_hash(
+			&self.vicinity.block_hashes,
+			self.vicinity.block_number,
+			number,
+		)
+		.unwrap_or_default()
+	}
+	fn block_number(&self) -> U256 {
+		self.vicinity.block_number
+	}
+	fn block_coinbase(&self) -> H160 {
+		self.vicinity.block_coinbase
+	}
+	fn block_timestamp(&self) -> U256 {
+		self.vicinity.block_timestamp
+	}
+	fn block_difficulty(&self) -> U256 {
+		self.vicinity.block_difficulty
+	}
+	fn block_gas_limit(&self) -> U256 {
+		self.vicinity.block_gas_limit
+	}
+	fn block_base_fee_per_gas(&self) -> U256 {
+		self.vicinity.block_base_fee_per_gas
+	}
+	fn block_randomness(&self) -> Option<H256> {
+		self.vicinity.block_randomness
+	}
+	fn block_blob_base_fee(&self) -> U256 {
+		self.vicinity.block_blob_base_fee
+	}
+	fn blob_hash(&self, index: usize) -> Option<H256> {
+		self.vicinity.blob_hashes.get(index).copied()
+	}
+
+	fn chain_id(&self) -> U256 {
+		self.vicinity.chain_id
+	}
+
+	fn exists(&self, address: H160) -> bool {
+		Self::get(self.read_account(address)).is_some()
+	}
+
+	fn basic(&self, address: H160) -> Basic {
+		Self::get(self.read_account(address))
+			.map(|a| Basic {
+				balance: a.balance,
+				nonce: a.nonce,
+			})
+			.unwrap_or_default()
+	}
+
+	fn code(&self, address: H160) -> Vec<u8> {
+		Self::get(self.read_code(address))
+	}
+
+	fn code_hash(&self, address: H160) -> H256 {
+		Self::get(self.read_account(address))
+			.map(|account| account.code_hash)
+			.unwrap_or(EMPTY_CODE_HASH)
+	}
+
+	fn storage(&self, address: H160, index: H256) -> H256 {
+		Self::get(self.read_storage(address, index))
+	}
+
+	fn original_storage(&self, address: H160, index: H256) -> Option<H256> {
+		Some(self.storage(address, index))
+	}
+}
+
+impl<'vicinity, S: KeyValueStore> ApplyBackend for DiskBackend<'vicinity, S> {
+	fn apply<A, I, L>(&mut self, values: A, _logs: L, delete_empty: bool)
+	where
+		A: IntoIterator<Item = Apply<I>>,
+		I: IntoIterator<Item = (H256, H256)>,
+		L: IntoIterator<Item = Log>,
+	{
+		self.try_apply(values, delete_empty)
+			.unwrap_or_else(|error| panic!("failed to write the backend store: {}", error))
+	}
+}
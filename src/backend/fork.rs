@@ -0,0 +1,328 @@
+use super::{
+	recent_block_hash, Apply, ApplyBackend, Backend, Basic, Log, MemoryBackend, MemoryVicinity,
+};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use primitive_types::{H160, H256, U256};
+
+/// Source of the state of a chain at a pinned block, such as a node queried
+/// over JSON-RPC or a local fixture.
+#[auto_impl::auto_impl(&, Arc, Box)]
+pub trait StateProvider {
+	/// Basic account information.
+	fn basic(&self, address: H160) -> Basic;
+	/// Account code.
+	fn code(&self, address: H160) -> Vec<u8>;
+	/// Storage value of address at index.
+	fn storage(&self, address: H160, index: H256) -> H256;
+	/// Hash of a block before the pinned block.
+	fn block_hash(&self, number: U256) -> H256;
+	/// Whether account at address exists. By default, whether it has a
+	/// balance, a nonce or code.
+	fn exists(&self, address: H160) -> bool {
+		let basic = self.basic(address);
+		basic.balance != U256::zero()
+			|| basic.nonce != U256::zero()
+			|| !self.code(address).is_empty()
+	}
+}
+
+impl<'vicinity> StateProvider for MemoryBackend<'vicinity> {
+	fn basic(&self, address: H160) -> Basic {
+		Backend::basic(self, address)
+	}
+	fn code(&self, address: H160) -> Vec<u8> {
+		Backend::code(self, address)
+	}
+	fn storage(&self, address: H160, index: H256) -> H256 {
+		Backend::storage(self, address, index)
+	}
+	fn block_hash(&self, number: U256) -> H256 {
+		Backend::block_hash(self, number)
+	}
+	fn exists(&self, address: H160) -> bool {
+		Backend::exists(self, address)
+	}
+}
+
+/// Values fetched from the state provider.
+#[derive(Clone, Debug, Default)]
+struct ForkCache {
+	exists: BTreeMap<H160, bool>,
+	basic: BTreeMap<H160, Basic>,
+	code: BTreeMap<H160, Vec<u8>>,
+	storage: BTreeMap<(H160, H256), H256>,
+	block_hashes: BTreeMap<U256, H256>,
+}
+
+/// Account written locally, over the upstream state.
+#[derive(Clone, Debug)]
+struct ForkAccount {
+	basic: Basic,
+	/// Code, or `None` for the upstream code.
+	code: Option<Vec<u8>>,
+	storage: BTreeMap<H256, H256>,
+	/// Whether the upstream storage is wiped.
+	reset_storage: bool,
+}
+
+/// Forking backend, reading the state of a chain from a `StateProvider`
+/// and applying changes locally on top of it.
+///
+/// Every value fetched from the provider is cached, so that each one is
+/// fetched at most once.
+#[derive(Clone, Debug)]
+pub struct ForkBackend<'vicinity, P> {
+	vicinity: &'vicinity MemoryVicinity,
+	provider: P,
+	cache: RefCell<ForkCache>,
+	/// Local accounts, `None` for deleted ones.
+	local: BTreeMap<H160, Option<ForkAccount>>,
+	logs: Vec<Log>,
+}
+
+impl<'vicinity, P: StateProvider> ForkBackend<'vicinity, P> {
+	/// Create a new forking backend. Block hashes of the vicinity take
+	/// precedence over the ones of the provider.
+	pub fn new(vicinity: &'vicinity MemoryVicinity, provider: P) -> Self {
+		Self {
+			vicinity,
+			provider,
+			cache: RefCell::new(ForkCache::default()),
+			local: BTreeMap::new(),
+			logs: Vec::new(),
+		}
+	}
+
+	/// Get the underlying state provider.
+	pub fn provider(&self) -> &P {
+		&self.provider
+	}
+
+	/// Logs applied to the backend.
+	pub fn logs(&self) -> &[Log] {
+		&self.logs
+	}
+
+	fn upstream_basic(&self, address: H160) -> Basic {
+		if let Some(basic) = self.cache.borrow().basic.get(&address) {
+			return basic.clone();
+		}
+		let basic = self.provider.basic(address);
+		self.cache.borrow_mut().basic.insert(address, basic.clone());
+		basic
+	}
+
+	fn upstream_code(&self, address: H160) -> Vec<u8> {
+		if let Some(code) = self.cache.borrow().code.get(&address) {
+			return code.clone();
+		}
+		let code = self.provider.code(address);
+		self.cache.borrow_mut().code.insert(address, code.clone());
+		code
+	}
+
+	fn upstream_storage(&self, address: H160, index: H256) -> H256 {
+		if let Some(value) = self.cache.borrow().storage.get(&(address, index)) {
+			return *value;
+		}
+		let value = self.provider.storage(address, index);
+		self.cache
+			.borrow_mut()
+			.storage
+			.insert((address, index), value);
+		value
+	}
+}
+
+impl<'vicinity, P: StateProvider> Backend for ForkBackend<'vicinity, P> {
+	fn gas_price(&self) -> U256 {
+		self.vicinity.gas_price
+	}
+	fn origin(&self) -> H160 {
+		self.vicinity.origin
+	}
+	fn block_hash(&self, number: U256) -> H256 {
+		if number >= self.vicinity.block_number
+			|| self.vicinity.block_number - number > U256::from(256)
+		{
+			return H256::default();
+		}
+		if let Some(hash) = recent_block_hash(
+			&self.vicinity.block_hashes,
+			self.vicinity.block_number,
+			number,
+		) {
+			return hash;
+		}
+
+		if let Some(hash) = self.cache.borrow().block_hashes.get(&number) {
+			return *hash;
+		}
+		let hash = self.provider.block_hash(number);
+		self.cache.borrow_mut().block_hashes.insert(number, hash);
+		hash
+	}
+	fn block_number(&self) -> U256 {
+		self.vicinity.block_number
+	}
+	fn block_coinbase(&self) -> H160 {
+		self.vicinity.block_coinbase
+	}
+	fn block_timestamp(&self) -> U256 {
+		self.vicinity.block_timestamp
+	}
+	fn block_difficulty(&self) -> U256 {
+		self.vicinity.block_difficulty
+	}
+	fn block_gas_limit(&self) -> U256 {
+		self.vicinity.block_gas_limit
+	}
+	fn block_base_fee_per_gas(&self) -> U256 {
+		self.vicinity.block_base_fee_per_gas
+	}
+	fn block_randomness(&self) -> Option<H256> {
+		self.vicinity.block_randomness
+	}
+	fn block_blob_base_fee(&self) -> U256 {
+		self.vicinity.block_blob_base_fee
+	}
+	fn blob_hash(&self, index: usize) -> Option<H256> {
+		self.vicinity.blob_hashes.get(index).copied()
+	}
+
+	fn chain_id(&self) -> U256 {
+		self.vicinity.chain_id
+	}
+
+	fn exists(&self, address: H160) -> bool {
+		match self.local.get(&address) {
+			Some(account) => account.is_some(),
+			None => {
+				if let Some(exists) = self.cache.borrow().exists.get(&address) {
+					return *exists;
+				}
+				let exists = self.provider.exists(address);
+				self.cache.borrow_mut().exists.insert(address, exists);
+				exists
+			}
+		}
+	}
+
+	fn basic(&self, address: H160) -> Basic {
+		match self.local.get(&address) {
+			Some(Some(account)) => account.basic.clone(),
+			Some(None) => Basic::default(),
+			None => self.upstream_basic(address),
+		}
+	}
+
+	fn code(&self, address: H160) -> Vec<u8> {
+		match self.local.get(&address) {
+			Some(Some(ForkAccount {
+				code: Some(code), ..
+			})) => code.clone(),
+			Some(Some(_)) | None => self.upstream_code(address),
+			Some(None) => Vec::new(),
+		}
+	}
+
+	fn storage(&self, address: H160, index: H256) -> H256 {
+		match self.local.get(&address) {
+			Some(Some(account)) => match account.storage.get(&index) {
+				Some(value) => *value,
+				None if account.reset_storage => H256::default(),
+				None => self.upstream_storage(address, index),
+			},
+			Some(None) => H256::default(),
+			None => self.upstream_storage(address, index),
+		}
+	}
+
+	fn original_storage(&self, address: H160, index: H256) -> Option<H256> {
+		Some(self.storage(address, index))
+	}
+}
+
+impl<'vicinity, P: StateProvider> ApplyBackend for ForkBackend<'vicinity, P> {
+	fn apply<A, I, L>(&mut self, values: A, logs: L, delete_empty: bool)
+	where
+		A: IntoIterator<Item = Apply<I>>,
+		I: IntoIterator<Item = (H256, H256)>,
+		L: IntoIterator<Item = Log>,
+	{
+		for apply in values {
+			match apply {
+				Apply::Modify {
+					address,
+					basic,
+					code,
+					storage,
+					reset_storage,
+				} => {
+					let is_empty = {
+						let account = match self.local.get(&address) {
+							Some(Some(_)) => None,
+							// A deleted account is recreated with empty code and storage.
+							Some(None) => Some(ForkAccount {
+								basic: Basic::default(),
+								code: Some(Vec::new()),
+								storage: BTreeMap::new(),
+								reset_storage: true,
+							}),
+							None => Some(ForkAccount {
+								basic: Basic::default(),
+								code: None,
+								storage: BTreeMap::new(),
+								reset_storage: false,
+							}),
+						};
+						if let Some(account) = account {
+							self.local.insert(address, Some(account));
+						}
+						let is_empty_code = code.as_ref().map(|code| code.is_empty());
+						let is_empty_code = match is_empty_code {
+							Some(is_empty_code) => is_empty_code,
+							None => self.code(address).is_empty(),
+						};
+
+						let account = self
+							.local
+							.get_mut(&address)
+							.and_then(Option::as_mut)
+							.expect("account was just inserted; qed");
+						account.basic = basic.clone();
+						if let Some(code) = code {
+							account.code = Some(code);
+						}
+
+						if reset_storage {
+							account.storage = BTreeMap::new();
+							account.reset_storage = true;
+						}
+
+						for (index, value) in storage {
+							account.storage.insert(index, value);
+						}
+
+						basic.balance == U256::zero()
+							&& basic.nonce == U256::zero()
+							&& is_empty_code
+					};
+
+					if is_empty && delete_empty {
+						self.local.insert(address, None);
+					}
+				}
+				Apply::Delete { address } => {
+					self.local.insert(address, None);
+				}
+			}
+		}
+
+		for log in logs {
+			self.logs.push(log);
+		}
+	}
+}
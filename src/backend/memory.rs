@@ -1,7 +1,9 @@
-use super::{Apply, ApplyBackend, Backend, Basic, Log};
+use super::trie::sec_trie_root;
+use super::{recent_block_hash, Apply, ApplyBackend, Backend, Basic, Log};
 use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
 use primitive_types::{H160, H256, U256};
+use sha3::{Digest, Keccak256};
 
 /// Vivinity value of a memory backend.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -31,6 +33,35 @@ pub struct MemoryVicinity {
 	pub block_gas_limit: U256,
 	/// Environmental base fee per gas.
 	pub block_base_fee_per_gas: U256,
+	/// Environmental randomness, after the Merge.
+	pub block_randomness: Option<H256>,
+	/// Environmental blob base fee.
+	pub block_blob_base_fee: U256,
+	/// Versioned hashes of the blobs of the transaction.
+	pub blob_hashes: Vec<H256>,
+}
+
+#[cfg(test)]
+impl MemoryVicinity {
+	/// Vicinity of a block on chain 1 with the given gas limit, and
+	/// everything else zero.
+	pub(crate) fn for_tests(block_gas_limit: u64) -> Self {
+		Self {
+			gas_price: U256::zero(),
+			origin: H160::default(),
+			chain_id: U256::one(),
+			block_hashes: Vec::new(),
+			block_number: U256::zero(),
+			block_coinbase: H160::default(),
+			block_timestamp: U256::zero(),
+			block_difficulty: U256::zero(),
+			block_gas_limit: U256::from(block_gas_limit),
+			block_base_fee_per_gas: U256::zero(),
+			block_randomness: None,
+			block_blob_base_fee: U256::zero(),
+			blob_hashes: Vec::new(),
+		}
+	}
 }
 
 /// Account information of a memory backend.
@@ -51,6 +82,28 @@ pub struct MemoryAccount {
 	pub code: Vec<u8>,
 }
 
+impl MemoryAccount {
+	/// Root hash of the account storage trie. Zero values are left out.
+	pub fn storage_root(&self) -> H256 {
+		sec_trie_root(
+			self.storage
+				.iter()
+				.filter(|(_, value)| **value != H256::default())
+				.map(|(index, value)| (index, rlp::encode(&U256::from_big_endian(&value[..])))),
+		)
+	}
+
+	/// RLP encoding of the account, as stored in the state trie.
+	pub fn rlp_bytes(&self) -> Vec<u8> {
+		let mut stream = rlp::RlpStream::new_list(4);
+		stream.append(&self.nonce);
+		stream.append(&self.balance);
+		stream.append(&self.storage_root());
+		stream.append(&H256::from_slice(&Keccak256::digest(&self.code)));
+		stream.out().to_vec()
+	}
+}
+
 /// Memory backend, storing all state values in a `BTreeMap` in memory.
 #[derive(Clone, Debug)]
 pub struct MemoryBackend<'vicinity> {
@@ -78,6 +131,15 @@ impl<'vicinity> MemoryBackend<'vicinity> {
 	pub fn state_mut(&mut self) -> &mut BTreeMap<H160, MemoryAccount> {
 		&mut self.state
 	}
+
+	/// Root hash of the state trie.
+	pub fn state_root(&self) -> H256 {
+		sec_trie_root(
+			self.state
+				.iter()
+				.map(|(address, account)| (address, account.rlp_bytes())),
+		)
+	}
 }
 
 impl<'vicinity> Backend for MemoryBackend<'vicinity> {
@@ -88,15 +150,12 @@ impl<'vicinity> Backend for MemoryBackend<'vicinity> {
 		self.vicinity.origin
 	}
 	fn block_hash(&self, number: U256) -> H256 {
-		if number >= self.vicinity.block_number
-			|| self.vicinity.block_number - number - U256::one()
-				>= U256::from(self.vicinity.block_hashes.len())
-		{
-			H256::default()
-		} else {
-			let index = (self.vicinity.block_number - number - U256::one()).as_usize();
-			self.vicinity.block_hashes[index]
-		}
+		recent_block_hash(
+			&self.vicinity.block_hashes,
+			self.vicinity.block_number,
+			number,
+		)
+		.unwrap_or_default()
 	}
 	fn block_number(&self) -> U256 {
 		self.vicinity.block_number
@@ -116,6 +175,15 @@ impl<'vicinity> Backend for MemoryBackend<'vicinity> {
 	fn block_base_fee_per_gas(&self) -> U256 {
 		self.vicinity.block_base_fee_per_gas
 	}
+	fn block_randomness(&self) -> Option<H256> {
+		self.vicinity.block_randomness
+	}
+	fn block_blob_base_fee(&self) -> U256 {
+		self.vicinity.block_blob_base_fee
+	}
+	fn blob_hash(&self, index: usize) -> Option<H256> {
+		self.vicinity.blob_hashes.get(index).copied()
+	}
 
 	fn chain_id(&self) -> U256 {
 		self.vicinity.chain_id
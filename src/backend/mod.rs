@@ -2,12 +2,55 @@
 //!
 //! Backends store state information of the VM, and exposes it to runtime.
 
+mod asynchronous;
+#[cfg(feature = "std")]
+mod disk;
+mod fork;
 mod memory;
+mod overlay;
+mod proof;
+mod trie;
 
+pub(crate) use self::asynchronous::PrefetchCache;
+
+pub use self::asynchronous::{AsyncStateProvider, BoxFuture, PrefetchBackend};
+#[cfg(feature = "std")]
+pub use self::disk::{Batch, DiskBackend, FileStore, KeyValueStore};
+pub use self::fork::{ForkBackend, StateProvider};
 pub use self::memory::{MemoryAccount, MemoryBackend, MemoryVicinity};
+pub use self::overlay::OverlayBackend;
+pub use self::proof::{AccountProof, StorageProof};
+pub use self::trie::{
+	sec_trie_proof, sec_trie_root, trie_proof, trie_root, verify_proof, ProofError, EMPTY_TRIE_ROOT,
+};
 
 use alloc::vec::Vec;
 use primitive_types::{H160, H256, U256};
+use sha3::{Digest, Keccak256};
+
+/// Keccak hash of the empty code.
+pub(crate) const EMPTY_CODE_HASH: H256 = H256([
+	0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0,
+	0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b, 0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70,
+]);
+
+/// Hash of block `number` in `block_hashes`, the hashes of the blocks before
+/// block `current` from the most recent one.
+pub(crate) fn recent_block_hash(
+	block_hashes: &[H256],
+	current: U256,
+	number: U256,
+) -> Option<H256> {
+	if number >= current {
+		return None;
+	}
+	let distance = current - number - U256::one();
+	if distance < U256::from(block_hashes.len()) {
+		Some(block_hashes[distance.as_usize()])
+	} else {
+		None
+	}
+}
 
 /// Basic account information.
 #[derive(Clone, Eq, PartialEq, Debug, Default)]
@@ -70,6 +113,20 @@ pub trait Backend {
 	fn block_gas_limit(&self) -> U256;
 	/// Environmental block base fee.
 	fn block_base_fee_per_gas(&self) -> U256;
+	/// Environmental block randomness (`PREVRANDAO`), after the Merge.
+	/// `None` by default, which `PREVRANDAO` reads as zero.
+	fn block_randomness(&self) -> Option<H256> {
+		None
+	}
+	/// Environmental blob base fee. See EIP-7516. Zero by default.
+	fn block_blob_base_fee(&self) -> U256 {
+		U256::zero()
+	}
+	/// Versioned hash of the blob at index of the transaction. See EIP-4844.
+	/// `None` by default, as for a transaction without blobs.
+	fn blob_hash(&self, _index: usize) -> Option<H256> {
+		None
+	}
 	/// Environmental chain ID.
 	fn chain_id(&self) -> U256;
 
@@ -79,6 +136,11 @@ pub trait Backend {
 	fn basic(&self, address: H160) -> Basic;
 	/// Get account code.
 	fn code(&self, address: H160) -> Vec<u8>;
+	/// Get the Keccak hash of the account code. Backends that know the hash
+	/// without loading the code should override it.
+	fn code_hash(&self, address: H160) -> H256 {
+		H256::from_slice(Keccak256::digest(&self.code(address)).as_slice())
+	}
 	/// Get storage value of address at index.
 	fn storage(&self, address: H160, index: H256) -> H256;
 	/// Get original storage value of address at index, if available.
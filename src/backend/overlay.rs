@@ -0,0 +1,311 @@
+use super::{Apply, ApplyBackend, Backend, Basic, Log, EMPTY_CODE_HASH};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use primitive_types::{H160, H256, U256};
+use sha3::{Digest, Keccak256};
+
+/// Account written in an overlay layer.
+#[derive(Clone, Debug)]
+struct OverlayAccount {
+	basic: Basic,
+	/// Code, or `None` for the code of the layers below.
+	code: Option<Vec<u8>>,
+	storage: BTreeMap<H256, H256>,
+	/// Whether the storage of the layers below is wiped.
+	reset_storage: bool,
+}
+
+/// Writes of an overlay layer. Deleted accounts are `None`.
+#[derive(Clone, Debug, Default)]
+struct OverlayLayer {
+	accounts: BTreeMap<H160, Option<OverlayAccount>>,
+	logs: Vec<Log>,
+}
+
+impl OverlayLayer {
+	/// Merge the writes of a layer above into this one.
+	fn merge(&mut self, above: OverlayLayer) {
+		for (address, account) in above.accounts {
+			match (self.accounts.get_mut(&address), account) {
+				(Some(Some(below)), Some(account)) => {
+					below.basic = account.basic;
+					if account.code.is_some() {
+						below.code = account.code;
+					}
+					if account.reset_storage {
+						below.storage = account.storage;
+						below.reset_storage = true;
+					} else {
+						below.storage.extend(account.storage);
+					}
+				}
+				(_, account) => {
+					self.accounts.insert(address, account);
+				}
+			}
+		}
+		self.logs.extend(above.logs);
+	}
+}
+
+/// Overlay backend, keeping writes in stacked layers on top of another
+/// backend, which is left unchanged until `commit`.
+///
+/// `checkpoint` starts a new layer, and `revert_to` drops the layers of a
+/// checkpoint and the ones after it, so speculative changes are discarded
+/// without copying the state.
+#[derive(Clone, Debug)]
+pub struct OverlayBackend<B> {
+	backend: B,
+	layers: Vec<OverlayLayer>,
+}
+
+impl<B: Backend> OverlayBackend<B> {
+	/// Create a new overlay backend on top of a backend.
+	pub fn new(backend: B) -> Self {
+		Self {
+			backend,
+			layers: Vec::new(),
+		}
+	}
+
+	/// Get the underlying backend.
+	pub fn inner(&self) -> &B {
+		&self.backend
+	}
+
+	/// Consume the overlay, discarding its writes, and return the underlying
+	/// backend.
+	pub fn into_inner(self) -> B {
+		self.backend
+	}
+
+	/// Start a new layer, and return its checkpoint ID.
+	pub fn checkpoint(&mut self) -> usize {
+		self.layers.push(OverlayLayer::default());
+		self.layers.len() - 1
+	}
+
+	/// Discard all writes since the checkpoint with the given ID, including
+	/// the checkpoints taken after it.
+	pub fn revert_to(&mut self, checkpoint: usize) {
+		self.layers.truncate(checkpoint);
+	}
+
+	/// Logs applied to the overlay.
+	pub fn logs(&self) -> impl Iterator<Item = &Log> {
+		self.layers.iter().flat_map(|layer| layer.logs.iter())
+	}
+
+	/// Latest write of an account, `Some(None)` if it is deleted and `None`
+	/// if no layer has written it.
+	fn account(&self, address: H160) -> Option<Option<&OverlayAccount>> {
+		self.layers
+			.iter()
+			.rev()
+			.find_map(|layer| layer.accounts.get(&address))
+			.map(Option::as_ref)
+	}
+
+	fn top(&mut self) -> &mut OverlayLayer {
+		if self.layers.is_empty() {
+			self.layers.push(OverlayLayer::default());
+		}
+		self.layers
+			.last_mut()
+			.expect("a layer was just pushed; qed")
+	}
+}
+
+impl<B: Backend + ApplyBackend> OverlayBackend<B> {
+	/// Apply the writes of all layers to the underlying backend, and drop all
+	/// checkpoints.
+	pub fn commit(&mut self) {
+		let mut merged = OverlayLayer::default();
+		for layer in self.layers.drain(..) {
+			merged.merge(layer);
+		}
+
+		let values = merged
+			.accounts
+			.into_iter()
+			.map(|(address, account)| match account {
+				Some(account) => Apply::Modify {
+					address,
+					basic: account.basic,
+					code: account.code,
+					storage: account.storage,
+					reset_storage: account.reset_storage,
+				},
+				None => Apply::Delete { address },
+			});
+		self.backend.apply(values, merged.logs, false);
+	}
+}
+
+impl<B: Backend> Backend for OverlayBackend<B> {
+	fn gas_price(&self) -> U256 {
+		self.backend.gas_price()
+	}
+	fn origin(&self) -> H160 {
+		self.backend.origin()
+	}
+	fn block_hash(&self, number: U256) -> H256 {
+		self.backend.block_hash(number)
+	}
+	fn block_number(&self) -> U256 {
+		self.backend.block_number()
+	}
+	fn block_coinbase(&self) -> H160 {
+		self.backend.block_coinbase()
+	}
+	fn block_timestamp(&self) -> U256 {
+		self.backend.block_timestamp()
+	}
+	fn block_difficulty(&self) -> U256 {
+		self.backend.block_difficulty()
+	}
+	fn block_gas_limit(&self) -> U256 {
+		self.backend.block_gas_limit()
+	}
+	fn block_base_fee_per_gas(&self) -> U256 {
+		self.backend.block_base_fee_per_gas()
+	}
+	fn block_randomness(&self) -> Option<H256> {
+		self.backend.block_randomness()
+	}
+	fn block_blob_base_fee(&self) -> U256 {
+		self.backend.block_blob_base_fee()
+	}
+	fn blob_hash(&self, index: usize) -> Option<H256> {
+		self.backend.blob_hash(index)
+	}
+	fn chain_id(&self) -> U256 {
+		self.backend.chain_id()
+	}
+
+	fn exists(&self, address: H160) -> bool {
+		match self.account(address) {
+			Some(account) => account.is_some(),
+			None => self.backend.exists(address),
+		}
+	}
+
+	fn basic(&self, address: H160) -> Basic {
+		match self.account(address) {
+			Some(Some(account)) => account.basic.clone(),
+			Some(None) => Basic::default(),
+			None => self.backend.basic(address),
+		}
+	}
+
+	fn code(&self, address: H160) -> Vec<u8> {
+		for layer in self.layers.iter().rev() {
+			match layer.accounts.get(&address) {
+				Some(Some(OverlayAccount {
+					code: Some(code), ..
+				})) => return code.clone(),
+				Some(None) => return Vec::new(),
+				Some(Some(_)) | None => (),
+			}
+		}
+		self.backend.code(address)
+	}
+
+	fn code_hash(&self, address: H160) -> H256 {
+		for layer in self.layers.iter().rev() {
+			match layer.accounts.get(&address) {
+				Some(Some(OverlayAccount {
+					code: Some(code), ..
+				})) => return H256::from_slice(Keccak256::digest(code).as_slice()),
+				Some(None) => return EMPTY_CODE_HASH,
+				Some(Some(_)) | None => (),
+			}
+		}
+		self.backend.code_hash(address)
+	}
+
+	fn storage(&self, address: H160, index: H256) -> H256 {
+		for layer in self.layers.iter().rev() {
+			match layer.accounts.get(&address) {
+				Some(Some(account)) => match account.storage.get(&index) {
+					Some(value) => return *value,
+					None if account.reset_storage => return H256::default(),
+					None => (),
+				},
+				Some(None) => return H256::default(),
+				None => (),
+			}
+		}
+		self.backend.storage(address, index)
+	}
+
+	fn original_storage(&self, address: H160, index: H256) -> Option<H256> {
+		Some(self.storage(address, index))
+	}
+}
+
+impl<B: Backend> ApplyBackend for OverlayBackend<B> {
+	fn apply<A, I, L>(&mut self, values: A, logs: L, delete_empty: bool)
+	where
+		A: IntoIterator<Item = Apply<I>>,
+		I: IntoIterator<Item = (H256, H256)>,
+		L: IntoIterator<Item = Log>,
+	{
+		for apply in values {
+			match apply {
+				Apply::Modify {
+					address,
+					basic,
+					code,
+					storage,
+					reset_storage,
+				} => {
+					let is_empty_code = match &code {
+						Some(code) => code.is_empty(),
+						None => self.code(address).is_empty(),
+					};
+					// A deleted account is recreated with empty code and storage.
+					let deleted = matches!(self.account(address), Some(None));
+
+					let account = self
+						.top()
+						.accounts
+						.entry(address)
+						.or_insert(None)
+						.get_or_insert_with(|| OverlayAccount {
+							basic: Basic::default(),
+							code: if deleted { Some(Vec::new()) } else { None },
+							storage: BTreeMap::new(),
+							reset_storage: deleted,
+						});
+					account.basic = basic.clone();
+					if let Some(code) = code {
+						account.code = Some(code);
+					}
+
+					if reset_storage {
+						account.storage = BTreeMap::new();
+						account.reset_storage = true;
+					}
+
+					for (index, value) in storage {
+						account.storage.insert(index, value);
+					}
+
+					let is_empty = basic.balance == U256::zero()
+						&& basic.nonce == U256::zero()
+						&& is_empty_code;
+					if is_empty && delete_empty {
+						self.top().accounts.insert(address, None);
+					}
+				}
+				Apply::Delete { address } => {
+					self.top().accounts.insert(address, None);
+				}
+			}
+		}
+
+		self.top().logs.extend(logs);
+	}
+}
@@ -0,0 +1,136 @@
+use super::trie::{sec_trie_proof, verify_proof, ProofError, EMPTY_TRIE_ROOT};
+use super::{MemoryAccount, MemoryBackend, EMPTY_CODE_HASH};
+use alloc::vec::Vec;
+use primitive_types::{H160, H256, U256};
+use sha3::{Digest, Keccak256};
+
+/// Proof of a storage value. See EIP-1186.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "with-codec",
+	derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StorageProof {
+	/// Storage index.
+	pub key: H256,
+	/// Storage value.
+	pub value: U256,
+	/// Nodes of the storage trie on the path to the index.
+	pub proof: Vec<Vec<u8>>,
+}
+
+/// Proof of an account and some of its storage values. See EIP-1186.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "with-codec",
+	derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccountProof {
+	/// Account address.
+	pub address: H160,
+	/// Account balance.
+	pub balance: U256,
+	/// Hash of the account code.
+	pub code_hash: H256,
+	/// Account nonce.
+	pub nonce: U256,
+	/// Root hash of the account storage trie.
+	pub storage_hash: H256,
+	/// Nodes of the state trie on the path to the account.
+	pub account_proof: Vec<Vec<u8>>,
+	/// Proofs of the requested storage values.
+	pub storage_proof: Vec<StorageProof>,
+}
+
+impl AccountProof {
+	/// Verify the proof against a state root. An account absent from the
+	/// state must be proven with empty values.
+	pub fn verify(&self, state_root: H256) -> Result<(), ProofError> {
+		let account = verify_proof(
+			state_root,
+			&Keccak256::digest(self.address.as_bytes()),
+			&self.account_proof,
+		)?;
+		match account {
+			Some(account) => {
+				let mut stream = rlp::RlpStream::new_list(4);
+				stream.append(&self.nonce);
+				stream.append(&self.balance);
+				stream.append(&self.storage_hash);
+				stream.append(&self.code_hash);
+				if stream.out()[..] != account[..] {
+					return Err(ProofError::ValueMismatch);
+				}
+			}
+			None => {
+				if self.nonce != U256::zero()
+					|| self.balance != U256::zero()
+					|| self.code_hash != EMPTY_CODE_HASH
+					|| self.storage_hash != EMPTY_TRIE_ROOT
+				{
+					return Err(ProofError::ValueMismatch);
+				}
+			}
+		}
+
+		for storage in &self.storage_proof {
+			let value = verify_proof(
+				self.storage_hash,
+				&Keccak256::digest(storage.key.as_bytes()),
+				&storage.proof,
+			)?;
+			let expected = if storage.value == U256::zero() {
+				None
+			} else {
+				Some(rlp::encode(&storage.value).to_vec())
+			};
+			if value != expected {
+				return Err(ProofError::ValueMismatch);
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl<'vicinity> MemoryBackend<'vicinity> {
+	/// Proof of an account and of its storage values at the given indexes,
+	/// against the `state_root` of the backend.
+	pub fn proof(&self, address: H160, indexes: &[H256]) -> AccountProof {
+		let account_proof = sec_trie_proof(
+			self.state()
+				.iter()
+				.map(|(address, account)| (address, account.rlp_bytes())),
+			address.as_bytes(),
+		);
+		let empty = MemoryAccount::default();
+		let account = self.state().get(&address).unwrap_or(&empty);
+		let storage = account
+			.storage
+			.iter()
+			.filter(|(_, value)| **value != H256::default())
+			.map(|(index, value)| (index, rlp::encode(&U256::from_big_endian(&value[..]))))
+			.collect::<Vec<_>>();
+
+		AccountProof {
+			address,
+			balance: account.balance,
+			code_hash: H256::from_slice(&Keccak256::digest(&account.code)),
+			nonce: account.nonce,
+			storage_hash: account.storage_root(),
+			account_proof,
+			storage_proof: indexes
+				.iter()
+				.map(|index| StorageProof {
+					key: *index,
+					value: U256::from_big_endian(
+						&account.storage.get(index).cloned().unwrap_or_default()[..],
+					),
+					proof: sec_trie_proof(storage.iter().cloned(), index.as_bytes()),
+				})
+				.collect(),
+		}
+	}
+}
@@ -0,0 +1,323 @@
+//! Root hash and proofs of a Merkle-Patricia trie, built at once from all
+//! its entries.
+
+use alloc::vec::Vec;
+use primitive_types::H256;
+use rlp::{Rlp, RlpStream};
+use sha3::{Digest, Keccak256};
+
+/// Root hash of the empty trie, the hash of the RLP empty string.
+pub const EMPTY_TRIE_ROOT: H256 = H256([
+	0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+	0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+]);
+
+/// Root hash of a secure trie, where entries are keyed by the keccak hash of
+/// their keys.
+pub fn sec_trie_root<I, K, V>(entries: I) -> H256
+where
+	I: IntoIterator<Item = (K, V)>,
+	K: AsRef<[u8]>,
+	V: AsRef<[u8]>,
+{
+	trie_root(
+		entries
+			.into_iter()
+			.map(|(key, value)| (Keccak256::digest(key.as_ref()), value)),
+	)
+}
+
+/// Root hash of a trie. Later entries replace earlier ones with the same
+/// key.
+pub fn trie_root<I, K, V>(entries: I) -> H256
+where
+	I: IntoIterator<Item = (K, V)>,
+	K: AsRef<[u8]>,
+	V: AsRef<[u8]>,
+{
+	let entries = sorted_entries(entries);
+	if entries.is_empty() {
+		return EMPTY_TRIE_ROOT;
+	}
+
+	H256::from_slice(&Keccak256::digest(&encode_node(&entries, 0)))
+}
+
+/// Proof of the value at key in a secure trie. See `trie_proof`.
+pub fn sec_trie_proof<I, K, V>(entries: I, key: &[u8]) -> Vec<Vec<u8>>
+where
+	I: IntoIterator<Item = (K, V)>,
+	K: AsRef<[u8]>,
+	V: AsRef<[u8]>,
+{
+	trie_proof(
+		entries
+			.into_iter()
+			.map(|(key, value)| (Keccak256::digest(key.as_ref()), value)),
+		&Keccak256::digest(key),
+	)
+}
+
+/// Proof of the value at key in a trie, or of its absence: the encoded
+/// nodes on the path to the key that are referred to by hash, starting with
+/// the root node.
+pub fn trie_proof<I, K, V>(entries: I, key: &[u8]) -> Vec<Vec<u8>>
+where
+	I: IntoIterator<Item = (K, V)>,
+	K: AsRef<[u8]>,
+	V: AsRef<[u8]>,
+{
+	let entries = sorted_entries(entries);
+	let mut proof = Vec::new();
+	if !entries.is_empty() {
+		prove_node(&entries, 0, &nibbles(key), &mut proof);
+	}
+	proof
+}
+
+/// Reason a trie proof is invalid.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ProofError {
+	/// A node the path refers to is missing from the proof.
+	MissingNode,
+	/// A node of the proof does not match the hash referring to it.
+	HashMismatch,
+	/// A node of the proof is not a valid trie node.
+	InvalidNode,
+	/// The proven value does not match the claimed one.
+	ValueMismatch,
+}
+
+/// Verify a proof of the value at key in a trie with the given root hash.
+/// Returns the value, or `None` if the proof shows the key is absent.
+pub fn verify_proof(
+	root: H256,
+	key: &[u8],
+	proof: &[Vec<u8>],
+) -> Result<Option<Vec<u8>>, ProofError> {
+	if root == EMPTY_TRIE_ROOT {
+		return Ok(None);
+	}
+
+	let key = nibbles(key);
+	let mut proof = proof.iter();
+	let mut node = next_node(&mut proof, root)?.to_vec();
+	let mut depth = 0;
+	loop {
+		let rlp = Rlp::new(&node);
+		let child = match rlp.item_count().map_err(|_| ProofError::InvalidNode)? {
+			17 => {
+				if depth == key.len() {
+					let value = rlp
+						.at(16)
+						.and_then(|value| value.data().map(<[u8]>::to_vec))
+						.map_err(|_| ProofError::InvalidNode)?;
+					return Ok(if value.is_empty() { None } else { Some(value) });
+				}
+				depth += 1;
+				rlp.at(usize::from(key[depth - 1]))
+			}
+			2 => {
+				let path = rlp
+					.at(0)
+					.and_then(|path| path.data().map(<[u8]>::to_vec))
+					.map_err(|_| ProofError::InvalidNode)?;
+				let (path, leaf) = decode_hex_prefix(&path)?;
+				if !key[depth..].starts_with(&path) {
+					return Ok(None);
+				}
+				depth += path.len();
+				if leaf {
+					if depth != key.len() {
+						return Ok(None);
+					}
+					let value = rlp
+						.at(1)
+						.and_then(|value| value.data().map(<[u8]>::to_vec))
+						.map_err(|_| ProofError::InvalidNode)?;
+					return Ok(Some(value));
+				}
+				rlp.at(1)
+			}
+			_ => return Err(ProofError::InvalidNode),
+		}
+		.map_err(|_| ProofError::InvalidNode)?;
+
+		node = if child.is_list() {
+			child.as_raw().to_vec()
+		} else {
+			let hash = child.data().map_err(|_| ProofError::InvalidNode)?;
+			match hash.len() {
+				0 => return Ok(None),
+				32 => next_node(&mut proof, H256::from_slice(hash))?.to_vec(),
+				_ => return Err(ProofError::InvalidNode),
+			}
+		};
+	}
+}
+
+/// Next node of a proof, checked against the hash referring to it.
+fn next_node<'a, I: Iterator<Item = &'a Vec<u8>>>(
+	proof: &mut I,
+	hash: H256,
+) -> Result<&'a [u8], ProofError> {
+	let node = proof.next().ok_or(ProofError::MissingNode)?;
+	if H256::from_slice(&Keccak256::digest(node)) != hash {
+		return Err(ProofError::HashMismatch);
+	}
+	Ok(node)
+}
+
+fn nibbles(key: &[u8]) -> Vec<u8> {
+	key.iter()
+		.flat_map(|byte| [byte >> 4, byte & 0x0f].to_vec())
+		.collect()
+}
+
+/// Entries keyed by nibbles, sorted and deduplicated, keeping the later
+/// entries.
+fn sorted_entries<I, K, V>(entries: I) -> Vec<(Vec<u8>, Vec<u8>)>
+where
+	I: IntoIterator<Item = (K, V)>,
+	K: AsRef<[u8]>,
+	V: AsRef<[u8]>,
+{
+	let mut entries = entries
+		.into_iter()
+		.map(|(key, value)| (nibbles(key.as_ref()), value.as_ref().to_vec()))
+		.collect::<Vec<_>>();
+	entries.reverse();
+	entries.sort_by(|a, b| a.0.cmp(&b.0));
+	entries.dedup_by(|a, b| a.0 == b.0);
+	entries
+}
+
+/// Add the nodes on the path to key under the node holding `entries` to the
+/// proof. See `encode_node`.
+fn prove_node(entries: &[(Vec<u8>, Vec<u8>)], depth: usize, key: &[u8], proof: &mut Vec<Vec<u8>>) {
+	let node = encode_node(entries, depth);
+	if depth == 0 || node.len() >= 32 {
+		proof.push(node);
+	}
+	if entries.len() == 1 {
+		return;
+	}
+
+	let first = &entries[0].0;
+	let last = &entries[entries.len() - 1].0;
+	let shared = first[depth..]
+		.iter()
+		.zip(&last[depth..])
+		.take_while(|(a, b)| a == b)
+		.count();
+	if shared > 0 {
+		if key.len() >= depth + shared && key[depth..depth + shared] == first[depth..depth + shared]
+		{
+			prove_node(entries, depth + shared, key, proof);
+		}
+		return;
+	}
+
+	if key.len() == depth {
+		return;
+	}
+	let children = entries
+		.iter()
+		.filter(|(entry, _)| entry.len() > depth && entry[depth] == key[depth])
+		.cloned()
+		.collect::<Vec<_>>();
+	if !children.is_empty() {
+		prove_node(&children, depth + 1, key, proof);
+	}
+}
+
+/// RLP encoding of the node holding the sorted `entries`, whose keys share
+/// their first `depth` nibbles.
+fn encode_node(entries: &[(Vec<u8>, Vec<u8>)], depth: usize) -> Vec<u8> {
+	if entries.len() == 1 {
+		let (key, value) = &entries[0];
+		let mut stream = RlpStream::new_list(2);
+		stream.append(&hex_prefix(&key[depth..], true));
+		stream.append(value);
+		return stream.out().to_vec();
+	}
+
+	let first = &entries[0].0;
+	let last = &entries[entries.len() - 1].0;
+	let shared = first[depth..]
+		.iter()
+		.zip(&last[depth..])
+		.take_while(|(a, b)| a == b)
+		.count();
+	if shared > 0 {
+		let mut stream = RlpStream::new_list(2);
+		stream.append(&hex_prefix(&first[depth..depth + shared], false));
+		append_reference(&mut stream, &encode_node(entries, depth + shared));
+		return stream.out().to_vec();
+	}
+
+	// A key ending here sorts first, and is the value of the branch.
+	let (value, mut rest) = if first.len() == depth {
+		(Some(&entries[0].1), &entries[1..])
+	} else {
+		(None, entries)
+	};
+
+	let mut stream = RlpStream::new_list(17);
+	for nibble in 0..16 {
+		let count = rest
+			.iter()
+			.take_while(|(key, _)| key[depth] == nibble)
+			.count();
+		if count == 0 {
+			stream.append_empty_data();
+		} else {
+			append_reference(&mut stream, &encode_node(&rest[..count], depth + 1));
+		}
+		rest = &rest[count..];
+	}
+	match value {
+		Some(value) => stream.append(value),
+		None => stream.append_empty_data(),
+	};
+	stream.out().to_vec()
+}
+
+/// Append a child node, inlined if its encoding is shorter than a hash.
+fn append_reference(stream: &mut RlpStream, node: &[u8]) {
+	if node.len() < 32 {
+		stream.append_raw(node, 1);
+	} else {
+		stream.append(&H256::from_slice(&Keccak256::digest(node)));
+	}
+}
+
+/// Path of nibbles of a hex-prefix encoding, and whether it is the path of
+/// a leaf.
+fn decode_hex_prefix(encoded: &[u8]) -> Result<(Vec<u8>, bool), ProofError> {
+	let flag = *encoded.first().ok_or(ProofError::InvalidNode)?;
+	if flag >> 4 > 3 {
+		return Err(ProofError::InvalidNode);
+	}
+	let mut path = Vec::with_capacity(encoded.len() * 2);
+	if flag & 0x10 != 0 {
+		path.push(flag & 0x0f);
+	}
+	path.extend(nibbles(&encoded[1..]));
+	Ok((path, flag & 0x20 != 0))
+}
+
+/// Hex-prefix encoding of a path of nibbles.
+fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+	let flag = if leaf { 0x20 } else { 0x00 };
+	let mut encoded = Vec::with_capacity(nibbles.len() / 2 + 1);
+	let rest = if nibbles.len() % 2 == 1 {
+		encoded.push(flag | 0x10 | nibbles[0]);
+		&nibbles[1..]
+	} else {
+		encoded.push(flag);
+		nibbles
+	};
+	encoded.extend(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+	encoded
+}
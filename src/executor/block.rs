@@ -0,0 +1,301 @@
+//! A block executor, applying the transactions of a block one after another
+//! on top of an `ApplyBackend`.
+
+pub use crate::executor::stack::InvalidTransaction;
+
+use crate::backend::{recent_block_hash, Apply, ApplyBackend, Backend, Basic, Log};
+use crate::executor::stack::{
+	CodeCache, MemoryStackState, PrecompileSet, StackExecutor, StackSubstateMetadata, Transaction,
+};
+use crate::{Config, ExitReason};
+use alloc::vec::Vec;
+use ethereum::{EIP658ReceiptData, ReceiptV3, TransactionV2};
+use primitive_types::{H160, H256, U256};
+use sha3::{Digest, Keccak256};
+
+/// Block environment, as given by a block header.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BlockEnv {
+	/// Block number.
+	pub number: U256,
+	/// Block coinbase, paid the priority fees.
+	pub coinbase: H160,
+	/// Block timestamp.
+	pub timestamp: U256,
+	/// Block difficulty.
+	pub difficulty: U256,
+	/// Block randomness, after the Merge.
+	pub randomness: Option<H256>,
+	/// Block gas limit.
+	pub gas_limit: U256,
+	/// Base fee per gas, after London.
+	pub base_fee_per_gas: U256,
+	/// Blob base fee, after Cancun.
+	pub blob_base_fee: U256,
+	/// Chain ID.
+	pub chain_id: U256,
+	/// Hashes of the most recent blocks, starting with the parent block.
+	pub block_hashes: Vec<H256>,
+}
+
+/// Result of a transaction applied by `BlockExecutor`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransactionOutcome {
+	/// Exit reason of the transaction.
+	pub exit_reason: ExitReason,
+	/// Gas used by the transaction, after refunds.
+	pub used_gas: U256,
+	/// Address of the created contract, for successful create transactions.
+	pub contract_address: Option<H160>,
+	/// Receipt of the transaction, with the gas used in the block so far.
+	pub receipt: ReceiptV3,
+}
+
+/// Invalid transaction met by `BlockExecutor::apply_transactions`, with the
+/// outcomes of the transactions applied before it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ApplyTransactionsError {
+	/// Outcomes of the transactions before the invalid one, which stay
+	/// applied to the backend.
+	pub outcomes: Vec<TransactionOutcome>,
+	/// Index of the invalid transaction.
+	pub index: usize,
+	/// Reason the transaction is invalid.
+	pub error: InvalidTransaction,
+}
+
+/// Backend seen by a transaction: the block environment and the transaction
+/// origin and gas price on top of the state of the underlying backend.
+struct TransactionBackend<'a, B> {
+	backend: &'a B,
+	env: &'a BlockEnv,
+	origin: H160,
+	gas_price: U256,
+}
+
+impl<'a, B: Backend> Backend for TransactionBackend<'a, B> {
+	fn gas_price(&self) -> U256 {
+		self.gas_price
+	}
+	fn origin(&self) -> H160 {
+		self.origin
+	}
+	fn block_hash(&self, number: U256) -> H256 {
+		recent_block_hash(&self.env.block_hashes, self.env.number, number).unwrap_or_default()
+	}
+	fn block_number(&self) -> U256 {
+		self.env.number
+	}
+	fn block_coinbase(&self) -> H160 {
+		self.env.coinbase
+	}
+	fn block_timestamp(&self) -> U256 {
+		self.env.timestamp
+	}
+	fn block_difficulty(&self) -> U256 {
+		self.env.difficulty
+	}
+	fn block_gas_limit(&self) -> U256 {
+		self.env.gas_limit
+	}
+	fn block_base_fee_per_gas(&self) -> U256 {
+		self.env.base_fee_per_gas
+	}
+	fn block_randomness(&self) -> Option<H256> {
+		self.env.randomness
+	}
+	fn block_blob_base_fee(&self) -> U256 {
+		self.env.blob_base_fee
+	}
+	fn blob_hash(&self, _index: usize) -> Option<H256> {
+		None
+	}
+	fn chain_id(&self) -> U256 {
+		self.env.chain_id
+	}
+
+	fn exists(&self, address: H160) -> bool {
+		self.backend.exists(address)
+	}
+	fn basic(&self, address: H160) -> Basic {
+		self.backend.basic(address)
+	}
+	fn code(&self, address: H160) -> Vec<u8> {
+		self.backend.code(address)
+	}
+	fn code_hash(&self, address: H160) -> H256 {
+		self.backend.code_hash(address)
+	}
+	fn storage(&self, address: H160, index: H256) -> H256 {
+		self.backend.storage(address, index)
+	}
+	fn original_storage(&self, address: H160, index: H256) -> Option<H256> {
+		self.backend.original_storage(address, index)
+	}
+}
+
+/// Add the address and topics of logs to a 2048 bits logs bloom.
+fn accrue_logs_bloom(bloom: &mut [u8; 256], logs: &[Log]) {
+	for log in logs {
+		let inputs = core::iter::once(log.address.as_bytes())
+			.chain(log.topics.iter().map(|topic| topic.as_bytes()));
+		for input in inputs {
+			let hash = Keccak256::digest(input);
+			for i in [0, 2, 4].iter() {
+				let bit = (usize::from(hash[*i]) << 8 | usize::from(hash[*i + 1])) & 2047;
+				bloom[255 - bit / 8] |= 1 << (bit % 8);
+			}
+		}
+	}
+}
+
+/// Block executor. Transactions are checked against the gas left in the
+/// block, then run with `StackExecutor::transact`, and their state changes
+/// applied to the backend. The analyzed code of deployed contracts is
+/// shared by the transactions, up to `CodeCache::DEFAULT_CAPACITY` codes.
+pub struct BlockExecutor<'config, 'precompiles, 'env, P> {
+	config: &'config Config,
+	precompile_set: &'precompiles P,
+	env: &'env BlockEnv,
+	gas_used: U256,
+	code_cache: CodeCache,
+}
+
+impl<'config, 'precompiles, 'env, P: PrecompileSet> BlockExecutor<'config, 'precompiles, 'env, P> {
+	/// Create a new block executor for the block environment.
+	pub fn new(
+		env: &'env BlockEnv,
+		config: &'config Config,
+		precompile_set: &'precompiles P,
+	) -> Self {
+		Self {
+			config,
+			precompile_set,
+			env,
+			gas_used: U256::zero(),
+			code_cache: CodeCache::new(),
+		}
+	}
+
+	/// Gas used by the transactions applied so far.
+	pub fn gas_used(&self) -> U256 {
+		self.gas_used
+	}
+
+	/// Apply transactions in order, each with its sender. Stops at the first
+	/// invalid transaction; transactions before it stay applied to the
+	/// backend, and their outcomes are returned with the error.
+	pub fn apply_transactions<B, I>(
+		&mut self,
+		backend: &mut B,
+		transactions: I,
+	) -> Result<Vec<TransactionOutcome>, ApplyTransactionsError>
+	where
+		B: Backend + ApplyBackend,
+		I: IntoIterator<Item = (H160, TransactionV2)>,
+	{
+		let mut outcomes = Vec::new();
+		for (index, (caller, transaction)) in transactions.into_iter().enumerate() {
+			match self.apply_transaction(backend, caller, transaction) {
+				Ok(outcome) => outcomes.push(outcome),
+				Err(error) => {
+					return Err(ApplyTransactionsError {
+						outcomes,
+						index,
+						error,
+					})
+				}
+			}
+		}
+		Ok(outcomes)
+	}
+
+	/// Apply a transaction sent by `caller`, validated and settled by
+	/// `StackExecutor::transact`. Senders are not recovered from signatures.
+	/// An invalid transaction leaves the backend unchanged.
+	pub fn apply_transaction<B: Backend + ApplyBackend>(
+		&mut self,
+		backend: &mut B,
+		caller: H160,
+		transaction: TransactionV2,
+	) -> Result<TransactionOutcome, InvalidTransaction> {
+		let receipt: fn(EIP658ReceiptData) -> ReceiptV3 = match transaction {
+			TransactionV2::Legacy(_) => ReceiptV3::Legacy,
+			TransactionV2::EIP2930(_) => ReceiptV3::EIP2930,
+			TransactionV2::EIP1559(_) => ReceiptV3::EIP1559,
+		};
+		let transaction = Transaction::new(caller, transaction);
+
+		if transaction.gas_limit > self.env.gas_limit.saturating_sub(self.gas_used) {
+			return Err(InvalidTransaction::GasLimitReached);
+		}
+
+		let (outcome, values, logs) = {
+			let base_fee_per_gas = if self.config.has_base_fee {
+				self.env.base_fee_per_gas
+			} else {
+				U256::zero()
+			};
+			let backend = TransactionBackend {
+				backend: &*backend,
+				env: self.env,
+				origin: caller,
+				gas_price: transaction.effective_gas_price(base_fee_per_gas),
+			};
+			let metadata = StackSubstateMetadata::new(transaction.gas_limit.low_u64(), self.config);
+			let state = MemoryStackState::new(metadata, &backend);
+			let mut executor =
+				StackExecutor::new_with_precompiles(state, self.config, self.precompile_set)
+					.with_code_cache(self.code_cache.clone());
+			let outcome = executor.transact(transaction)?;
+
+			let (values, logs) = executor.into_state().deconstruct();
+			let values = values
+				.into_iter()
+				.map(|apply| match apply {
+					Apply::Modify {
+						address,
+						basic,
+						code,
+						storage,
+						reset_storage,
+					} => Apply::Modify {
+						address,
+						basic,
+						code,
+						storage: storage.into_iter().collect::<Vec<_>>(),
+						reset_storage,
+					},
+					Apply::Delete { address } => Apply::Delete { address },
+				})
+				.collect::<Vec<_>>();
+			let logs = logs.into_iter().collect::<Vec<_>>();
+
+			(outcome, values, logs)
+		};
+
+		backend.apply(values, logs.clone(), !self.config.empty_considered_exists);
+		let used_gas = U256::from(outcome.used_gas);
+		self.gas_used += used_gas;
+
+		let mut logs_bloom = [0u8; 256];
+		accrue_logs_bloom(&mut logs_bloom, &logs);
+		let receipt = receipt(EIP658ReceiptData {
+			status_code: if outcome.exit_reason.is_succeed() {
+				1
+			} else {
+				0
+			},
+			used_gas: self.gas_used,
+			logs_bloom: logs_bloom.into(),
+			logs,
+		});
+
+		Ok(TransactionOutcome {
+			exit_reason: outcome.exit_reason,
+			used_gas,
+			contract_address: outcome.contract_address,
+			receipt,
+		})
+	}
+}
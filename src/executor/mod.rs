@@ -3,6 +3,8 @@
 //! Executors are structs that hook gasometer and the EVM core together. It
 //! also handles the call stacks in EVM.
 //!
-//! Currently only a stack-based (customizable) executor is provided.
+//! A stack-based (customizable) executor runs single transactions, and a
+//! block executor applies the transactions of a block on top of it.
 
+pub mod block;
 pub mod stack;
@@ -0,0 +1,296 @@
+use crate::backend::Backend;
+use crate::executor::stack::{
+	Accessed, MemoryStackState, PrecompileSet, StackExecutor, StackState, StackSubstateMetadata,
+};
+use crate::{Config, CreateScheme, ExitReason};
+use alloc::{
+	collections::{BTreeMap, BTreeSet},
+	vec::Vec,
+};
+use primitive_types::{H160, H256, U256};
+
+/// Access list generated by `StackExecutor::create_access_list`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AccessListResult {
+	/// Exit reason of the transaction when run with `access_list`.
+	pub exit_reason: ExitReason,
+	/// Accessed addresses and storage keys, sorted by address.
+	pub access_list: Vec<(H160, Vec<H256>)>,
+	/// Gas used by the transaction when run with `access_list`.
+	pub used_gas: u64,
+}
+
+/// Most runs of a transaction by `StackExecutor::create_access_list`. A
+/// list that still changes after that many runs is returned as is.
+const MAX_RUNS: usize = 16;
+
+/// Access list in canonical form, so that two lists can be compared.
+type AccessSet = BTreeMap<H160, BTreeSet<H256>>;
+
+/// Build an access set. As in geth, excluded addresses are only kept when
+/// some of their storage keys are accessed.
+fn access_set<A, S, E>(addresses: A, storages: S, excluded: E) -> AccessSet
+where
+	A: Iterator<Item = H160>,
+	S: Iterator<Item = (H160, H256)>,
+	E: Fn(H160) -> bool,
+{
+	let mut set = AccessSet::new();
+	for address in addresses.filter(|address| !excluded(*address)) {
+		set.entry(address).or_default();
+	}
+	for (address, key) in storages {
+		set.entry(address).or_default().insert(key);
+	}
+	set
+}
+
+impl<'backend, 'config, 'precompiles, B: Backend, P: PrecompileSet>
+	StackExecutor<'config, 'precompiles, MemoryStackState<'backend, 'config, B>, P>
+{
+	/// Generate an access list for a transaction, as `eth_createAccessList`
+	/// does. The transaction is run on top of `backend` with `access_list`,
+	/// then again with the addresses and storage keys it accessed, until the
+	/// list no longer changes, up to 16 runs. The caller, the
+	/// recipient, precompiles and the coinbase once it is warm (EIP-3651)
+	/// are left out unless their storage is accessed. An `address` of `None`
+	/// is a `CREATE` transaction with `data` as init code.
+	///
+	/// Accesses done in reverted substates are not part of the list. Before
+	/// Berlin no accesses are tracked and the list is empty.
+	#[allow(clippy::too_many_arguments)]
+	pub fn create_access_list(
+		backend: &'backend B,
+		config: &'config Config,
+		precompile_set: &'precompiles P,
+		caller: H160,
+		address: Option<H160>,
+		value: U256,
+		data: Vec<u8>,
+		gas_limit: u64,
+		access_list: Vec<(H160, Vec<H256>)>,
+	) -> AccessListResult {
+		let recipient = match address {
+			Some(address) => address,
+			None => {
+				let metadata = StackSubstateMetadata::new(gas_limit, config);
+				let state = MemoryStackState::new(metadata, backend);
+				Self::new_with_precompiles(state, config, precompile_set)
+					.create_address(CreateScheme::Legacy { caller })
+			}
+		};
+		let coinbase = backend.block_coinbase();
+		let excluded = |address: H160| {
+			address == caller
+				|| address == recipient
+				|| precompile_set.is_precompile(address)
+				|| (config.warm_coinbase_address && address == coinbase)
+		};
+
+		let mut current_set = access_set(
+			access_list.iter().map(|(address, _)| *address),
+			access_list
+				.iter()
+				.flat_map(|(address, keys)| keys.iter().map(move |key| (*address, *key))),
+			&excluded,
+		);
+
+		let mut runs = 0;
+		loop {
+			runs += 1;
+			let access_list: Vec<(H160, Vec<H256>)> = current_set
+				.iter()
+				.map(|(address, keys)| (*address, keys.iter().copied().collect()))
+				.collect();
+
+			let metadata = StackSubstateMetadata::new(gas_limit, config);
+			let state = MemoryStackState::new(metadata, backend);
+			let mut executor = Self::new_with_precompiles(state, config, precompile_set);
+
+			let exit_reason = match address {
+				Some(address) => {
+					executor
+						.transact_call(
+							caller,
+							address,
+							value,
+							data.clone(),
+							gas_limit,
+							access_list.clone(),
+						)
+						.0
+				}
+				None => executor.transact_create(
+					caller,
+					value,
+					data.clone(),
+					gas_limit,
+					access_list.clone(),
+				),
+			};
+
+			let accessed_set = match executor.state().metadata().accessed() {
+				Some(Accessed {
+					accessed_addresses,
+					accessed_storage,
+				}) => access_set(
+					accessed_addresses.iter().copied(),
+					accessed_storage.iter().copied(),
+					&excluded,
+				),
+				None => AccessSet::new(),
+			};
+
+			if accessed_set == current_set || runs == MAX_RUNS {
+				return AccessListResult {
+					exit_reason,
+					access_list,
+					used_gas: executor.used_gas(),
+				};
+			}
+
+			current_set = accessed_set;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::backend::{MemoryAccount, MemoryBackend, MemoryVicinity};
+	use crate::ExitSucceed;
+
+	#[test]
+	fn creates_access_list() {
+		let config = Config::berlin();
+		let vicinity = MemoryVicinity::for_tests(100_000);
+		let mut state = BTreeMap::new();
+		// SLOAD(1), BALANCE(0x33..33), BALANCE(caller)
+		state.insert(
+			H160::repeat_byte(0x11),
+			MemoryAccount {
+				nonce: U256::one(),
+				balance: U256::zero(),
+				storage: BTreeMap::new(),
+				code: hex::decode("60015450733333333333333333333333333333333333333333315033315000")
+					.unwrap(),
+			},
+		);
+		let backend = MemoryBackend::new(&vicinity, state);
+		let precompiles = BTreeMap::new();
+
+		let result = StackExecutor::create_access_list(
+			&backend,
+			&config,
+			&precompiles,
+			H160::repeat_byte(0x22),
+			Some(H160::repeat_byte(0x11)),
+			U256::zero(),
+			Vec::new(),
+			100_000,
+			Vec::new(),
+		);
+
+		assert_eq!(
+			result.exit_reason,
+			ExitReason::Succeed(ExitSucceed::Stopped)
+		);
+		assert_eq!(
+			result.access_list,
+			vec![
+				(H160::repeat_byte(0x11), vec![H256::from_low_u64_be(1)]),
+				(H160::repeat_byte(0x33), Vec::new()),
+			]
+		);
+
+		let metadata = StackSubstateMetadata::new(100_000, &config);
+		let state = MemoryStackState::new(metadata, &backend);
+		let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+		executor.transact_call(
+			H160::repeat_byte(0x22),
+			H160::repeat_byte(0x11),
+			U256::zero(),
+			Vec::new(),
+			100_000,
+			result.access_list,
+		);
+		assert_eq!(result.used_gas, executor.used_gas());
+	}
+
+	#[test]
+	fn leaves_out_warm_coinbase() {
+		let config = Config::shanghai();
+		let vicinity = MemoryVicinity {
+			block_coinbase: H160::repeat_byte(0x33),
+			..MemoryVicinity::for_tests(100_000)
+		};
+		let mut state = BTreeMap::new();
+		// BALANCE(0x33..33)
+		state.insert(
+			H160::repeat_byte(0x11),
+			MemoryAccount {
+				nonce: U256::one(),
+				balance: U256::zero(),
+				storage: BTreeMap::new(),
+				code: hex::decode("733333333333333333333333333333333333333333315000").unwrap(),
+			},
+		);
+		let backend = MemoryBackend::new(&vicinity, state);
+		let precompiles = BTreeMap::new();
+
+		let result = StackExecutor::create_access_list(
+			&backend,
+			&config,
+			&precompiles,
+			H160::repeat_byte(0x22),
+			Some(H160::repeat_byte(0x11)),
+			U256::zero(),
+			Vec::new(),
+			100_000,
+			Vec::new(),
+		);
+
+		assert_eq!(
+			result.exit_reason,
+			ExitReason::Succeed(ExitSucceed::Stopped)
+		);
+		assert!(result.access_list.is_empty());
+	}
+
+	#[test]
+	fn stops_after_max_runs() {
+		let config = Config::berlin();
+		let vicinity = MemoryVicinity::for_tests(100_000);
+		let mut state = BTreeMap::new();
+		// BALANCE(GAS), whose address changes with the cost of the list.
+		state.insert(
+			H160::repeat_byte(0x11),
+			MemoryAccount {
+				nonce: U256::one(),
+				balance: U256::zero(),
+				storage: BTreeMap::new(),
+				code: hex::decode("5a315000").unwrap(),
+			},
+		);
+		let backend = MemoryBackend::new(&vicinity, state);
+		let precompiles = BTreeMap::new();
+
+		let result = StackExecutor::create_access_list(
+			&backend,
+			&config,
+			&precompiles,
+			H160::repeat_byte(0x22),
+			Some(H160::repeat_byte(0x11)),
+			U256::zero(),
+			Vec::new(),
+			100_000,
+			Vec::new(),
+		);
+
+		assert_eq!(
+			result.exit_reason,
+			ExitReason::Succeed(ExitSucceed::Stopped)
+		);
+		assert_eq!(result.access_list.len(), MAX_RUNS - 1);
+	}
+}
@@ -0,0 +1,105 @@
+use crate::backend::{
+	Apply, AsyncStateProvider, Log, MemoryVicinity, PrefetchBackend, PrefetchCache,
+};
+use crate::executor::stack::{
+	MemoryStackState, PrecompileSet, StackExecutor, StackSubstateMetadata,
+};
+use crate::Config;
+use alloc::vec::Vec;
+use primitive_types::H256;
+
+/// Error of `execute_async`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AsyncExecutionError {
+	/// The execution still reads values that are not fetched yet after the
+	/// maximum number of rounds.
+	TooManyRounds,
+}
+
+/// Run an execution against state fetched asynchronously from a provider,
+/// returning its result together with the values and logs to apply.
+///
+/// `f` runs the execution, for example a `transact_call`, on an executor
+/// over a `PrefetchBackend`. Whenever it reads values that are not fetched
+/// yet, they are fetched concurrently from the provider and `f` is run again
+/// from the start, until it reads no new value. The task is thus only
+/// suspended while waiting on the provider, so many executions can run
+/// concurrently on the same thread. `f` must be deterministic, and side
+/// effects of the discarded runs, such as tracing events, are observed more
+/// than once.
+///
+/// Values that only become known once others are fetched, such as a chain
+/// of `SLOAD`s each reading the slot given by the previous one, take one
+/// round each. The work done is thus quadratic in the length of such
+/// chains. Each run is a speculative execution over partial state that can
+/// use up to `gas_limit`, for example in a loop whose exit condition reads a
+/// value not fetched yet. Missing values are fetched at most `max_rounds`
+/// times, after which the execution fails with `TooManyRounds`.
+#[allow(clippy::type_complexity)]
+pub async fn execute_async<'config, 'precompiles, A, P, F, R>(
+	provider: &A,
+	vicinity: &MemoryVicinity,
+	config: &'config Config,
+	precompiles: &'precompiles P,
+	gas_limit: u64,
+	max_rounds: usize,
+	mut f: F,
+) -> Result<(R, Vec<Apply<Vec<(H256, H256)>>>, Vec<Log>), AsyncExecutionError>
+where
+	A: AsyncStateProvider + ?Sized,
+	P: PrecompileSet,
+	F: for<'backend> FnMut(
+		&mut StackExecutor<
+			'config,
+			'precompiles,
+			MemoryStackState<'backend, 'config, PrefetchBackend<'backend>>,
+			P,
+		>,
+	) -> R,
+{
+	let mut cache = PrefetchCache::default();
+	let mut rounds = 0;
+	loop {
+		let missing = {
+			let backend = PrefetchBackend::new(vicinity, &cache);
+			let metadata = StackSubstateMetadata::new(gas_limit, config);
+			let state = MemoryStackState::new(metadata, &backend);
+			let mut executor = StackExecutor::new_with_precompiles(state, config, precompiles);
+			let result = f(&mut executor);
+
+			let (values, logs) = executor.into_state().deconstruct();
+			let values = values
+				.into_iter()
+				.map(|apply| match apply {
+					Apply::Modify {
+						address,
+						basic,
+						code,
+						storage,
+						reset_storage,
+					} => Apply::Modify {
+						address,
+						basic,
+						code,
+						storage: storage.into_iter().collect::<Vec<_>>(),
+						reset_storage,
+					},
+					Apply::Delete { address } => Apply::Delete { address },
+				})
+				.collect::<Vec<_>>();
+			let logs = logs.into_iter().collect::<Vec<_>>();
+
+			let missing = backend.into_missing();
+			if missing.is_empty() {
+				return Ok((result, values, logs));
+			}
+			missing
+		};
+
+		if rounds == max_rounds {
+			return Err(AsyncExecutionError::TooManyRounds);
+		}
+		rounds += 1;
+		cache.fetch(provider, missing).await;
+	}
+}
@@ -0,0 +1,184 @@
+use crate::backend::Backend;
+use crate::gasometer::ControlFlowGraph;
+use crate::{StaticBlocks, Valids};
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use primitive_types::{H160, H256};
+
+/// Code together with its analysis, shared by all the calls running it.
+#[derive(Clone, Debug)]
+pub struct AnalyzedCode {
+	/// Code.
+	pub code: Rc<Vec<u8>>,
+	/// Valid jump destinations of the code.
+	pub valids: Rc<Valids>,
+	/// Static blocks of the code, if they were asked for.
+	pub static_blocks: Option<Rc<StaticBlocks>>,
+}
+
+impl AnalyzedCode {
+	/// Analyze code.
+	pub fn new(code: Vec<u8>) -> Self {
+		let valids = Valids::new(&code);
+		Self {
+			code: Rc::new(code),
+			valids: Rc::new(valids),
+			static_blocks: None,
+		}
+	}
+
+	/// Compute the static blocks of the code, if they are not known yet.
+	pub fn with_static_blocks(mut self) -> Self {
+		if self.static_blocks.is_none() {
+			let graph = ControlFlowGraph::new(&self.code);
+			self.static_blocks = Some(Rc::new(graph.static_blocks(&self.code)));
+		}
+		self
+	}
+}
+
+/// Source of analyzed code for the calls of a stack executor. Only the code
+/// of existing accounts goes through it, init code is analyzed on each
+/// create.
+pub trait AnalysisCache {
+	/// Get the analysis of the code of an account, including its static
+	/// blocks if asked for. `None` if the code is not cached, in which case
+	/// the executor analyzes it for the call.
+	fn analyzed_code<B: Backend>(
+		&self,
+		backend: &B,
+		address: H160,
+		static_blocks: bool,
+	) -> Option<AnalyzedCode>;
+}
+
+/// No cache, the code is analyzed on every call.
+impl AnalysisCache for () {
+	fn analyzed_code<B: Backend>(&self, _: &B, _: H160, _: bool) -> Option<AnalyzedCode> {
+		None
+	}
+}
+
+#[derive(Debug)]
+struct Entries {
+	analyses: BTreeMap<H256, AnalyzedCode>,
+	order: VecDeque<H256>,
+	capacity: usize,
+}
+
+/// Cache of analyzed code, keyed by code hash. Clones of a cache share its
+/// entries, so one cache can be used by the executors of many transactions.
+/// Once the cache is full, the oldest entries are evicted first.
+#[derive(Clone, Debug)]
+pub struct CodeCache(Rc<RefCell<Entries>>);
+
+impl CodeCache {
+	/// Number of entries kept by a cache created with `new`.
+	pub const DEFAULT_CAPACITY: usize = 1024;
+
+	/// Create a new empty cache, keeping up to `DEFAULT_CAPACITY` entries.
+	pub fn new() -> Self {
+		Self::with_capacity(Self::DEFAULT_CAPACITY)
+	}
+
+	/// Create a new empty cache, keeping up to `capacity` entries.
+	pub fn with_capacity(capacity: usize) -> Self {
+		Self(Rc::new(RefCell::new(Entries {
+			analyses: BTreeMap::new(),
+			order: VecDeque::new(),
+			capacity,
+		})))
+	}
+
+	/// Get the analysis of the code with the given hash, analyzing the code
+	/// returned by `code` if it is not cached yet.
+	pub fn get<F: FnOnce() -> Vec<u8>>(&self, code_hash: H256, code: F) -> AnalyzedCode {
+		if let Some(analyzed) = self.0.borrow().analyses.get(&code_hash) {
+			return analyzed.clone();
+		}
+
+		let analyzed = AnalyzedCode::new(code());
+		self.insert(code_hash, analyzed.clone());
+		analyzed
+	}
+
+	/// Get the analysis of the code with the given hash including its
+	/// static blocks, computing what is not cached yet.
+	pub fn get_with_static_blocks<F: FnOnce() -> Vec<u8>>(
+		&self,
+		code_hash: H256,
+		code: F,
+	) -> AnalyzedCode {
+		let analyzed = self.get(code_hash, code);
+		if analyzed.static_blocks.is_some() {
+			return analyzed;
+		}
+
+		let analyzed = analyzed.with_static_blocks();
+		if let Some(entry) = self.0.borrow_mut().analyses.get_mut(&code_hash) {
+			*entry = analyzed.clone();
+		}
+		analyzed
+	}
+
+	fn insert(&self, code_hash: H256, analyzed: AnalyzedCode) {
+		let mut entries = self.0.borrow_mut();
+		if entries.capacity == 0 {
+			return;
+		}
+		while entries.order.len() >= entries.capacity {
+			if let Some(oldest) = entries.order.pop_front() {
+				entries.analyses.remove(&oldest);
+			}
+		}
+		entries.order.push_back(code_hash);
+		entries.analyses.insert(code_hash, analyzed);
+	}
+
+	/// Maximum number of cached codes.
+	pub fn capacity(&self) -> usize {
+		self.0.borrow().capacity
+	}
+
+	/// Number of cached codes.
+	pub fn len(&self) -> usize {
+		self.0.borrow().analyses.len()
+	}
+
+	/// Whether no code is cached.
+	pub fn is_empty(&self) -> bool {
+		self.0.borrow().analyses.is_empty()
+	}
+
+	/// Remove all cached codes.
+	pub fn clear(&self) {
+		let mut entries = self.0.borrow_mut();
+		entries.analyses.clear();
+		entries.order.clear();
+	}
+}
+
+impl Default for CodeCache {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl AnalysisCache for CodeCache {
+	fn analyzed_code<B: Backend>(
+		&self,
+		backend: &B,
+		address: H160,
+		static_blocks: bool,
+	) -> Option<AnalyzedCode> {
+		let code_hash = backend.code_hash(address);
+		let code = || backend.code(address);
+		Some(if static_blocks {
+			self.get_with_static_blocks(code_hash, code)
+		} else {
+			self.get(code_hash, code)
+		})
+	}
+}
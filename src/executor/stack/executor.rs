@@ -1,8 +1,11 @@
 use crate::backend::Backend;
-use crate::gasometer::{self, Gasometer, StorageTarget};
+use crate::executor::stack::{
+	AnalysisCache, AnalyzedCode, MemoryStackState, MemoryStackSubstateSnapshot,
+};
+use crate::gasometer::{self, ControlFlowGraph, Gasometer, StorageTarget};
 use crate::{
 	Capture, Config, Context, CreateScheme, ExitError, ExitReason, ExitSucceed, Handler, Opcode,
-	Runtime, Stack, Transfer,
+	Resolve, Runtime, RuntimeSnapshot, Stack, Transfer,
 };
 use alloc::{
 	collections::{BTreeMap, BTreeSet},
@@ -11,7 +14,7 @@ use alloc::{
 };
 use core::{cmp::min, convert::Infallible};
 use ethereum::Log;
-use evm_core::{ExitFatal, ExitRevert};
+use evm_core::{EofContainer, EofError, ExitFatal, ExitRevert};
 use primitive_types::{H160, H256, U256};
 use sha3::{Digest, Keccak256};
 
@@ -35,13 +38,180 @@ macro_rules! emit_exit {
 	}};
 }
 
+/// Emit the exit of a create frame, given the result of `cleanup_for_create`.
+/// A successful create returns the deployed code to tracing listeners, while
+/// its return data stays empty.
+macro_rules! emit_create_exit {
+	($self:expr, $result:expr) => {{
+		let result: (ExitReason, Option<H160>, Vec<u8>) = $result;
+		#[cfg(feature = "tracing")]
+		{
+			let (reason, address, return_value) = &result;
+			event!(Exit {
+				reason,
+				return_value: &match address {
+					Some(address) => $self.state.code(*address),
+					None => return_value.clone(),
+				},
+			});
+		}
+		result
+	}};
+}
+
 pub enum StackExitKind {
 	Succeeded,
 	Reverted,
 	Failed,
 }
 
-#[derive(Default, Clone, Debug)]
+/// Kind of a call frame, with the address it runs for.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "with-codec",
+	derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RuntimeKind {
+	/// Create frame, with the address being created.
+	Create(H160),
+	/// Call frame, with the code address.
+	Call(H160),
+}
+
+/// A runtime waiting to be executed, tagged with the kind of frame it
+/// belongs to.
+struct TaggedRuntime<'config> {
+	kind: RuntimeKind,
+	runtime: Runtime<'config>,
+}
+
+/// A transaction whose execution has been paused, holding all of its call
+/// frames. Resume it with `StackExecutor::resume`.
+pub struct StackExecution<'config> {
+	root: TaggedRuntime<'config>,
+	call_stack: Vec<TaggedRuntime<'config>>,
+}
+
+impl<'config> StackExecution<'config> {
+	/// Current call depth of the execution, starting at zero.
+	pub fn depth(&self) -> usize {
+		self.call_stack.len()
+	}
+}
+
+/// Limits after which a resumable execution pauses. A limit of `None` is
+/// unbounded.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ExecutionLimit {
+	/// Number of opcodes to execute.
+	pub steps: Option<u64>,
+	/// Gas to spend on executed opcodes. Gas forwarded to sub-calls and
+	/// creates is counted by the sub-frames that spend it.
+	pub gas: Option<u64>,
+}
+
+impl ExecutionLimit {
+	/// No limit. The execution runs until it exits.
+	pub const fn unlimited() -> Self {
+		Self {
+			steps: None,
+			gas: None,
+		}
+	}
+
+	/// Pause after the given number of steps.
+	pub const fn steps(steps: u64) -> Self {
+		Self {
+			steps: Some(steps),
+			gas: None,
+		}
+	}
+
+	/// Pause after the given amount of gas has been spent.
+	pub const fn gas(gas: u64) -> Self {
+		Self {
+			steps: None,
+			gas: Some(gas),
+		}
+	}
+
+	fn is_unlimited(&self) -> bool {
+		self.steps.is_none() && self.gas.is_none()
+	}
+
+	fn is_exhausted(&self) -> bool {
+		self.steps == Some(0) || self.gas == Some(0)
+	}
+
+	fn record(&mut self, gas: u64) {
+		self.steps = self.steps.map(|steps| steps.saturating_sub(1));
+		self.gas = self.gas.map(|left| left.saturating_sub(gas));
+	}
+}
+
+/// Result of `StackExecutor::estimate_gas`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GasEstimate {
+	/// Smallest gas limit with which the transaction succeeds, or the block
+	/// gas limit if it fails even with it.
+	pub gas_limit: u64,
+	/// Exit reason of the transaction when run with `gas_limit`.
+	pub exit_reason: ExitReason,
+	/// Return value of the transaction when run with `gas_limit`.
+	pub return_value: Vec<u8>,
+}
+
+/// Snapshot of a call frame.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+	feature = "with-codec",
+	derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StackFrameSnapshot {
+	pub kind: RuntimeKind,
+	pub runtime: RuntimeSnapshot,
+}
+
+/// Snapshot of a paused execution and the state of its executor.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+	feature = "with-codec",
+	derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StackExecutionSnapshot {
+	/// Call frames, outermost first.
+	pub frames: Vec<StackFrameSnapshot>,
+	/// Substates of the executor.
+	pub substate: MemoryStackSubstateSnapshot,
+	/// Whether the executor pre-validates static blocks, see
+	/// `StackExecutor::set_block_validation`.
+	pub block_validation: bool,
+}
+
+/// Error restoring an executor from a snapshot.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SnapshotError {
+	/// The snapshot has no call frames.
+	NoFrames,
+	/// The code of a call frame is an invalid EOF container.
+	InvalidEof(EofError),
+}
+
+/// Call interrupt of the stack-based executor. Holds the new call frame.
+pub struct StackExecutorCallInterrupt<'config>(TaggedRuntime<'config>);
+
+/// Create interrupt of the stack-based executor. Holds the new create frame.
+pub struct StackExecutorCreateInterrupt<'config>(TaggedRuntime<'config>);
+
+#[derive(Default, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "with-codec",
+	derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Accessed {
 	pub accessed_addresses: BTreeSet<H160>,
 	pub accessed_storage: BTreeSet<(H160, H256)>,
@@ -71,6 +241,22 @@ impl Accessed {
 	}
 }
 
+/// Snapshot of substate metadata.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "with-codec",
+	derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StackSubstateMetadataSnapshot {
+	pub gas_limit: u64,
+	/// Gasometer snapshot, or the error the gasometer has failed with.
+	pub gasometer: Result<gasometer::Snapshot, ExitError>,
+	pub is_static: bool,
+	pub depth: Option<u64>,
+	pub accessed: Option<Accessed>,
+}
+
 #[derive(Clone, Debug)]
 pub struct StackSubstateMetadata<'config> {
 	gasometer: Gasometer<'config>,
@@ -94,6 +280,25 @@ impl<'config> StackSubstateMetadata<'config> {
 		}
 	}
 
+	pub fn snapshot(&self) -> StackSubstateMetadataSnapshot {
+		StackSubstateMetadataSnapshot {
+			gas_limit: self.gasometer.gas_limit(),
+			gasometer: self.gasometer.try_snapshot(),
+			is_static: self.is_static,
+			depth: self.depth.map(|depth| depth as u64),
+			accessed: self.accessed.clone(),
+		}
+	}
+
+	pub fn from_snapshot(snapshot: StackSubstateMetadataSnapshot, config: &'config Config) -> Self {
+		Self {
+			gasometer: Gasometer::from_snapshot(snapshot.gas_limit, snapshot.gasometer, config),
+			is_static: snapshot.is_static,
+			depth: snapshot.depth.map(|depth| depth as usize),
+			accessed: snapshot.accessed,
+		}
+	}
+
 	pub fn swallow_commit(&mut self, other: Self) -> Result<(), ExitError> {
 		self.gasometer.record_stipend(other.gasometer.gas())?;
 		self.gasometer
@@ -204,6 +409,16 @@ pub trait StackState<'config>: Backend {
 	fn inc_nonce(&mut self, address: H160);
 	fn set_storage(&mut self, address: H160, key: H256, value: H256);
 	fn reset_storage(&mut self, address: H160);
+	/// Transient storage value of an address at a key. See EIP-1153.
+	fn transient_storage(&self, address: H160, key: H256) -> H256;
+	fn set_transient_storage(&mut self, address: H160, key: H256, value: H256);
+	/// Whether the account was created in the current transaction. See
+	/// EIP-6780.
+	fn created(&self, address: H160) -> bool;
+	fn set_created(&mut self, address: H160);
+	/// Drop the changes scoped to the transaction that just ended, such as
+	/// transient storage and created accounts.
+	fn end_transaction(&mut self) {}
 	fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>);
 	fn set_deleted(&mut self, address: H160);
 	fn set_code(&mut self, address: H160, code: Vec<u8>);
@@ -306,26 +521,22 @@ impl PrecompileSet for BTreeMap<H160, PrecompileFn> {
 	}
 }
 
-/// Stack-based executor.
-pub struct StackExecutor<'config, 'precompiles, S, P> {
+/// Stack-based executor. Code is analyzed on every call unless a cache is
+/// set with `with_code_cache`.
+pub struct StackExecutor<'config, 'precompiles, S, P, C = ()> {
 	config: &'config Config,
 	state: S,
 	precompile_set: &'precompiles P,
+	code_cache: C,
+	block_validation: bool,
+	/// Gas left in the frame that last entered a sub-call or create, after
+	/// the cost of the opcode and before the gas given to the new frame.
+	parent_gas: u64,
 }
 
 impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 	StackExecutor<'config, 'precompiles, S, P>
 {
-	/// Return a reference of the Config.
-	pub fn config(&self) -> &'config Config {
-		self.config
-	}
-
-	/// Return a reference to the precompile set.
-	pub fn precompiles(&self) -> &'precompiles P {
-		self.precompile_set
-	}
-
 	/// Create a new stack-based executor with given precompiles.
 	pub fn new_with_precompiles(
 		state: S,
@@ -336,9 +547,82 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			config,
 			state,
 			precompile_set,
+			code_cache: (),
+			block_validation: false,
+			parent_gas: 0,
+		}
+	}
+}
+
+impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet, C: AnalysisCache>
+	StackExecutor<'config, 'precompiles, S, P, C>
+{
+	/// Return a reference of the Config.
+	pub fn config(&self) -> &'config Config {
+		self.config
+	}
+
+	/// Return a reference to the precompile set.
+	pub fn precompiles(&self) -> &'precompiles P {
+		self.precompile_set
+	}
+
+	/// Cache of analyzed code used by the executor.
+	pub fn code_cache(&self) -> &C {
+		&self.code_cache
+	}
+
+	/// Use a cache of analyzed code, such as a `CodeCache` shared with other
+	/// executors.
+	pub fn with_code_cache<D: AnalysisCache>(
+		self,
+		code_cache: D,
+	) -> StackExecutor<'config, 'precompiles, S, P, D> {
+		StackExecutor {
+			config: self.config,
+			state: self.state,
+			precompile_set: self.precompile_set,
+			code_cache,
+			block_validation: self.block_validation,
+			parent_gas: self.parent_gas,
 		}
 	}
 
+	/// Pre-validate runs of instructions with static costs once per run,
+	/// charging their gas and checking their stack bounds at once, instead
+	/// of once per instruction. Gas usage and exit reasons are the same, but
+	/// the gas costs reported to tracing listeners are summed per run.
+	pub fn set_block_validation(&mut self, enabled: bool) {
+		self.block_validation = enabled;
+	}
+
+	fn new_runtime(&self, code: AnalyzedCode, data: Vec<u8>, context: Context) -> Runtime<'config> {
+		let code = if self.block_validation {
+			code.with_static_blocks()
+		} else {
+			code
+		};
+		let mut runtime =
+			Runtime::new_with_valids(code.code, code.valids, Rc::new(data), context, self.config);
+		if let Some(static_blocks) = code.static_blocks.filter(|_| self.block_validation) {
+			runtime.set_static_blocks(static_blocks);
+		}
+		runtime
+	}
+
+	fn call_runtime(
+		&self,
+		code_address: H160,
+		data: Vec<u8>,
+		context: Context,
+	) -> Runtime<'config> {
+		let code = self
+			.code_cache
+			.analyzed_code(&self.state, code_address, self.block_validation)
+			.unwrap_or_else(|| AnalyzedCode::new(self.state.code(code_address)));
+		self.new_runtime(code, data, context)
+	}
+
 	pub fn state(&self) -> &S {
 		&self.state
 	}
@@ -366,10 +650,175 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 	}
 
 	/// Execute the runtime until it returns.
-	pub fn execute(&mut self, runtime: &mut Runtime) -> ExitReason {
-		match runtime.run(self) {
-			Capture::Exit(s) => s,
-			Capture::Trap(_) => unreachable!("Trap is Infallible"),
+	///
+	/// Sub-calls and creates are not executed recursively. Their frames are
+	/// kept in a heap-allocated call stack, so the native stack usage does
+	/// not depend on the call depth.
+	pub fn execute(&mut self, runtime: &mut Runtime<'config>) -> ExitReason {
+		match self.execute_with_call_stack(
+			runtime,
+			&mut Vec::new(),
+			&mut ExecutionLimit::unlimited(),
+		) {
+			Capture::Exit(reason) => reason,
+			Capture::Trap(()) => unreachable!("execution without limit does not pause"),
+		}
+	}
+
+	/// Execute `runtime` together with the sub-frames in `call_stack`, until
+	/// `runtime` exits or the limit is reached.
+	fn execute_with_call_stack(
+		&mut self,
+		runtime: &mut Runtime<'config>,
+		call_stack: &mut Vec<TaggedRuntime<'config>>,
+		limit: &mut ExecutionLimit,
+	) -> Capture<ExitReason, ()> {
+		loop {
+			let current = match call_stack.last_mut() {
+				Some(frame) => &mut frame.runtime,
+				None => &mut *runtime,
+			};
+
+			let reason = match self.run_frame(current, limit) {
+				Some(Capture::Exit(reason)) => reason,
+				Some(Capture::Trap(frame)) => {
+					call_stack.push(frame);
+					continue;
+				}
+				None => return Capture::Trap(()),
+			};
+
+			let frame = match call_stack.pop() {
+				Some(frame) => frame,
+				None => return Capture::Exit(reason),
+			};
+			let return_value = frame.runtime.machine().return_value();
+			let parent = match call_stack.last_mut() {
+				Some(frame) => &mut frame.runtime,
+				None => &mut *runtime,
+			};
+
+			// A fatal result exits the parent, which is picked up on its next run.
+			let _ = match frame.kind {
+				RuntimeKind::Call(code_address) => {
+					let (reason, return_data) =
+						self.cleanup_for_call(code_address, reason, return_value);
+					let (reason, return_data) = emit_exit!(reason, return_data);
+					parent.finish_call(reason, return_data)
+				}
+				RuntimeKind::Create(address) => {
+					let (reason, address, return_data) = emit_create_exit!(
+						self,
+						self.cleanup_for_create(address, reason, return_value)
+					);
+					parent.finish_create(reason, address, return_data)
+				}
+			};
+		}
+	}
+
+	/// Run a single frame until it exits or traps into a new frame. Returns
+	/// `None` if the limit is reached first.
+	fn run_frame(
+		&mut self,
+		runtime: &mut Runtime<'config>,
+		limit: &mut ExecutionLimit,
+	) -> Option<Capture<ExitReason, TaggedRuntime<'config>>> {
+		if limit.is_unlimited() {
+			// Interrupts are resolved with `finish_call` and `finish_create`
+			// once their frame exits.
+			return Some(match runtime.run(self) {
+				Capture::Exit(reason) => Capture::Exit(reason),
+				Capture::Trap(Resolve::Call(interrupt, resolve)) => {
+					resolve.detach();
+					Capture::Trap(interrupt.0)
+				}
+				Capture::Trap(Resolve::Create(interrupt, resolve)) => {
+					resolve.detach();
+					Capture::Trap(interrupt.0)
+				}
+			});
+		}
+
+		loop {
+			if limit.is_exhausted() {
+				return None;
+			}
+
+			let gas_before = self.gas();
+			let capture = match runtime.step(self) {
+				Ok(()) => None,
+				Err(Capture::Exit(reason)) => Some(Capture::Exit(reason)),
+				Err(Capture::Trap(Resolve::Call(interrupt, resolve))) => {
+					resolve.detach();
+					Some(Capture::Trap(interrupt.0))
+				}
+				Err(Capture::Trap(Resolve::Create(interrupt, resolve))) => {
+					resolve.detach();
+					Some(Capture::Trap(interrupt.0))
+				}
+			};
+
+			// After a trap the gasometer is the one of the new frame, which
+			// counts the gas given to it as it spends it.
+			let spent = match capture {
+				Some(Capture::Trap(_)) => gas_before.saturating_sub(self.parent_gas),
+				_ => gas_before.saturating_sub(self.gas()),
+			};
+			limit.record(spent);
+
+			if capture.is_some() {
+				return capture;
+			}
+		}
+	}
+
+	/// Resume a paused execution, running it until it exits or the limit is
+	/// reached. Returns the still-paused execution in the latter case.
+	pub fn resume(
+		&mut self,
+		mut execution: StackExecution<'config>,
+		limit: ExecutionLimit,
+	) -> Capture<(ExitReason, Vec<u8>), StackExecution<'config>> {
+		let mut limit = limit;
+		let reason = match self.execute_with_call_stack(
+			&mut execution.root.runtime,
+			&mut execution.call_stack,
+			&mut limit,
+		) {
+			Capture::Exit(reason) => reason,
+			Capture::Trap(()) => return Capture::Trap(execution),
+		};
+
+		let return_value = execution.root.runtime.machine().return_value();
+		let result = match execution.root.kind {
+			RuntimeKind::Call(code_address) => {
+				let (reason, return_data) =
+					self.cleanup_for_call(code_address, reason, return_value);
+				emit_exit!(reason, return_data)
+			}
+			RuntimeKind::Create(address) => {
+				let (reason, _, return_data) =
+					emit_create_exit!(self, self.cleanup_for_create(address, reason, return_value));
+				(reason, return_data)
+			}
+		};
+		self.state.end_transaction();
+		Capture::Exit(result)
+	}
+
+	pub(crate) fn finish_transact(
+		&mut self,
+		capture: Capture<(ExitReason, Vec<u8>), StackExecution<'config>>,
+	) -> (ExitReason, Vec<u8>) {
+		let execution = match capture {
+			Capture::Exit(result) => return result,
+			Capture::Trap(execution) => execution,
+		};
+
+		match self.resume(execution, ExecutionLimit::unlimited()) {
+			Capture::Exit(result) => result,
+			Capture::Trap(_) => unreachable!("execution without limit does not pause"),
 		}
 	}
 
@@ -383,6 +832,12 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		init_code: &[u8],
 		access_list: &[(H160, Vec<H256>)],
 	) -> Result<(), ExitError> {
+		if let Some(limit) = self.config.max_initcode_size {
+			if init_code.len() > limit {
+				return Err(ExitError::CreateContractLimit);
+			}
+		}
+
 		let transaction_cost = gasometer::create_transaction_cost(init_code, access_list);
 		let gasometer = &mut self.state.metadata_mut().gasometer;
 		gasometer.record_transaction(transaction_cost)
@@ -397,6 +852,20 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		gas_limit: u64,
 		access_list: Vec<(H160, Vec<H256>)>, // See EIP-2930
 	) -> ExitReason {
+		let capture = self.begin_create(caller, value, init_code, gas_limit, access_list);
+		self.finish_transact(capture).0
+	}
+
+	/// Start a `CREATE` transaction without running it. The returned
+	/// execution is run with `resume`.
+	pub fn begin_create(
+		&mut self,
+		caller: H160,
+		value: U256,
+		init_code: Vec<u8>,
+		gas_limit: u64,
+		access_list: Vec<(H160, Vec<H256>)>, // See EIP-2930
+	) -> Capture<(ExitReason, Vec<u8>), StackExecution<'config>> {
 		event!(TransactCreate {
 			caller,
 			value,
@@ -406,9 +875,10 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		});
 
 		if let Err(e) = self.record_create_transaction_cost(&init_code, &access_list) {
-			return emit_exit!(e.into());
+			return Capture::Exit((emit_exit!(e.into()), Vec::new()));
 		}
 		self.initialize_with_access_list(access_list);
+		self.warm_coinbase();
 
 		match self.create_inner(
 			caller,
@@ -418,8 +888,11 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			Some(gas_limit),
 			false,
 		) {
-			Capture::Exit((s, _, _)) => emit_exit!(s),
-			Capture::Trap(_) => unreachable!(),
+			Capture::Exit((s, _, v)) => Capture::Exit((emit_exit!(s), v)),
+			Capture::Trap(interrupt) => Capture::Trap(StackExecution {
+				root: interrupt.0,
+				call_stack: Vec::new(),
+			}),
 		}
 	}
 
@@ -433,6 +906,21 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		gas_limit: u64,
 		access_list: Vec<(H160, Vec<H256>)>, // See EIP-2930
 	) -> ExitReason {
+		let capture = self.begin_create2(caller, value, init_code, salt, gas_limit, access_list);
+		self.finish_transact(capture).0
+	}
+
+	/// Start a `CREATE2` transaction without running it. The returned
+	/// execution is run with `resume`.
+	pub fn begin_create2(
+		&mut self,
+		caller: H160,
+		value: U256,
+		init_code: Vec<u8>,
+		salt: H256,
+		gas_limit: u64,
+		access_list: Vec<(H160, Vec<H256>)>, // See EIP-2930
+	) -> Capture<(ExitReason, Vec<u8>), StackExecution<'config>> {
 		let code_hash = H256::from_slice(Keccak256::digest(&init_code).as_slice());
 		event!(TransactCreate2 {
 			caller,
@@ -448,9 +936,10 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		});
 
 		if let Err(e) = self.record_create_transaction_cost(&init_code, &access_list) {
-			return emit_exit!(e.into());
+			return Capture::Exit((emit_exit!(e.into()), Vec::new()));
 		}
 		self.initialize_with_access_list(access_list);
+		self.warm_coinbase();
 
 		match self.create_inner(
 			caller,
@@ -464,8 +953,11 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			Some(gas_limit),
 			false,
 		) {
-			Capture::Exit((s, _, _)) => emit_exit!(s),
-			Capture::Trap(_) => unreachable!(),
+			Capture::Exit((s, _, v)) => Capture::Exit((emit_exit!(s), v)),
+			Capture::Trap(interrupt) => Capture::Trap(StackExecution {
+				root: interrupt.0,
+				call_stack: Vec::new(),
+			}),
 		}
 	}
 
@@ -484,6 +976,21 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		gas_limit: u64,
 		access_list: Vec<(H160, Vec<H256>)>,
 	) -> (ExitReason, Vec<u8>) {
+		let capture = self.begin_call(caller, address, value, data, gas_limit, access_list);
+		self.finish_transact(capture)
+	}
+
+	/// Start a `CALL` transaction without running it. The returned execution
+	/// is run with `resume`, and can be paused and snapshotted in between.
+	pub fn begin_call(
+		&mut self,
+		caller: H160,
+		address: H160,
+		value: U256,
+		data: Vec<u8>,
+		gas_limit: u64,
+		access_list: Vec<(H160, Vec<H256>)>,
+	) -> Capture<(ExitReason, Vec<u8>), StackExecution<'config>> {
 		event!(TransactCall {
 			caller,
 			address,
@@ -496,7 +1003,7 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		let gasometer = &mut self.state.metadata_mut().gasometer;
 		match gasometer.record_transaction(transaction_cost) {
 			Ok(()) => (),
-			Err(e) => return emit_exit!(e.into(), Vec::new()),
+			Err(e) => return Capture::Exit(emit_exit!(e.into(), Vec::new())),
 		}
 
 		// Initialize initial addresses for EIP-2929
@@ -505,6 +1012,7 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			self.state.metadata_mut().access_addresses(addresses);
 
 			self.initialize_with_access_list(access_list);
+			self.warm_coinbase();
 		}
 
 		self.state.inc_nonce(caller);
@@ -529,9 +1037,109 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			false,
 			context,
 		) {
-			Capture::Exit((s, v)) => emit_exit!(s, v),
-			Capture::Trap(_) => unreachable!(),
+			Capture::Exit((s, v)) => Capture::Exit(emit_exit!(s, v)),
+			Capture::Trap(interrupt) => Capture::Trap(StackExecution {
+				root: interrupt.0,
+				call_stack: Vec::new(),
+			}),
+		}
+	}
+
+	/// Estimate the gas limit needed by a transaction, as `eth_estimateGas`
+	/// does. The transaction is first run with the block gas limit. If it
+	/// succeeds, the smallest gas limit with which it still succeeds is
+	/// binary-searched, so that it neither runs out of gas nor fails because
+	/// of the 63/64 rule or the call stipend. An `address` of `None` is a
+	/// `CREATE` transaction with `data` as init code.
+	///
+	/// Each run happens in a substate that is discarded afterwards, so the
+	/// state of the executor is left untouched.
+	pub fn estimate_gas(
+		&mut self,
+		caller: H160,
+		address: Option<H160>,
+		value: U256,
+		data: Vec<u8>,
+		access_list: Vec<(H160, Vec<H256>)>,
+	) -> GasEstimate {
+		let block_gas_limit = self.state.block_gas_limit();
+		let cap = if block_gas_limit > U256::from(u64::MAX) {
+			u64::MAX
+		} else {
+			block_gas_limit.as_u64()
+		};
+
+		let (exit_reason, return_value) = self.transact_discarded(
+			caller,
+			address,
+			value,
+			data.clone(),
+			cap,
+			access_list.clone(),
+		);
+		if !exit_reason.is_succeed() {
+			return GasEstimate {
+				gas_limit: cap,
+				exit_reason,
+				return_value,
+			};
+		}
+
+		let mut lo = match address {
+			Some(_) => self.config.gas_transaction_call,
+			None => self.config.gas_transaction_create,
+		}
+		.saturating_sub(1);
+		let mut hi = GasEstimate {
+			gas_limit: cap,
+			exit_reason,
+			return_value,
+		};
+		while lo + 1 < hi.gas_limit {
+			let mid = lo + (hi.gas_limit - lo) / 2;
+			let (exit_reason, return_value) = self.transact_discarded(
+				caller,
+				address,
+				value,
+				data.clone(),
+				mid,
+				access_list.clone(),
+			);
+			if exit_reason.is_succeed() {
+				hi = GasEstimate {
+					gas_limit: mid,
+					exit_reason,
+					return_value,
+				};
+			} else {
+				lo = mid;
+			}
 		}
+
+		hi
+	}
+
+	fn transact_discarded(
+		&mut self,
+		caller: H160,
+		address: Option<H160>,
+		value: U256,
+		data: Vec<u8>,
+		gas_limit: u64,
+		access_list: Vec<(H160, Vec<H256>)>,
+	) -> (ExitReason, Vec<u8>) {
+		self.enter_substate(gas_limit, false);
+		// Run as a transaction, not as a sub-call of the current substate.
+		self.state.metadata_mut().depth = None;
+
+		let capture = match address {
+			Some(address) => self.begin_call(caller, address, value, data, gas_limit, access_list),
+			None => self.begin_create(caller, value, data, gas_limit, access_list),
+		};
+		let result = self.finish_transact(capture);
+
+		let _ = self.exit_substate(StackExitKind::Failed);
+		result
 	}
 
 	/// Get used gas for the current executor, given the price.
@@ -590,6 +1198,14 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		self.state.metadata_mut().access_storages(storage_keys);
 	}
 
+	/// Warm the coinbase at the start of a transaction. See EIP-3651.
+	fn warm_coinbase(&mut self) {
+		if self.config.warm_coinbase_address {
+			let coinbase = self.block_coinbase();
+			self.state.metadata_mut().access_address(coinbase);
+		}
+	}
+
 	fn create_inner(
 		&mut self,
 		caller: H160,
@@ -598,7 +1214,7 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		init_code: Vec<u8>,
 		target_gas: Option<u64>,
 		take_l64: bool,
-	) -> Capture<(ExitReason, Option<H160>, Vec<u8>), Infallible> {
+	) -> Capture<(ExitReason, Option<H160>, Vec<u8>), StackExecutorCreateInterrupt<'config>> {
 		macro_rules! try_or_fail {
 			( $e:expr ) => {
 				match $e {
@@ -608,15 +1224,6 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			};
 		}
 
-		fn check_first_byte(config: &Config, code: &[u8]) -> Result<(), ExitError> {
-			if config.disallow_executable_format {
-				if let Some(0xef) = code.get(0) {
-					return Err(ExitError::InvalidCode);
-				}
-			}
-			Ok(())
-		}
-
 		fn l64(gas: u64) -> u64 {
 			gas - gas / 64
 		}
@@ -661,6 +1268,7 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		let target_gas = target_gas.unwrap_or(after_gas);
 
 		let gas_limit = min(after_gas, target_gas);
+		self.parent_gas = self.state.metadata().gasometer.gas();
 		try_or_fail!(self.state.metadata_mut().gasometer.record_cost(gas_limit));
 
 		self.state.inc_nonce(caller);
@@ -681,6 +1289,15 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			self.state.reset_storage(address);
 		}
 
+		// Only valid EOF containers can run as initcode (EIP-3540).
+		if self.config.has_eof
+			&& init_code.starts_with(&[0xef, 0x00])
+			&& EofContainer::validate(&init_code).is_err()
+		{
+			let _ = self.exit_substate(StackExitKind::Failed);
+			return Capture::Exit((ExitError::InvalidCode.into(), None, Vec::new()));
+		}
+
 		let context = Context {
 			address,
 			caller,
@@ -702,77 +1319,14 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		if self.config.create_increase_nonce {
 			self.state.inc_nonce(address);
 		}
+		self.state.set_created(address);
 
-		let mut runtime = Runtime::new(
-			Rc::new(init_code),
-			Rc::new(Vec::new()),
-			context,
-			self.config,
-		);
-
-		let reason = self.execute(&mut runtime);
-		log::debug!(target: "evm", "Create execution using address {}: {:?}", address, reason);
+		let runtime = self.new_runtime(AnalyzedCode::new(init_code), Vec::new(), context);
 
-		match reason {
-			ExitReason::Succeed(s) => {
-				let out = runtime.machine().return_value();
-
-				// As of EIP-3541 code starting with 0xef cannot be deployed
-				if let Err(e) = check_first_byte(self.config, &out) {
-					self.state.metadata_mut().gasometer.fail();
-					let _ = self.exit_substate(StackExitKind::Failed);
-					return Capture::Exit((e.into(), None, Vec::new()));
-				}
-
-				if let Some(limit) = self.config.create_contract_limit {
-					if out.len() > limit {
-						self.state.metadata_mut().gasometer.fail();
-						let _ = self.exit_substate(StackExitKind::Failed);
-						return Capture::Exit((
-							ExitError::CreateContractLimit.into(),
-							None,
-							Vec::new(),
-						));
-					}
-				}
-
-				match self
-					.state
-					.metadata_mut()
-					.gasometer
-					.record_deposit(out.len())
-				{
-					Ok(()) => {
-						let e = self.exit_substate(StackExitKind::Succeeded);
-						self.state.set_code(address, out);
-						try_or_fail!(e);
-						Capture::Exit((ExitReason::Succeed(s), Some(address), Vec::new()))
-					}
-					Err(e) => {
-						let _ = self.exit_substate(StackExitKind::Failed);
-						Capture::Exit((ExitReason::Error(e), None, Vec::new()))
-					}
-				}
-			}
-			ExitReason::Error(e) => {
-				self.state.metadata_mut().gasometer.fail();
-				let _ = self.exit_substate(StackExitKind::Failed);
-				Capture::Exit((ExitReason::Error(e), None, Vec::new()))
-			}
-			ExitReason::Revert(e) => {
-				let _ = self.exit_substate(StackExitKind::Reverted);
-				Capture::Exit((
-					ExitReason::Revert(e),
-					None,
-					runtime.machine().return_value(),
-				))
-			}
-			ExitReason::Fatal(e) => {
-				self.state.metadata_mut().gasometer.fail();
-				let _ = self.exit_substate(StackExitKind::Failed);
-				Capture::Exit((ExitReason::Fatal(e), None, Vec::new()))
-			}
-		}
+		Capture::Trap(StackExecutorCreateInterrupt(TaggedRuntime {
+			kind: RuntimeKind::Create(address),
+			runtime,
+		}))
 	}
 
 	#[allow(clippy::too_many_arguments)]
@@ -786,7 +1340,7 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		take_l64: bool,
 		take_stipend: bool,
 		context: Context,
-	) -> Capture<(ExitReason, Vec<u8>), Infallible> {
+	) -> Capture<(ExitReason, Vec<u8>), StackExecutorCallInterrupt<'config>> {
 		macro_rules! try_or_fail {
 			( $e:expr ) => {
 				match $e {
@@ -825,6 +1379,7 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		let target_gas = target_gas.unwrap_or(after_gas);
 		let mut gas_limit = min(target_gas, after_gas);
 
+		self.parent_gas = self.state.metadata().gasometer.gas();
 		try_or_fail!(self.state.metadata_mut().gasometer.record_cost(gas_limit));
 
 		if let Some(transfer) = transfer.as_ref() {
@@ -833,8 +1388,6 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			}
 		}
 
-		let code = self.code(code_address);
-
 		self.enter_substate(gas_limit, is_static);
 		self.state.touch(context.address);
 
@@ -905,39 +1458,201 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			};
 		}
 
-		let mut runtime = Runtime::new(Rc::new(code), Rc::new(input), context, self.config);
+		let runtime = self.call_runtime(code_address, input, context);
+
+		Capture::Trap(StackExecutorCallInterrupt(TaggedRuntime {
+			kind: RuntimeKind::Call(code_address),
+			runtime,
+		}))
+	}
+
+	fn cleanup_for_create(
+		&mut self,
+		address: H160,
+		reason: ExitReason,
+		return_value: Vec<u8>,
+	) -> (ExitReason, Option<H160>, Vec<u8>) {
+		fn check_first_byte(config: &Config, code: &[u8]) -> Result<(), ExitError> {
+			if let Some(0xef) = code.get(0) {
+				if config.has_eof {
+					return EofContainer::validate(code)
+						.map(|_| ())
+						.map_err(|_| ExitError::InvalidCode);
+				}
+				if config.disallow_executable_format {
+					return Err(ExitError::InvalidCode);
+				}
+			}
+			Ok(())
+		}
+
+		log::debug!(target: "evm", "Create execution using address {}: {:?}", address, reason);
+
+		match reason {
+			ExitReason::Succeed(s) => {
+				let out = return_value;
+
+				// As of EIP-3541 code starting with 0xef cannot be deployed,
+				// unless it is a valid EOF container as of EIP-3540
+				if let Err(e) = check_first_byte(self.config, &out) {
+					self.state.metadata_mut().gasometer.fail();
+					let _ = self.exit_substate(StackExitKind::Failed);
+					return (e.into(), None, Vec::new());
+				}
 
-		let reason = self.execute(&mut runtime);
+				if let Some(limit) = self.config.create_contract_limit {
+					if out.len() > limit {
+						self.state.metadata_mut().gasometer.fail();
+						let _ = self.exit_substate(StackExitKind::Failed);
+						return (ExitError::CreateContractLimit.into(), None, Vec::new());
+					}
+				}
+
+				match self
+					.state
+					.metadata_mut()
+					.gasometer
+					.record_deposit(out.len())
+				{
+					Ok(()) => {
+						let e = self.exit_substate(StackExitKind::Succeeded);
+						self.state.set_code(address, out);
+						if let Err(e) = e {
+							return (e.into(), None, Vec::new());
+						}
+						(ExitReason::Succeed(s), Some(address), Vec::new())
+					}
+					Err(e) => {
+						let _ = self.exit_substate(StackExitKind::Failed);
+						(ExitReason::Error(e), None, Vec::new())
+					}
+				}
+			}
+			ExitReason::Error(e) => {
+				self.state.metadata_mut().gasometer.fail();
+				let _ = self.exit_substate(StackExitKind::Failed);
+				(ExitReason::Error(e), None, Vec::new())
+			}
+			ExitReason::Revert(e) => {
+				let _ = self.exit_substate(StackExitKind::Reverted);
+				(ExitReason::Revert(e), None, return_value)
+			}
+			ExitReason::Fatal(e) => {
+				self.state.metadata_mut().gasometer.fail();
+				let _ = self.exit_substate(StackExitKind::Failed);
+				(ExitReason::Fatal(e), None, Vec::new())
+			}
+		}
+	}
+
+	fn cleanup_for_call(
+		&mut self,
+		code_address: H160,
+		reason: ExitReason,
+		return_value: Vec<u8>,
+	) -> (ExitReason, Vec<u8>) {
 		log::debug!(target: "evm", "Call execution using address {}: {:?}", code_address, reason);
 
 		match reason {
 			ExitReason::Succeed(s) => {
 				let _ = self.exit_substate(StackExitKind::Succeeded);
-				Capture::Exit((ExitReason::Succeed(s), runtime.machine().return_value()))
+				(ExitReason::Succeed(s), return_value)
 			}
 			ExitReason::Error(e) => {
 				let _ = self.exit_substate(StackExitKind::Failed);
-				Capture::Exit((ExitReason::Error(e), Vec::new()))
+				(ExitReason::Error(e), Vec::new())
 			}
 			ExitReason::Revert(e) => {
 				let _ = self.exit_substate(StackExitKind::Reverted);
-				Capture::Exit((ExitReason::Revert(e), runtime.machine().return_value()))
+				(ExitReason::Revert(e), return_value)
 			}
 			ExitReason::Fatal(e) => {
 				self.state.metadata_mut().gasometer.fail();
 				let _ = self.exit_substate(StackExitKind::Failed);
-				Capture::Exit((ExitReason::Fatal(e), Vec::new()))
+				(ExitReason::Fatal(e), Vec::new())
 			}
 		}
 	}
 }
 
-impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet> Handler
-	for StackExecutor<'config, 'precompiles, S, P>
+impl<'backend, 'config, 'precompiles, B: Backend, P: PrecompileSet, C: AnalysisCache>
+	StackExecutor<'config, 'precompiles, MemoryStackState<'backend, 'config, B>, P, C>
+{
+	/// Take a snapshot of a paused execution, together with the substates of
+	/// the executor.
+	pub fn snapshot(&self, execution: &StackExecution<'config>) -> StackExecutionSnapshot {
+		let frames = core::iter::once(&execution.root)
+			.chain(execution.call_stack.iter())
+			.map(|frame| StackFrameSnapshot {
+				kind: frame.kind,
+				runtime: frame.runtime.snapshot(),
+			})
+			.collect();
+
+		StackExecutionSnapshot {
+			frames,
+			substate: self.state.snapshot(),
+			block_validation: self.block_validation,
+		}
+	}
+
+	/// Restore an executor and its paused execution from a snapshot. The
+	/// executor uses `code_cache` for the calls made after it is restored.
+	pub fn from_snapshot(
+		snapshot: StackExecutionSnapshot,
+		backend: &'backend B,
+		config: &'config Config,
+		precompile_set: &'precompiles P,
+		code_cache: C,
+	) -> Result<(Self, StackExecution<'config>), SnapshotError> {
+		let block_validation = snapshot.block_validation;
+		let mut frames = snapshot
+			.frames
+			.into_iter()
+			.map(|frame| {
+				let static_blocks = if block_validation && !frame.runtime.machine.eof {
+					let code = &frame.runtime.machine.code;
+					Some(ControlFlowGraph::new(code).static_blocks(code))
+				} else {
+					None
+				};
+				let mut runtime = Runtime::from_snapshot(frame.runtime, config)
+					.map_err(SnapshotError::InvalidEof)?;
+				if let Some(static_blocks) = static_blocks {
+					runtime.set_static_blocks(Rc::new(static_blocks));
+				}
+				Ok(TaggedRuntime {
+					kind: frame.kind,
+					runtime,
+				})
+			})
+			.collect::<Result<Vec<_>, _>>()?
+			.into_iter();
+		let root = frames.next().ok_or(SnapshotError::NoFrames)?;
+		let execution = StackExecution {
+			root,
+			call_stack: frames.collect(),
+		};
+
+		let state = MemoryStackState::from_snapshot(snapshot.substate, backend, config);
+		let executor = Self {
+			config,
+			state,
+			precompile_set,
+			code_cache,
+			block_validation,
+			parent_gas: 0,
+		};
+		Ok((executor, execution))
+	}
+}
+
+impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet, C: AnalysisCache> Handler
+	for StackExecutor<'config, 'precompiles, S, P, C>
 {
-	type CreateInterrupt = Infallible;
+	type CreateInterrupt = StackExecutorCreateInterrupt<'config>;
 	type CreateFeedback = Infallible;
-	type CallInterrupt = Infallible;
+	type CallInterrupt = StackExecutorCallInterrupt<'config>;
 	type CallFeedback = Infallible;
 
 	fn balance(&self, address: H160) -> U256 {
@@ -953,7 +1668,7 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet> Handler
 			return H256::default();
 		}
 
-		H256::from_slice(Keccak256::digest(&self.state.code(address)).as_slice())
+		self.state.code_hash(address)
 	}
 
 	fn code(&self, address: H160) -> Vec<u8> {
@@ -970,6 +1685,10 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet> Handler
 			.unwrap_or_default()
 	}
 
+	fn transient_storage(&self, address: H160, index: H256) -> H256 {
+		self.state.transient_storage(address, index)
+	}
+
 	fn exists(&self, address: H160) -> bool {
 		if self.config.empty_considered_exists {
 			self.state.exists(address)
@@ -1016,6 +1735,15 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet> Handler
 	fn block_base_fee_per_gas(&self) -> U256 {
 		self.state.block_base_fee_per_gas()
 	}
+	fn block_randomness(&self) -> Option<H256> {
+		self.state.block_randomness()
+	}
+	fn block_blob_base_fee(&self) -> U256 {
+		self.state.block_blob_base_fee()
+	}
+	fn blob_hash(&self, index: usize) -> Option<H256> {
+		self.state.blob_hash(index)
+	}
 	fn chain_id(&self) -> U256 {
 		self.state.chain_id()
 	}
@@ -1034,6 +1762,16 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet> Handler
 		Ok(())
 	}
 
+	fn set_transient_storage(
+		&mut self,
+		address: H160,
+		index: H256,
+		value: H256,
+	) -> Result<(), ExitError> {
+		self.state.set_transient_storage(address, index, value);
+		Ok(())
+	}
+
 	fn mark_delete(&mut self, address: H160, target: H160) -> Result<(), ExitError> {
 		let balance = self.balance(address);
 
@@ -1043,6 +1781,19 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet> Handler
 			balance,
 		});
 
+		// After EIP-6780, only accounts created in the same transaction are
+		// deleted. Others only send their balance.
+		if self.config.suicide_only_in_same_tx && !self.state.created(address) {
+			if address != target {
+				self.state.transfer(Transfer {
+					source: address,
+					target,
+					value: balance,
+				})?;
+			}
+			return Ok(());
+		}
+
 		self.state.transfer(Transfer {
 			source: address,
 			target,
@@ -1134,6 +1885,11 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet> Handler
 		capture
 	}
 
+	fn pre_validate_static(&mut self, _context: &Context, gas: u64) -> bool {
+		let gasometer = &mut self.state.metadata_mut().gasometer;
+		gasometer.gas() >= gas && gasometer.record_cost(gas).is_ok()
+	}
+
 	#[inline]
 	fn pre_validate(
 		&mut self,
@@ -1,6 +1,8 @@
 use crate::backend::{Apply, Backend, Basic, Log};
-use crate::executor::stack::executor::{Accessed, StackState, StackSubstateMetadata};
-use crate::{ExitError, Transfer};
+use crate::executor::stack::executor::{
+	Accessed, StackState, StackSubstateMetadata, StackSubstateMetadataSnapshot,
+};
+use crate::{Config, ExitError, Transfer};
 use alloc::{
 	boxed::Box,
 	collections::{BTreeMap, BTreeSet},
@@ -8,14 +10,41 @@ use alloc::{
 };
 use core::mem;
 use primitive_types::{H160, H256, U256};
-
-#[derive(Clone, Debug)]
+use sha3::{Digest, Keccak256};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "with-codec",
+	derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MemoryStackAccount {
 	pub basic: Basic,
 	pub code: Option<Vec<u8>>,
 	pub reset: bool,
 }
 
+/// Snapshot of a memory stack substate, including all of its parents.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "with-codec",
+	derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryStackSubstateSnapshot {
+	pub metadata: StackSubstateMetadataSnapshot,
+	pub parent: Option<Box<MemoryStackSubstateSnapshot>>,
+	pub logs: Vec<Log>,
+	pub accounts: BTreeMap<H160, MemoryStackAccount>,
+	/// Storage changes as `(address, key, value)`, so that the snapshot has
+	/// no compound map keys.
+	pub storages: Vec<(H160, H256, H256)>,
+	pub deletes: BTreeSet<H160>,
+	/// Transient storage changes as `(address, key, value)`.
+	pub transient_storage: Vec<(H160, H256, H256)>,
+	pub creates: BTreeSet<H160>,
+}
+
 #[derive(Clone, Debug)]
 pub struct MemoryStackSubstate<'config> {
 	metadata: StackSubstateMetadata<'config>,
@@ -24,6 +53,8 @@ pub struct MemoryStackSubstate<'config> {
 	accounts: BTreeMap<H160, MemoryStackAccount>,
 	storages: BTreeMap<(H160, H256), H256>,
 	deletes: BTreeSet<H160>,
+	transient_storage: BTreeMap<(H160, H256), H256>,
+	creates: BTreeSet<H160>,
 }
 
 impl<'config> MemoryStackSubstate<'config> {
@@ -35,6 +66,55 @@ impl<'config> MemoryStackSubstate<'config> {
 			accounts: BTreeMap::new(),
 			storages: BTreeMap::new(),
 			deletes: BTreeSet::new(),
+			transient_storage: BTreeMap::new(),
+			creates: BTreeSet::new(),
+		}
+	}
+
+	pub fn snapshot(&self) -> MemoryStackSubstateSnapshot {
+		MemoryStackSubstateSnapshot {
+			metadata: self.metadata.snapshot(),
+			parent: self
+				.parent
+				.as_ref()
+				.map(|parent| Box::new(parent.snapshot())),
+			logs: self.logs.clone(),
+			accounts: self.accounts.clone(),
+			storages: self
+				.storages
+				.iter()
+				.map(|(&(address, key), &value)| (address, key, value))
+				.collect(),
+			deletes: self.deletes.clone(),
+			transient_storage: self
+				.transient_storage
+				.iter()
+				.map(|(&(address, key), &value)| (address, key, value))
+				.collect(),
+			creates: self.creates.clone(),
+		}
+	}
+
+	pub fn from_snapshot(snapshot: MemoryStackSubstateSnapshot, config: &'config Config) -> Self {
+		Self {
+			metadata: StackSubstateMetadata::from_snapshot(snapshot.metadata, config),
+			parent: snapshot
+				.parent
+				.map(|parent| Box::new(Self::from_snapshot(*parent, config))),
+			logs: snapshot.logs,
+			accounts: snapshot.accounts,
+			storages: snapshot
+				.storages
+				.into_iter()
+				.map(|(address, key, value)| ((address, key), value))
+				.collect(),
+			deletes: snapshot.deletes,
+			transient_storage: snapshot
+				.transient_storage
+				.into_iter()
+				.map(|(address, key, value)| ((address, key), value))
+				.collect(),
+			creates: snapshot.creates,
 		}
 	}
 
@@ -120,6 +200,8 @@ impl<'config> MemoryStackSubstate<'config> {
 			accounts: BTreeMap::new(),
 			storages: BTreeMap::new(),
 			deletes: BTreeSet::new(),
+			transient_storage: BTreeMap::new(),
+			creates: BTreeSet::new(),
 		};
 		mem::swap(&mut entering, self);
 
@@ -153,9 +235,27 @@ impl<'config> MemoryStackSubstate<'config> {
 		self.storages.append(&mut exited.storages);
 		self.deletes.append(&mut exited.deletes);
 
+		// The root substate outlives transactions, so transaction-scoped
+		// changes end when the top-level call or create commits into it.
+		if self.parent.is_some() {
+			self.transient_storage.append(&mut exited.transient_storage);
+			self.creates.append(&mut exited.creates);
+		}
+
 		Ok(())
 	}
 
+	/// Drop transient storage and created accounts, in this substate and its
+	/// parents. Transactions run in a substate that outlives them, as with
+	/// `StackExecutor::estimate_gas`, commit them into it.
+	pub fn end_transaction(&mut self) {
+		self.transient_storage.clear();
+		self.creates.clear();
+		if let Some(parent) = self.parent.as_mut() {
+			parent.end_transaction();
+		}
+	}
+
 	pub fn exit_revert(&mut self) -> Result<(), ExitError> {
 		let mut exited = *self.parent.take().expect("Cannot discard on root substate");
 		mem::swap(&mut exited, self);
@@ -278,6 +378,30 @@ impl<'config> MemoryStackSubstate<'config> {
 		false
 	}
 
+	pub fn known_transient_storage(&self, address: H160, key: H256) -> Option<H256> {
+		if let Some(value) = self.transient_storage.get(&(address, key)) {
+			return Some(*value);
+		}
+
+		if let Some(parent) = self.parent.as_ref() {
+			return parent.known_transient_storage(address, key);
+		}
+
+		None
+	}
+
+	pub fn created(&self, address: H160) -> bool {
+		if self.creates.contains(&address) {
+			return true;
+		}
+
+		if let Some(parent) = self.parent.as_ref() {
+			return parent.created(address);
+		}
+
+		false
+	}
+
 	#[allow(clippy::map_entry)]
 	fn account_mut<B: Backend>(&mut self, address: H160, backend: &B) -> &mut MemoryStackAccount {
 		if !self.accounts.contains_key(&address) {
@@ -325,6 +449,14 @@ impl<'config> MemoryStackSubstate<'config> {
 		self.account_mut(address, backend).reset = true;
 	}
 
+	pub fn set_transient_storage(&mut self, address: H160, key: H256, value: H256) {
+		self.transient_storage.insert((address, key), value);
+	}
+
+	pub fn set_created(&mut self, address: H160) {
+		self.creates.insert(address);
+	}
+
 	pub fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) {
 		self.logs.push(Log {
 			address,
@@ -427,6 +559,15 @@ impl<'backend, 'config, B: Backend> Backend for MemoryStackState<'backend, 'conf
 	fn block_base_fee_per_gas(&self) -> U256 {
 		self.backend.block_base_fee_per_gas()
 	}
+	fn block_randomness(&self) -> Option<H256> {
+		self.backend.block_randomness()
+	}
+	fn block_blob_base_fee(&self) -> U256 {
+		self.backend.block_blob_base_fee()
+	}
+	fn blob_hash(&self, index: usize) -> Option<H256> {
+		self.backend.blob_hash(index)
+	}
 
 	fn chain_id(&self) -> U256 {
 		self.backend.chain_id()
@@ -448,6 +589,13 @@ impl<'backend, 'config, B: Backend> Backend for MemoryStackState<'backend, 'conf
 			.unwrap_or_else(|| self.backend.code(address))
 	}
 
+	fn code_hash(&self, address: H160) -> H256 {
+		match self.substate.known_code(address) {
+			Some(code) => H256::from_slice(Keccak256::digest(&code).as_slice()),
+			None => self.backend.code_hash(address),
+		}
+	}
+
 	fn storage(&self, address: H160, key: H256) -> H256 {
 		self.substate
 			.known_storage(address, key)
@@ -522,6 +670,28 @@ impl<'backend, 'config, B: Backend> StackState<'config> for MemoryStackState<'ba
 		self.substate.reset_storage(address, self.backend);
 	}
 
+	fn transient_storage(&self, address: H160, key: H256) -> H256 {
+		self.substate
+			.known_transient_storage(address, key)
+			.unwrap_or_default()
+	}
+
+	fn set_transient_storage(&mut self, address: H160, key: H256, value: H256) {
+		self.substate.set_transient_storage(address, key, value)
+	}
+
+	fn created(&self, address: H160) -> bool {
+		self.substate.created(address)
+	}
+
+	fn set_created(&mut self, address: H160) {
+		self.substate.set_created(address)
+	}
+
+	fn end_transaction(&mut self) {
+		self.substate.end_transaction()
+	}
+
 	fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) {
 		self.substate.log(address, topics, data);
 	}
@@ -565,6 +735,21 @@ impl<'backend, 'config, B: Backend> MemoryStackState<'backend, 'config, B> {
 		self.substate.deconstruct(self.backend)
 	}
 
+	pub fn snapshot(&self) -> MemoryStackSubstateSnapshot {
+		self.substate.snapshot()
+	}
+
+	pub fn from_snapshot(
+		snapshot: MemoryStackSubstateSnapshot,
+		backend: &'backend B,
+		config: &'config Config,
+	) -> Self {
+		Self {
+			backend,
+			substate: MemoryStackSubstate::from_snapshot(snapshot, config),
+		}
+	}
+
 	pub fn withdraw(&mut self, address: H160, value: U256) -> Result<(), ExitError> {
 		self.substate.withdraw(address, value, self.backend)
 	}
@@ -2,14 +2,34 @@
 //! A memory-based state is provided, but can replaced by a custom
 //! implementation, for exemple one interacting with a database.
 
+mod access_list;
+mod asynchronous;
+mod code_cache;
 mod executor;
 mod memory;
+mod prestate;
+mod transaction;
+
+pub use self::access_list::AccessListResult;
+
+pub use self::asynchronous::{execute_async, AsyncExecutionError};
+
+pub use self::code_cache::{AnalysisCache, AnalyzedCode, CodeCache};
 
 pub use self::executor::{
-	Accessed, PrecompileFailure, PrecompileFn, PrecompileOutput, PrecompileSet, StackExecutor,
-	StackExitKind, StackState, StackSubstateMetadata,
+	Accessed, ExecutionLimit, GasEstimate, PrecompileFailure, PrecompileFn, PrecompileOutput,
+	PrecompileResult, PrecompileSet, RuntimeKind, SnapshotError, StackExecution,
+	StackExecutionSnapshot, StackExecutor, StackExecutorCallInterrupt,
+	StackExecutorCreateInterrupt, StackExitKind, StackFrameSnapshot, StackState,
+	StackSubstateMetadata, StackSubstateMetadataSnapshot,
 };
 
-pub use self::memory::{MemoryStackAccount, MemoryStackState, MemoryStackSubstate};
+pub use self::memory::{
+	MemoryStackAccount, MemoryStackState, MemoryStackSubstate, MemoryStackSubstateSnapshot,
+};
+
+pub use self::prestate::{Prestate, PrestateAccount, PrestateTracer, StateDiff};
+
+pub use self::transaction::{InvalidTransaction, TransactOutcome, Transaction};
 
 pub use ethereum::Log;
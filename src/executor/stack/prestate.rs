@@ -0,0 +1,399 @@
+use crate::backend::{Apply, Backend, Basic, Log};
+use crate::executor::stack::MemoryStackState;
+use alloc::{
+	collections::{BTreeMap, BTreeSet},
+	vec::Vec,
+};
+use core::cell::RefCell;
+use core::fmt::{self, Write};
+use primitive_types::{H160, H256, U256};
+
+/// Account in a prestate or in the post-state of a diff. Formatting it with
+/// `Display` gives the JSON object of geth's `prestateTracer`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PrestateAccount {
+	/// Balance. In a post-state, `None` if unchanged.
+	pub balance: Option<U256>,
+	/// Nonce. In a post-state, `None` if unchanged.
+	pub nonce: Option<U256>,
+	/// Code. In a post-state, `None` if unchanged.
+	pub code: Option<Vec<u8>>,
+	/// Storage slots.
+	pub storage: BTreeMap<H256, H256>,
+}
+
+impl fmt::Display for PrestateAccount {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let mut separator = "";
+		f.write_char('{')?;
+		if let Some(balance) = self.balance {
+			write!(f, "\"balance\":\"{:#x}\"", balance)?;
+			separator = ",";
+		}
+		if let Some(nonce) = self.nonce.filter(|nonce| !nonce.is_zero()) {
+			write!(f, "{}\"nonce\":{}", separator, nonce)?;
+			separator = ",";
+		}
+		if let Some(code) = self.code.as_ref().filter(|code| !code.is_empty()) {
+			write!(f, "{}\"code\":\"0x", separator)?;
+			for byte in code {
+				write!(f, "{:02x}", byte)?;
+			}
+			f.write_char('"')?;
+			separator = ",";
+		}
+		if !self.storage.is_empty() {
+			write!(f, "{}\"storage\":{{", separator)?;
+			for (i, (key, value)) in self.storage.iter().enumerate() {
+				if i > 0 {
+					f.write_char(',')?;
+				}
+				write!(f, "\"{:#x}\":\"{:#x}\"", key, value)?;
+			}
+			f.write_char('}')?;
+		}
+		f.write_char('}')
+	}
+}
+
+/// Accounts of a prestate or post-state, by address.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Prestate {
+	pub accounts: BTreeMap<H160, PrestateAccount>,
+}
+
+impl fmt::Display for Prestate {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_char('{')?;
+		for (i, (address, account)) in self.accounts.iter().enumerate() {
+			if i > 0 {
+				f.write_char(',')?;
+			}
+			write!(f, "\"{:#x}\":{}", address, account)?;
+		}
+		f.write_char('}')
+	}
+}
+
+/// State changed by a transaction, in the shape of geth's `prestateTracer`
+/// in `diffMode`.
+///
+/// `pre` holds the modified accounts that existed before the transaction,
+/// with only their modified storage slots. `post` holds the accounts still
+/// existing after the transaction, with only their modified fields.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StateDiff {
+	pub pre: Prestate,
+	pub post: Prestate,
+}
+
+impl fmt::Display for StateDiff {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{{\"pre\":{},\"post\":{}}}", self.pre, self.post)
+	}
+}
+
+/// Backend recording all accounts and storage slots read from it.
+///
+/// Use it as the backend of a `MemoryStackState` to get the prestate of a
+/// transaction with `prestate`, and its state diff with `deconstruct`.
+pub struct PrestateTracer<'backend, B> {
+	backend: &'backend B,
+	accounts: RefCell<BTreeSet<H160>>,
+	storages: RefCell<BTreeSet<(H160, H256)>>,
+}
+
+impl<'backend, B: Backend> PrestateTracer<'backend, B> {
+	pub fn new(backend: &'backend B) -> Self {
+		Self {
+			backend,
+			accounts: RefCell::new(BTreeSet::new()),
+			storages: RefCell::new(BTreeSet::new()),
+		}
+	}
+
+	/// All accounts and storage slots read so far, with their original
+	/// values.
+	pub fn prestate(&self) -> Prestate {
+		let mut prestate = Prestate::default();
+		for address in self.accounts.borrow().iter() {
+			prestate
+				.accounts
+				.insert(*address, self.original_account(*address));
+		}
+		for (address, key) in self.storages.borrow().iter() {
+			prestate
+				.accounts
+				.entry(*address)
+				.or_insert_with(|| self.original_account(*address))
+				.storage
+				.insert(*key, self.backend.storage(*address, *key));
+		}
+
+		prestate
+	}
+
+	/// Deconstruct the state of a finished transaction, returning its state
+	/// diff together with the values and logs to apply.
+	#[allow(clippy::type_complexity)]
+	pub fn deconstruct(
+		&self,
+		state: MemoryStackState<'_, '_, Self>,
+	) -> (StateDiff, Vec<Apply<Vec<(H256, H256)>>>, Vec<Log>) {
+		let (values, logs) = state.deconstruct();
+		let values = values
+			.into_iter()
+			.map(|apply| match apply {
+				Apply::Modify {
+					address,
+					basic,
+					code,
+					storage,
+					reset_storage,
+				} => Apply::Modify {
+					address,
+					basic,
+					code,
+					storage: storage.into_iter().collect::<Vec<_>>(),
+					reset_storage,
+				},
+				Apply::Delete { address } => Apply::Delete { address },
+			})
+			.collect::<Vec<_>>();
+		let logs = logs.into_iter().collect();
+
+		let prestate = self.prestate();
+		let mut diff = StateDiff::default();
+		for apply in &values {
+			match apply {
+				Apply::Modify {
+					address,
+					basic,
+					code,
+					storage,
+					reset_storage,
+				} => {
+					let pre = prestate
+						.accounts
+						.get(address)
+						.cloned()
+						.unwrap_or_else(|| self.original_account(*address));
+
+					// Slots that were read, or written to, with their values
+					// after the transaction.
+					let mut slots = BTreeMap::new();
+					if !reset_storage {
+						slots.extend(pre.storage.iter().map(|(key, value)| (*key, *value)));
+					} else {
+						slots.extend(pre.storage.keys().map(|key| (*key, H256::default())));
+					}
+					slots.extend(storage.iter().cloned());
+
+					let mut pre_account = PrestateAccount {
+						storage: BTreeMap::new(),
+						..pre.clone()
+					};
+					let mut post_account = PrestateAccount::default();
+					for (key, value) in slots {
+						let original = match pre.storage.get(&key) {
+							Some(original) => *original,
+							None => self.backend.storage(*address, key),
+						};
+						if original != value {
+							pre_account.storage.insert(key, original);
+							if value != H256::default() {
+								post_account.storage.insert(key, value);
+							}
+						}
+					}
+					if pre.balance != Some(basic.balance) {
+						post_account.balance = Some(basic.balance);
+					}
+					if pre.nonce != Some(basic.nonce) {
+						post_account.nonce = Some(basic.nonce);
+					}
+					if let Some(code) = code {
+						if pre.code.as_ref() != Some(code) {
+							post_account.code = Some(code.clone());
+						}
+					}
+
+					let modified = post_account != PrestateAccount::default()
+						|| !pre_account.storage.is_empty();
+					if modified {
+						if self.backend.exists(*address) {
+							diff.pre.accounts.insert(*address, pre_account);
+						}
+						diff.post.accounts.insert(*address, post_account);
+					}
+				}
+				Apply::Delete { address } => {
+					if self.backend.exists(*address) {
+						let pre = prestate
+							.accounts
+							.get(address)
+							.cloned()
+							.unwrap_or_else(|| self.original_account(*address));
+						diff.pre.accounts.insert(*address, pre);
+					}
+				}
+			}
+		}
+
+		(diff, values, logs)
+	}
+
+	fn original_account(&self, address: H160) -> PrestateAccount {
+		let basic = self.backend.basic(address);
+		PrestateAccount {
+			balance: Some(basic.balance),
+			nonce: Some(basic.nonce),
+			code: Some(self.backend.code(address)),
+			storage: BTreeMap::new(),
+		}
+	}
+}
+
+impl<'backend, B: Backend> Backend for PrestateTracer<'backend, B> {
+	fn gas_price(&self) -> U256 {
+		self.backend.gas_price()
+	}
+	fn origin(&self) -> H160 {
+		self.backend.origin()
+	}
+	fn block_hash(&self, number: U256) -> H256 {
+		self.backend.block_hash(number)
+	}
+	fn block_number(&self) -> U256 {
+		self.backend.block_number()
+	}
+	fn block_coinbase(&self) -> H160 {
+		self.backend.block_coinbase()
+	}
+	fn block_timestamp(&self) -> U256 {
+		self.backend.block_timestamp()
+	}
+	fn block_difficulty(&self) -> U256 {
+		self.backend.block_difficulty()
+	}
+	fn block_gas_limit(&self) -> U256 {
+		self.backend.block_gas_limit()
+	}
+	fn block_base_fee_per_gas(&self) -> U256 {
+		self.backend.block_base_fee_per_gas()
+	}
+	fn block_randomness(&self) -> Option<H256> {
+		self.backend.block_randomness()
+	}
+	fn block_blob_base_fee(&self) -> U256 {
+		self.backend.block_blob_base_fee()
+	}
+	fn blob_hash(&self, index: usize) -> Option<H256> {
+		self.backend.blob_hash(index)
+	}
+	fn chain_id(&self) -> U256 {
+		self.backend.chain_id()
+	}
+
+	fn exists(&self, address: H160) -> bool {
+		self.accounts.borrow_mut().insert(address);
+		self.backend.exists(address)
+	}
+
+	fn basic(&self, address: H160) -> Basic {
+		self.accounts.borrow_mut().insert(address);
+		self.backend.basic(address)
+	}
+
+	fn code(&self, address: H160) -> Vec<u8> {
+		self.accounts.borrow_mut().insert(address);
+		self.backend.code(address)
+	}
+
+	fn storage(&self, address: H160, index: H256) -> H256 {
+		self.storages.borrow_mut().insert((address, index));
+		self.backend.storage(address, index)
+	}
+
+	fn original_storage(&self, address: H160, index: H256) -> Option<H256> {
+		self.storages.borrow_mut().insert((address, index));
+		self.backend.original_storage(address, index)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::backend::{MemoryAccount, MemoryBackend, MemoryVicinity};
+	use crate::executor::stack::{StackExecutor, StackSubstateMetadata};
+	use crate::Config;
+	use alloc::string::ToString;
+
+	#[test]
+	fn prestate_and_diff() {
+		let config = Config::istanbul();
+		let vicinity = MemoryVicinity::for_tests(100_000);
+		let mut state = BTreeMap::new();
+		// SSTORE(0, SLOAD(1) + 1)
+		state.insert(
+			H160::repeat_byte(0x11),
+			MemoryAccount {
+				nonce: U256::one(),
+				balance: U256::zero(),
+				storage: core::iter::once((H256::from_low_u64_be(1), H256::from_low_u64_be(5)))
+					.collect(),
+				code: hex::decode("60015460010160005500").unwrap(),
+			},
+		);
+		state.insert(
+			H160::repeat_byte(0x22),
+			MemoryAccount {
+				nonce: U256::zero(),
+				balance: U256::from(10),
+				storage: BTreeMap::new(),
+				code: Vec::new(),
+			},
+		);
+		let backend = MemoryBackend::new(&vicinity, state);
+		let tracer = PrestateTracer::new(&backend);
+		let metadata = StackSubstateMetadata::new(100_000, &config);
+		let state = MemoryStackState::new(metadata, &tracer);
+		let precompiles = BTreeMap::new();
+		let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+
+		executor.transact_call(
+			H160::repeat_byte(0x22),
+			H160::repeat_byte(0x11),
+			U256::zero(),
+			Vec::new(),
+			100_000,
+			Vec::new(),
+		);
+
+		assert_eq!(
+			tracer.prestate().to_string(),
+			"{\"0x1111111111111111111111111111111111111111\":{\"balance\":\"0x0\",\"nonce\":1,\
+			 \"code\":\"0x60015460010160005500\",\"storage\":{\
+			 \"0x0000000000000000000000000000000000000000000000000000000000000000\":\
+			 \"0x0000000000000000000000000000000000000000000000000000000000000000\",\
+			 \"0x0000000000000000000000000000000000000000000000000000000000000001\":\
+			 \"0x0000000000000000000000000000000000000000000000000000000000000005\"}},\
+			 \"0x2222222222222222222222222222222222222222\":{\"balance\":\"0xa\"}}"
+		);
+
+		let (diff, values, _) = tracer.deconstruct(executor.into_state());
+		assert_eq!(values.len(), 2);
+		assert_eq!(
+			diff.to_string(),
+			"{\"pre\":{\"0x1111111111111111111111111111111111111111\":{\"balance\":\"0x0\",\
+			 \"nonce\":1,\"code\":\"0x60015460010160005500\",\"storage\":{\
+			 \"0x0000000000000000000000000000000000000000000000000000000000000000\":\
+			 \"0x0000000000000000000000000000000000000000000000000000000000000000\"}},\
+			 \"0x2222222222222222222222222222222222222222\":{\"balance\":\"0xa\"}},\
+			 \"post\":{\"0x1111111111111111111111111111111111111111\":{\"storage\":{\
+			 \"0x0000000000000000000000000000000000000000000000000000000000000000\":\
+			 \"0x0000000000000000000000000000000000000000000000000000000000000006\"}},\
+			 \"0x2222222222222222222222222222222222222222\":{\"nonce\":1}}}"
+		);
+	}
+}
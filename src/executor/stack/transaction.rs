@@ -0,0 +1,297 @@
+use crate::backend::Backend;
+use crate::executor::stack::{
+	AnalysisCache, MemoryStackState, PrecompileSet, StackExecutor, StackState,
+};
+use crate::gasometer::{self, Gasometer};
+use crate::{CreateScheme, ExitReason};
+use alloc::vec::Vec;
+use core::cmp::min;
+use ethereum::{TransactionAction, TransactionV2};
+use primitive_types::{H160, H256, U256};
+
+/// Reason a transaction is invalid, and cannot be included in a block.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InvalidTransaction {
+	/// Transaction is signed for another chain.
+	InvalidChainId,
+	/// Nonce is lower than the sender account nonce.
+	NonceTooLow,
+	/// Nonce is higher than the sender account nonce.
+	NonceTooHigh,
+	/// Sender account has code. See EIP-3607.
+	SenderNotEoa,
+	/// Sender balance does not cover the gas limit at the max fee per gas
+	/// plus the value.
+	InsufficientFunds,
+	/// Gas limit is lower than the intrinsic gas of the transaction.
+	IntrinsicGasTooLow,
+	/// Gas limit is higher than the gas left in the block.
+	GasLimitReached,
+	/// Max fee per gas is lower than the block base fee.
+	FeeCapTooLow,
+	/// Max priority fee per gas is higher than the max fee per gas.
+	TipAboveFeeCap,
+	/// Init code of a create transaction is over the size limit. See
+	/// EIP-3860.
+	InitCodeTooLarge,
+	/// Gas limit differs from the gas limit of the executor.
+	GasLimitMismatch,
+}
+
+/// Transaction validated, executed and settled by `StackExecutor::transact`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Transaction {
+	/// Sender.
+	pub caller: H160,
+	/// Chain ID, for transactions that are replay protected.
+	pub chain_id: Option<u64>,
+	/// Nonce.
+	pub nonce: U256,
+	/// Gas limit.
+	pub gas_limit: U256,
+	/// Max fee per gas. This is the gas price of legacy and EIP-2930
+	/// transactions.
+	pub max_fee_per_gas: U256,
+	/// Max priority fee per gas. This is the gas price of legacy and
+	/// EIP-2930 transactions.
+	pub max_priority_fee_per_gas: U256,
+	/// Called address, or `None` for a create transaction.
+	pub address: Option<H160>,
+	/// Value.
+	pub value: U256,
+	/// Call data, or init code of a create transaction.
+	pub data: Vec<u8>,
+	/// Access list. See EIP-2930.
+	pub access_list: Vec<(H160, Vec<H256>)>,
+}
+
+impl Transaction {
+	/// Fields of an `ethereum` transaction sent by `caller`. The sender is
+	/// not recovered from the signature.
+	pub fn new(caller: H160, transaction: TransactionV2) -> Self {
+		let address = |action| match action {
+			TransactionAction::Call(address) => Some(address),
+			TransactionAction::Create => None,
+		};
+
+		match transaction {
+			TransactionV2::Legacy(t) => Self {
+				caller,
+				chain_id: t.signature.chain_id(),
+				nonce: t.nonce,
+				gas_limit: t.gas_limit,
+				max_fee_per_gas: t.gas_price,
+				max_priority_fee_per_gas: t.gas_price,
+				address: address(t.action),
+				value: t.value,
+				data: t.input,
+				access_list: Vec::new(),
+			},
+			TransactionV2::EIP2930(t) => Self {
+				caller,
+				chain_id: Some(t.chain_id),
+				nonce: t.nonce,
+				gas_limit: t.gas_limit,
+				max_fee_per_gas: t.gas_price,
+				max_priority_fee_per_gas: t.gas_price,
+				address: address(t.action),
+				value: t.value,
+				data: t.input,
+				access_list: t
+					.access_list
+					.into_iter()
+					.map(|item| (item.address, item.slots))
+					.collect(),
+			},
+			TransactionV2::EIP1559(t) => Self {
+				caller,
+				chain_id: Some(t.chain_id),
+				nonce: t.nonce,
+				gas_limit: t.gas_limit,
+				max_fee_per_gas: t.max_fee_per_gas,
+				max_priority_fee_per_gas: t.max_priority_fee_per_gas,
+				address: address(t.action),
+				value: t.value,
+				data: t.input,
+				access_list: t
+					.access_list
+					.into_iter()
+					.map(|item| (item.address, item.slots))
+					.collect(),
+			},
+		}
+	}
+
+	/// Gas price paid by the sender given the block base fee, which is zero
+	/// before London.
+	pub fn effective_gas_price(&self, base_fee_per_gas: U256) -> U256 {
+		min(
+			self.max_fee_per_gas,
+			base_fee_per_gas.saturating_add(self.max_priority_fee_per_gas),
+		)
+	}
+}
+
+/// Result of a transaction run by `StackExecutor::transact`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransactOutcome {
+	/// Exit reason of the transaction.
+	pub exit_reason: ExitReason,
+	/// Return value of a call transaction, or revert data.
+	pub return_value: Vec<u8>,
+	/// Gas used, after refunds.
+	pub used_gas: u64,
+	/// Gas price paid by the sender.
+	pub effective_gas_price: U256,
+	/// Address of the created contract, for successful create transactions.
+	pub contract_address: Option<H160>,
+}
+
+impl<'backend, 'config, 'precompiles, B: Backend, P: PrecompileSet, C: AnalysisCache>
+	StackExecutor<'config, 'precompiles, MemoryStackState<'backend, 'config, B>, P, C>
+{
+	/// Validate a transaction against the sender account and the block,
+	/// execute it, then settle its fees. The executor is expected to be
+	/// fresh, with the transaction gas limit as its gas limit, otherwise the
+	/// transaction is rejected with `GasLimitMismatch`.
+	///
+	/// Before execution the sender is charged the gas limit at the
+	/// effective gas price. After it the unused gas is refunded, and the
+	/// coinbase is paid the gas used at the effective gas price less the
+	/// base fee, which is burnt. An invalid transaction leaves the state
+	/// unchanged.
+	pub fn transact(
+		&mut self,
+		transaction: Transaction,
+	) -> Result<TransactOutcome, InvalidTransaction> {
+		let caller = transaction.caller;
+
+		if let Some(chain_id) = transaction.chain_id {
+			if U256::from(chain_id) != self.state().chain_id() {
+				return Err(InvalidTransaction::InvalidChainId);
+			}
+		}
+
+		if transaction.gas_limit > self.state().block_gas_limit()
+			|| transaction.gas_limit > U256::from(u64::MAX)
+		{
+			return Err(InvalidTransaction::GasLimitReached);
+		}
+		let gas_limit = transaction.gas_limit.as_u64();
+		if self.state().metadata().gasometer().gas_limit() != gas_limit {
+			return Err(InvalidTransaction::GasLimitMismatch);
+		}
+
+		let base_fee_per_gas = if self.config().has_base_fee {
+			if transaction.max_priority_fee_per_gas > transaction.max_fee_per_gas {
+				return Err(InvalidTransaction::TipAboveFeeCap);
+			}
+			let base_fee_per_gas = self.state().block_base_fee_per_gas();
+			if transaction.max_fee_per_gas < base_fee_per_gas {
+				return Err(InvalidTransaction::FeeCapTooLow);
+			}
+			base_fee_per_gas
+		} else {
+			U256::zero()
+		};
+		let gas_price = transaction.effective_gas_price(base_fee_per_gas);
+
+		let transaction_cost = match transaction.address {
+			Some(_) => {
+				gasometer::call_transaction_cost(&transaction.data, &transaction.access_list)
+			}
+			None => {
+				if let Some(limit) = self.config().max_initcode_size {
+					if transaction.data.len() > limit {
+						return Err(InvalidTransaction::InitCodeTooLarge);
+					}
+				}
+				gasometer::create_transaction_cost(&transaction.data, &transaction.access_list)
+			}
+		};
+		if Gasometer::new(gas_limit, self.config())
+			.record_transaction(transaction_cost)
+			.is_err()
+		{
+			return Err(InvalidTransaction::IntrinsicGasTooLow);
+		}
+
+		let nonce = self.nonce(caller);
+		if transaction.nonce < nonce {
+			return Err(InvalidTransaction::NonceTooLow);
+		}
+		if transaction.nonce > nonce {
+			return Err(InvalidTransaction::NonceTooHigh);
+		}
+
+		if !self.state().code(caller).is_empty() {
+			return Err(InvalidTransaction::SenderNotEoa);
+		}
+
+		let max_cost = transaction
+			.gas_limit
+			.checked_mul(transaction.max_fee_per_gas)
+			.and_then(|fee| fee.checked_add(transaction.value));
+		match max_cost {
+			Some(max_cost) if max_cost <= self.state().basic(caller).balance => (),
+			_ => return Err(InvalidTransaction::InsufficientFunds),
+		}
+		if self
+			.state_mut()
+			.withdraw(caller, transaction.gas_limit * gas_price)
+			.is_err()
+		{
+			return Err(InvalidTransaction::InsufficientFunds);
+		}
+
+		let (exit_reason, return_value, contract_address) = match transaction.address {
+			Some(address) => {
+				let (exit_reason, return_value) = self.transact_call(
+					caller,
+					address,
+					transaction.value,
+					transaction.data,
+					gas_limit,
+					transaction.access_list,
+				);
+				(exit_reason, return_value, None)
+			}
+			None => {
+				let address = self.create_address(CreateScheme::Legacy { caller });
+				let capture = self.begin_create(
+					caller,
+					transaction.value,
+					transaction.data,
+					gas_limit,
+					transaction.access_list,
+				);
+				let (exit_reason, return_value) = self.finish_transact(capture);
+				let contract_address = if exit_reason.is_succeed() {
+					Some(address)
+				} else {
+					None
+				};
+				(exit_reason, return_value, contract_address)
+			}
+		};
+
+		let used_gas = self.used_gas();
+		self.state_mut().deposit(
+			caller,
+			(transaction.gas_limit - U256::from(used_gas)) * gas_price,
+		);
+		let coinbase = self.state().block_coinbase();
+		self.state_mut().deposit(
+			coinbase,
+			U256::from(used_gas) * (gas_price - base_fee_per_gas),
+		);
+
+		Ok(TransactOutcome {
+			exit_reason,
+			return_value,
+			used_gas,
+			effective_gas_price: gas_price,
+			contract_address,
+		})
+	}
+}
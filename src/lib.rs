@@ -28,3 +28,5 @@ macro_rules! event {
 
 pub mod backend;
 pub mod executor;
+#[cfg(feature = "precompiles")]
+pub mod precompiles;
@@ -0,0 +1,111 @@
+use super::{ensure_gas, fail, read_input, succeed};
+use crate::executor::stack::{PrecompileFailure, PrecompileResult};
+use crate::ExitError;
+use alloc::{vec, vec::Vec};
+use bn::{AffineG1, AffineG2, Fq, Fq2, Fr, Group, Gt, G1, G2};
+use primitive_types::U256;
+
+/// `ecadd` at `0x06` (EIP-196), repriced by EIP-1108 when `istanbul` is set.
+pub fn add(input: &[u8], gas_limit: Option<u64>, istanbul: bool) -> PrecompileResult {
+	let cost = if istanbul { 150 } else { 500 };
+	ensure_gas(cost, gas_limit)?;
+
+	let input = read_input(input, 0, 128);
+	let p1 = read_g1(&input[0..64])?;
+	let p2 = read_g1(&input[64..128])?;
+
+	succeed(cost, encode_g1(p1 + p2))
+}
+
+/// `ecmul` at `0x07` (EIP-196), repriced by EIP-1108 when `istanbul` is set.
+pub fn mul(input: &[u8], gas_limit: Option<u64>, istanbul: bool) -> PrecompileResult {
+	let cost = if istanbul { 6000 } else { 40000 };
+	ensure_gas(cost, gas_limit)?;
+
+	let input = read_input(input, 0, 96);
+	let p = read_g1(&input[0..64])?;
+	let scalar = Fr::from_slice(&input[64..96]).map_err(|_| fail("invalid scalar"))?;
+
+	succeed(cost, encode_g1(p * scalar))
+}
+
+/// `ecpairing` at `0x08` (EIP-197), repriced by EIP-1108 when `istanbul` is
+/// set.
+pub fn pairing(input: &[u8], gas_limit: Option<u64>, istanbul: bool) -> PrecompileResult {
+	const PAIR_LEN: usize = 192;
+
+	if input.len() % PAIR_LEN != 0 {
+		return Err(fail("invalid pairing input length"));
+	}
+
+	let pairs = input.len() / PAIR_LEN;
+	let (base, per_pair) = if istanbul {
+		(45000, 34000)
+	} else {
+		(100000, 80000)
+	};
+	let cost = (pairs as u64)
+		.checked_mul(per_pair)
+		.and_then(|cost| cost.checked_add(base))
+		.ok_or(PrecompileFailure::Error {
+			exit_status: ExitError::OutOfGas,
+		})?;
+	ensure_gas(cost, gas_limit)?;
+
+	let mut points = Vec::with_capacity(pairs);
+	for pair in input.chunks(PAIR_LEN) {
+		points.push((read_g1(&pair[0..64])?, read_g2(&pair[64..192])?));
+	}
+
+	let success = points.is_empty() || bn::pairing_batch(&points) == Gt::one();
+	let mut output = [0u8; 32];
+	U256::from(success as u8).to_big_endian(&mut output);
+	succeed(cost, output.to_vec())
+}
+
+fn read_fq(input: &[u8]) -> Result<Fq, PrecompileFailure> {
+	Fq::from_slice(input).map_err(|_| fail("invalid field element"))
+}
+
+fn read_g1(input: &[u8]) -> Result<G1, PrecompileFailure> {
+	let x = read_fq(&input[0..32])?;
+	let y = read_fq(&input[32..64])?;
+
+	if x.is_zero() && y.is_zero() {
+		Ok(G1::zero())
+	} else {
+		AffineG1::new(x, y)
+			.map(Into::into)
+			.map_err(|_| fail("invalid G1 point"))
+	}
+}
+
+fn read_g2(input: &[u8]) -> Result<G2, PrecompileFailure> {
+	// Coefficients of `Fq2` are encoded imaginary part first.
+	let x = Fq2::new(read_fq(&input[32..64])?, read_fq(&input[0..32])?);
+	let y = Fq2::new(read_fq(&input[96..128])?, read_fq(&input[64..96])?);
+
+	if x.is_zero() && y.is_zero() {
+		Ok(G2::zero())
+	} else {
+		AffineG2::new(x, y)
+			.map(Into::into)
+			.map_err(|_| fail("invalid G2 point"))
+	}
+}
+
+fn encode_g1(point: G1) -> Vec<u8> {
+	let mut output = vec![0; 64];
+	// The point at infinity is encoded as zeros.
+	if let Some(point) = AffineG1::from_jacobian(point) {
+		point
+			.x()
+			.to_big_endian(&mut output[0..32])
+			.expect("slice is 32 bytes");
+		point
+			.y()
+			.to_big_endian(&mut output[32..64])
+			.expect("slice is 32 bytes");
+	}
+	output
+}
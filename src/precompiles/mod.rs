@@ -0,0 +1,352 @@
+//! # Standard precompiles
+//!
+//! The precompiled contracts living at addresses `0x01` to `0x0a` on the
+//! Ethereum mainnet, packaged as a [`PrecompileSet`] whose availability and
+//! gas costs follow the hard forks.
+//!
+//! The KZG point evaluation precompile of Cancun (`0x0a`, EIP-4844) is not
+//! implemented. Its address is part of the Cancun set, so that it is warm
+//! and cannot be mistaken for an empty account, but calling it exits with
+//! `ExitFatal::NotSupported`.
+
+mod blake2f;
+mod bn128;
+mod modexp;
+mod simple;
+
+use crate::executor::stack::{
+	PrecompileFailure, PrecompileOutput, PrecompileResult, PrecompileSet,
+};
+use crate::{Config, Context, ExitError, ExitFatal, ExitSucceed};
+use alloc::{vec, vec::Vec};
+use primitive_types::H160;
+
+/// Hard fork a precompile set is priced for.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+enum Fork {
+	Frontier,
+	Byzantium,
+	Istanbul,
+	Berlin,
+	Cancun,
+}
+
+/// Standard Ethereum precompiles, with availability and gas costs of a given
+/// hard fork.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StandardPrecompileSet {
+	fork: Fork,
+}
+
+impl StandardPrecompileSet {
+	/// Precompiles matching the hard fork a `Config` describes.
+	pub fn new(config: &Config) -> Self {
+		let fork = if config.has_blob_hash {
+			Fork::Cancun
+		} else if config.increase_state_access_gas {
+			Fork::Berlin
+		} else if config.has_chain_id {
+			Fork::Istanbul
+		} else if config.has_return_data {
+			Fork::Byzantium
+		} else {
+			Fork::Frontier
+		};
+
+		Self { fork }
+	}
+
+	/// Frontier precompiles: `ecrecover`, `sha256`, `ripemd160` and `identity`.
+	pub const fn frontier() -> Self {
+		Self {
+			fork: Fork::Frontier,
+		}
+	}
+
+	/// Byzantium precompiles, adding `modexp` (EIP-198) and the alt_bn128
+	/// operations (EIP-196 and EIP-197).
+	pub const fn byzantium() -> Self {
+		Self {
+			fork: Fork::Byzantium,
+		}
+	}
+
+	/// Istanbul precompiles, repricing alt_bn128 (EIP-1108) and adding
+	/// `blake2f` (EIP-152).
+	pub const fn istanbul() -> Self {
+		Self {
+			fork: Fork::Istanbul,
+		}
+	}
+
+	/// Berlin precompiles, repricing `modexp` (EIP-2565).
+	pub const fn berlin() -> Self {
+		Self { fork: Fork::Berlin }
+	}
+
+	/// London precompiles. Same as Berlin.
+	pub const fn london() -> Self {
+		Self::berlin()
+	}
+
+	/// Cancun precompiles, adding the KZG point evaluation (EIP-4844), which
+	/// is not supported, see the module documentation.
+	pub const fn cancun() -> Self {
+		Self { fork: Fork::Cancun }
+	}
+
+	/// Number of precompiles available in this set. They occupy the
+	/// addresses `1..=len`.
+	pub fn len(&self) -> u64 {
+		match self.fork {
+			Fork::Frontier => 4,
+			Fork::Byzantium => 8,
+			Fork::Istanbul | Fork::Berlin => 9,
+			Fork::Cancun => 10,
+		}
+	}
+
+	/// Whether the set is empty. Always `false`.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Addresses of all precompiles in this set.
+	pub fn addresses(&self) -> impl Iterator<Item = H160> {
+		(1..=self.len()).map(|index| {
+			let mut address = H160::default();
+			address.0[19] = index as u8;
+			address
+		})
+	}
+
+	fn index(&self, address: H160) -> Option<u64> {
+		if address[..19] != [0u8; 19] {
+			return None;
+		}
+
+		let index = u64::from(address[19]);
+		if index >= 1 && index <= self.len() {
+			Some(index)
+		} else {
+			None
+		}
+	}
+}
+
+impl PrecompileSet for StandardPrecompileSet {
+	fn execute(
+		&self,
+		address: H160,
+		input: &[u8],
+		gas_limit: Option<u64>,
+		_context: &Context,
+		_is_static: bool,
+	) -> Option<PrecompileResult> {
+		let istanbul = self.fork >= Fork::Istanbul;
+
+		Some(match self.index(address)? {
+			1 => simple::ecrecover(input, gas_limit),
+			2 => simple::sha256(input, gas_limit),
+			3 => simple::ripemd160(input, gas_limit),
+			4 => simple::identity(input, gas_limit),
+			5 => modexp::modexp(input, gas_limit, self.fork >= Fork::Berlin),
+			6 => bn128::add(input, gas_limit, istanbul),
+			7 => bn128::mul(input, gas_limit, istanbul),
+			8 => bn128::pairing(input, gas_limit, istanbul),
+			9 => blake2f::blake2f(input, gas_limit),
+			10 => Err(PrecompileFailure::Fatal {
+				exit_status: ExitFatal::NotSupported,
+			}),
+			_ => return None,
+		})
+	}
+
+	fn is_precompile(&self, address: H160) -> bool {
+		self.index(address).is_some()
+	}
+}
+
+/// Fail with `OutOfGas` if `cost` exceeds the gas limit.
+fn ensure_gas(cost: u64, gas_limit: Option<u64>) -> Result<(), PrecompileFailure> {
+	match gas_limit {
+		Some(gas_limit) if cost > gas_limit => Err(PrecompileFailure::Error {
+			exit_status: ExitError::OutOfGas,
+		}),
+		_ => Ok(()),
+	}
+}
+
+/// Cost of `base` plus `word` for every started 32-byte word of input.
+fn linear_cost(len: usize, base: u64, word: u64) -> Result<u64, PrecompileFailure> {
+	let words = (len as u64 + 31) / 32;
+	words
+		.checked_mul(word)
+		.and_then(|cost| cost.checked_add(base))
+		.ok_or(PrecompileFailure::Error {
+			exit_status: ExitError::OutOfGas,
+		})
+}
+
+/// Read `len` bytes of `input` from `offset`, right-padding with zeros.
+fn read_input(input: &[u8], offset: usize, len: usize) -> Vec<u8> {
+	let mut ret = vec![0; len];
+	if offset < input.len() {
+		let end = core::cmp::min(input.len(), offset.saturating_add(len));
+		ret[..(end - offset)].copy_from_slice(&input[offset..end]);
+	}
+	ret
+}
+
+fn succeed(cost: u64, output: Vec<u8>) -> PrecompileResult {
+	Ok(PrecompileOutput {
+		exit_status: ExitSucceed::Returned,
+		cost,
+		output,
+		logs: Vec::new(),
+	})
+}
+
+fn fail(reason: &'static str) -> PrecompileFailure {
+	PrecompileFailure::Error {
+		exit_status: ExitError::Other(reason.into()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn run(set: &StandardPrecompileSet, index: u64, input: &str) -> PrecompileResult {
+		let context = Context {
+			address: H160::from_low_u64_be(index),
+			caller: H160::default(),
+			apparent_value: Default::default(),
+		};
+		set.execute(
+			H160::from_low_u64_be(index),
+			&hex::decode(input).unwrap(),
+			None,
+			&context,
+			false,
+		)
+		.expect("address is a precompile")
+	}
+
+	fn output(result: PrecompileResult) -> (String, u64) {
+		let output = result.expect("precompile succeeds");
+		(hex::encode(output.output), output.cost)
+	}
+
+	#[test]
+	fn availability_follows_forks() {
+		assert!(StandardPrecompileSet::frontier().is_precompile(H160::from_low_u64_be(4)));
+		assert!(!StandardPrecompileSet::frontier().is_precompile(H160::from_low_u64_be(5)));
+		assert!(!StandardPrecompileSet::byzantium().is_precompile(H160::from_low_u64_be(9)));
+		assert!(StandardPrecompileSet::istanbul().is_precompile(H160::from_low_u64_be(9)));
+		assert!(!StandardPrecompileSet::london().is_precompile(H160::from_low_u64_be(10)));
+		assert!(!StandardPrecompileSet::london().is_precompile(H160::zero()));
+		assert_eq!(StandardPrecompileSet::berlin().addresses().count(), 9);
+		assert_eq!(
+			StandardPrecompileSet::new(&Config::frontier()),
+			StandardPrecompileSet::frontier()
+		);
+		assert_eq!(
+			StandardPrecompileSet::new(&Config::istanbul()),
+			StandardPrecompileSet::istanbul()
+		);
+		assert_eq!(
+			StandardPrecompileSet::new(&Config::london()),
+			StandardPrecompileSet::london()
+		);
+		assert_eq!(
+			StandardPrecompileSet::new(&Config::cancun()),
+			StandardPrecompileSet::cancun()
+		);
+	}
+
+	#[test]
+	fn point_evaluation_is_not_supported() {
+		let cancun = StandardPrecompileSet::cancun();
+		assert!(cancun.is_precompile(H160::from_low_u64_be(10)));
+		assert_eq!(cancun.addresses().count(), 10);
+		assert_eq!(
+			run(&cancun, 10, ""),
+			Err(PrecompileFailure::Fatal {
+				exit_status: ExitFatal::NotSupported
+			})
+		);
+	}
+
+	#[test]
+	fn ecrecover_and_hashes() {
+		let set = StandardPrecompileSet::london();
+
+		assert_eq!(
+			output(run(&set, 1, "18c547e4f7b0f325ad1e56f57e26c745b09a3e503d86e00e5255ff7f715d3d1c000000000000000000000000000000000000000000000000000000000000001c73b1693892219d736caba55bdb67216e485557ea6b6af75f37096c9aa6a5a75feeb940b1d03b21e36b0e47e79769f095fe2ab855bd91e3a38756b7d75a9c4549")),
+			("000000000000000000000000a94f5374fce5edbc8e2a8697c15331677e6ebf0b".into(), 3000)
+		);
+		assert_eq!(output(run(&set, 1, "00")), (String::new(), 3000));
+		assert_eq!(
+			output(run(&set, 2, "")),
+			(
+				"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855".into(),
+				60
+			)
+		);
+		assert_eq!(
+			output(run(&set, 3, "")),
+			(
+				"0000000000000000000000009c1185a5c5e9fc54612808977ee8f548b2258d31".into(),
+				600
+			)
+		);
+		assert_eq!(output(run(&set, 4, "0102")), ("0102".into(), 18));
+	}
+
+	#[test]
+	fn modexp_pricing() {
+		// 3 ** 5 % 7 with one-byte operands.
+		let input = "000000000000000000000000000000000000000000000000000000000000000100000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000001030507";
+
+		assert_eq!(
+			output(run(&StandardPrecompileSet::byzantium(), 5, input)),
+			("05".into(), 0)
+		);
+		assert_eq!(
+			output(run(&StandardPrecompileSet::berlin(), 5, input)),
+			("05".into(), 200)
+		);
+	}
+
+	#[test]
+	fn bn128_add() {
+		// G + G on alt_bn128.
+		let input = "0000000000000000000000000000000000000000000000000000000000000001000000000000000000000000000000000000000000000000000000000000000200000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000002";
+		let double = "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd315ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4";
+
+		assert_eq!(
+			output(run(&StandardPrecompileSet::byzantium(), 6, input)),
+			(double.into(), 500)
+		);
+		assert_eq!(
+			output(run(&StandardPrecompileSet::istanbul(), 6, input)),
+			(double.into(), 150)
+		);
+		// Replace the second point with (1, 3), which is not on the curve.
+		let invalid = format!("{}03", &input[..input.len() - 2]);
+		assert!(run(&StandardPrecompileSet::istanbul(), 6, &invalid).is_err());
+	}
+
+	#[test]
+	fn blake2f_eip152_vector() {
+		// Test vector 5 of EIP-152.
+		let input = "0000000c48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b61626300000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000300000000000000000000000000000001";
+
+		assert_eq!(
+			output(run(&StandardPrecompileSet::istanbul(), 9, input)),
+			("ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923".into(), 12)
+		);
+		assert!(run(&StandardPrecompileSet::istanbul(), 9, &input[2..]).is_err());
+	}
+}
@@ -0,0 +1,98 @@
+use super::{ensure_gas, read_input, succeed};
+use crate::executor::stack::{PrecompileFailure, PrecompileResult};
+use crate::ExitError;
+use alloc::{vec, vec::Vec};
+use core::cmp::max;
+use num_bigint::BigUint;
+use primitive_types::U256;
+
+const OUT_OF_GAS: PrecompileFailure = PrecompileFailure::Error {
+	exit_status: ExitError::OutOfGas,
+};
+
+/// `modexp` at `0x05`, priced by EIP-198, or EIP-2565 when `berlin` is set.
+pub fn modexp(input: &[u8], gas_limit: Option<u64>, berlin: bool) -> PrecompileResult {
+	let base_len = U256::from_big_endian(&read_input(input, 0, 32));
+	let exp_len = U256::from_big_endian(&read_input(input, 32, 32));
+	let mod_len = U256::from_big_endian(&read_input(input, 64, 32));
+
+	// Only the first word of the exponent is looked at for pricing.
+	let exp_head = {
+		let offset = base_len.saturating_add(U256::from(96));
+		let len = exp_len.min(U256::from(32)).as_usize();
+		let mut head = [0u8; 32];
+		if offset < U256::from(input.len()) {
+			head[(32 - len)..].copy_from_slice(&read_input(input, offset.as_usize(), len));
+		}
+		U256::from_big_endian(&head)
+	};
+	let adjusted_exp_len = adjusted_exponent_length(exp_len, exp_head);
+
+	let cost = if berlin {
+		berlin_cost(max(base_len, mod_len), adjusted_exp_len)
+	} else {
+		byzantium_cost(max(base_len, mod_len), adjusted_exp_len)
+	}
+	.ok_or(OUT_OF_GAS)?;
+	if cost > U256::from(u64::MAX) {
+		return Err(OUT_OF_GAS);
+	}
+	let cost = cost.as_u64();
+	ensure_gas(cost, gas_limit)?;
+
+	if mod_len.is_zero() {
+		return succeed(cost, Vec::new());
+	}
+
+	// Paying for the call bounds the operand lengths.
+	let (base_len, exp_len, mod_len) =
+		(base_len.as_usize(), exp_len.as_usize(), mod_len.as_usize());
+	let base = BigUint::from_bytes_be(&read_input(input, 96, base_len));
+	let exponent = BigUint::from_bytes_be(&read_input(input, 96 + base_len, exp_len));
+	let modulus = BigUint::from_bytes_be(&read_input(input, 96 + base_len + exp_len, mod_len));
+
+	let mut output = vec![0; mod_len];
+	if modulus != BigUint::from(0u32) {
+		let result = base.modpow(&exponent, &modulus).to_bytes_be();
+		output[(mod_len - result.len())..].copy_from_slice(&result);
+	}
+	succeed(cost, output)
+}
+
+fn adjusted_exponent_length(exp_len: U256, exp_head: U256) -> U256 {
+	let head_bits = if exp_head.is_zero() {
+		U256::zero()
+	} else {
+		U256::from(exp_head.bits() - 1)
+	};
+
+	if exp_len <= U256::from(32) {
+		head_bits
+	} else {
+		(exp_len - U256::from(32))
+			.saturating_mul(U256::from(8))
+			.saturating_add(head_bits)
+	}
+}
+
+fn byzantium_cost(max_len: U256, adjusted_exp_len: U256) -> Option<U256> {
+	let x = max_len;
+	let square = x.checked_mul(x)?;
+	let complexity = if x <= U256::from(64) {
+		square
+	} else if x <= U256::from(1024) {
+		square / 4 + x * 96 - 3072
+	} else {
+		(square / 16).checked_add(x.checked_mul(U256::from(480))?)? - 199_680
+	};
+
+	Some(complexity.checked_mul(max(adjusted_exp_len, U256::one()))? / 20)
+}
+
+fn berlin_cost(max_len: U256, adjusted_exp_len: U256) -> Option<U256> {
+	let words = max_len.checked_add(U256::from(7))? / 8;
+	let complexity = words.checked_mul(words)?;
+	let cost = complexity.checked_mul(max(adjusted_exp_len, U256::one()))? / 3;
+
+	Some(max(cost, U256::from(200)))
+}
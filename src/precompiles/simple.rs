@@ -0,0 +1,61 @@
+use super::{ensure_gas, linear_cost, read_input, succeed};
+use crate::executor::stack::PrecompileResult;
+use alloc::{vec, vec::Vec};
+use sha3::{Digest, Keccak256};
+
+/// `ecrecover` at `0x01`. Recovers the signer of a message hash, returning
+/// empty output if the signature is invalid.
+pub fn ecrecover(input: &[u8], gas_limit: Option<u64>) -> PrecompileResult {
+	const COST: u64 = 3000;
+	ensure_gas(COST, gas_limit)?;
+
+	let input = read_input(input, 0, 128);
+
+	// `v` is a full word which must be either 27 or 28.
+	if input[32..63] != [0u8; 31] || (input[63] != 27 && input[63] != 28) {
+		return succeed(COST, Vec::new());
+	}
+
+	let recovered = (|| {
+		let message = libsecp256k1::Message::parse_slice(&input[0..32]).ok()?;
+		let signature = libsecp256k1::Signature::parse_standard_slice(&input[64..128]).ok()?;
+		let recovery_id = libsecp256k1::RecoveryId::parse(input[63] - 27).ok()?;
+		libsecp256k1::recover(&message, &signature, &recovery_id).ok()
+	})();
+
+	match recovered {
+		Some(public) => {
+			let hash = Keccak256::digest(&public.serialize()[1..]);
+			let mut output = vec![0; 32];
+			output[12..].copy_from_slice(&hash[12..]);
+			succeed(COST, output)
+		}
+		None => succeed(COST, Vec::new()),
+	}
+}
+
+/// `sha256` at `0x02`.
+pub fn sha256(input: &[u8], gas_limit: Option<u64>) -> PrecompileResult {
+	let cost = linear_cost(input.len(), 60, 12)?;
+	ensure_gas(cost, gas_limit)?;
+
+	succeed(cost, sha2::Sha256::digest(input).to_vec())
+}
+
+/// `ripemd160` at `0x03`. The 20-byte digest is left-padded to a word.
+pub fn ripemd160(input: &[u8], gas_limit: Option<u64>) -> PrecompileResult {
+	let cost = linear_cost(input.len(), 600, 120)?;
+	ensure_gas(cost, gas_limit)?;
+
+	let mut output = vec![0; 32];
+	output[12..].copy_from_slice(&ripemd160::Ripemd160::digest(input));
+	succeed(cost, output)
+}
+
+/// `identity` at `0x04`.
+pub fn identity(input: &[u8], gas_limit: Option<u64>) -> PrecompileResult {
+	let cost = linear_cost(input.len(), 15, 3)?;
+	ensure_gas(cost, gas_limit)?;
+
+	succeed(cost, input.to_vec())
+}
@@ -0,0 +1,779 @@
+//! Call tracer building the tree of call frames of a transaction, in the same
+//! shape as geth's `callTracer`.
+
+use super::{error_message, write_hex, write_string, Event, EventListener};
+use crate::{Config, CreateScheme, ExitReason, Opcode};
+use alloc::{rc::Rc, string::String, vec::Vec};
+use core::cell::RefCell;
+use core::cmp::min;
+use core::fmt::{self, Write};
+use evm_gasometer::tracing::{Event as GasometerEvent, EventListener as GasometerEventListener};
+use evm_gasometer::Snapshot;
+use evm_runtime::tracing::{Event as RuntimeEvent, EventListener as RuntimeEventListener};
+use primitive_types::{H160, U256};
+
+/// Type of a call frame.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CallType {
+	Call,
+	CallCode,
+	DelegateCall,
+	StaticCall,
+	Create,
+	Create2,
+	SelfDestruct,
+}
+
+impl CallType {
+	/// Name of the type, as the opcode starting the frame.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Self::Call => "CALL",
+			Self::CallCode => "CALLCODE",
+			Self::DelegateCall => "DELEGATECALL",
+			Self::StaticCall => "STATICCALL",
+			Self::Create => "CREATE",
+			Self::Create2 => "CREATE2",
+			Self::SelfDestruct => "SELFDESTRUCT",
+		}
+	}
+}
+
+/// A call frame and its sub-calls. Formatting it with `Display` gives the
+/// JSON object of geth's `callTracer`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CallFrame {
+	pub call_type: CallType,
+	pub from: H160,
+	/// Callee or created address. `None` for a failed create.
+	pub to: Option<H160>,
+	/// Value transferred. `None` for delegate and static calls.
+	pub value: Option<U256>,
+	/// Gas given to the frame. For the outermost frame, the gas limit of
+	/// the transaction.
+	pub gas: u64,
+	/// Gas used by the frame. For the outermost frame, the gas used by the
+	/// transaction, after refunds.
+	pub gas_used: u64,
+	pub input: Vec<u8>,
+	pub output: Vec<u8>,
+	pub error: Option<String>,
+	/// Reason string of a `revert(string)`.
+	pub revert_reason: Option<String>,
+	pub calls: Vec<CallFrame>,
+}
+
+impl fmt::Display for CallFrame {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"{{\"type\":\"{}\",\"from\":\"{:#x}\",\"gas\":\"{:#x}\",\"gasUsed\":\"{:#x}\"",
+			self.call_type.as_str(),
+			self.from,
+			self.gas,
+			self.gas_used
+		)?;
+		if let Some(to) = self.to {
+			write!(f, ",\"to\":\"{:#x}\"", to)?;
+		}
+		f.write_str(",\"input\":")?;
+		write_hex(f, &self.input)?;
+		if !self.output.is_empty() {
+			f.write_str(",\"output\":")?;
+			write_hex(f, &self.output)?;
+		}
+		if let Some(error) = &self.error {
+			f.write_str(",\"error\":")?;
+			write_string(f, error)?;
+		}
+		if let Some(revert_reason) = &self.revert_reason {
+			f.write_str(",\"revertReason\":")?;
+			write_string(f, revert_reason)?;
+		}
+		if !self.calls.is_empty() {
+			f.write_str(",\"calls\":[")?;
+			for (i, call) in self.calls.iter().enumerate() {
+				if i > 0 {
+					f.write_char(',')?;
+				}
+				write!(f, "{}", call)?;
+			}
+			f.write_char(']')?;
+		}
+		if let Some(value) = self.value {
+			write!(f, ",\"value\":\"{:#x}\"", value)?;
+		}
+		f.write_char('}')
+	}
+}
+
+/// Event listener building one call tree per transaction out of executor,
+/// runtime and gasometer events.
+///
+/// Install it with `using`, and collect the trees with `take_traces`.
+pub struct CallTracer {
+	state: Rc<RefCell<State>>,
+}
+
+impl CallTracer {
+	/// Create a call tracer for transactions executed with `config`.
+	pub fn new(config: &Config) -> Self {
+		Self {
+			state: Rc::new(RefCell::new(State {
+				call_stipend: config.call_stipend,
+				estimate_l64: config.estimate && config.call_l64_after_gas,
+				max_refund_quotient: config.max_refund_quotient,
+				transaction: None,
+				opcode: None,
+				frames: Vec::new(),
+				traces: Vec::new(),
+			})),
+		}
+	}
+
+	/// Run closure with the tracer listening to executor, runtime and
+	/// gasometer events.
+	pub fn using<R, F: FnOnce() -> R>(&self, f: F) -> R {
+		let mut executor = Listener(self.state.clone());
+		let mut runtime = Listener(self.state.clone());
+		let mut gasometer = Listener(self.state.clone());
+
+		super::using(&mut executor, || {
+			evm_runtime::tracing::using(&mut runtime, || {
+				evm_gasometer::tracing::using(&mut gasometer, f)
+			})
+		})
+	}
+
+	/// Take the call trees of the transactions traced so far.
+	pub fn take_traces(&self) -> Vec<CallFrame> {
+		core::mem::take(&mut self.state.borrow_mut().traces)
+	}
+}
+
+/// Gas of a gasometer, as known from its snapshots.
+#[derive(Clone, Copy)]
+struct GasTracker {
+	gas_limit: u64,
+	gas_left: u64,
+	refunded_gas: i64,
+}
+
+/// Change of a gasometer, as described by a gasometer event.
+#[derive(Clone, Copy)]
+enum GasChange {
+	Transaction(u64),
+	Cost(u64),
+	Stipend(u64),
+	Refund(i64),
+	DynamicCost {
+		gas_cost: u64,
+		memory_gas: u64,
+		gas_refund: i64,
+	},
+}
+
+impl GasChange {
+	/// Gasometer after the change, given its snapshot before the change.
+	fn apply(self, snapshot: &Snapshot) -> GasTracker {
+		let gas = snapshot.gas();
+		let (gas_left, refund) = match self {
+			Self::Transaction(cost) | Self::Cost(cost) => (gas.saturating_sub(cost), 0),
+			Self::Stipend(stipend) => (gas.saturating_add(stipend), 0),
+			Self::Refund(refund) => (gas, refund),
+			Self::DynamicCost {
+				gas_cost,
+				memory_gas,
+				gas_refund,
+			} => {
+				let memory_cost = memory_gas.saturating_sub(snapshot.memory_gas);
+				(
+					gas.saturating_sub(gas_cost).saturating_sub(memory_cost),
+					gas_refund,
+				)
+			}
+		};
+
+		GasTracker {
+			gas_limit: snapshot.gas_limit,
+			gas_left,
+			refunded_gas: snapshot.refunded_gas + refund,
+		}
+	}
+}
+
+struct OpenFrame {
+	frame: CallFrame,
+	/// Gasometer of the frame, once it has recorded anything.
+	gasometer: Option<GasTracker>,
+	/// Gas the parent frame set aside for this frame.
+	forwarded: u64,
+	stipend: u64,
+	/// Costs the parent still records before this frame starts.
+	pending_costs: u8,
+}
+
+struct Transaction {
+	gas_limit: u64,
+	gasometer: GasTracker,
+}
+
+struct State {
+	call_stipend: u64,
+	/// Whether calls and creates made by a frame record the gas kept by the
+	/// frame as a cost before the gas given to them. See `Config::estimate`.
+	estimate_l64: bool,
+	max_refund_quotient: u64,
+	transaction: Option<Transaction>,
+	/// Last opcode stepped into, telling the scheme of the next call.
+	opcode: Option<Opcode>,
+	frames: Vec<OpenFrame>,
+	traces: Vec<CallFrame>,
+}
+
+impl State {
+	fn transact(&mut self, gas_limit: u64) {
+		self.frames.clear();
+		self.opcode = None;
+		self.transaction = Some(Transaction {
+			gas_limit,
+			gasometer: GasTracker {
+				gas_limit,
+				gas_left: gas_limit,
+				refunded_gas: 0,
+			},
+		});
+	}
+
+	fn enter(&mut self, frame: CallFrame, stipend: u64) {
+		// The parent records the gas it gives to the frame, after the gas it
+		// keeps for itself in estimate mode. The outermost frame is given
+		// the gas of the transaction, which keeps nothing.
+		let pending_costs = if self.estimate_l64 && !self.frames.is_empty() {
+			2
+		} else {
+			1
+		};
+		self.frames.push(OpenFrame {
+			frame,
+			gasometer: None,
+			forwarded: 0,
+			stipend,
+			pending_costs,
+		});
+	}
+
+	fn exit(&mut self, reason: &ExitReason, return_value: &[u8]) {
+		let OpenFrame {
+			mut frame,
+			gasometer,
+			forwarded,
+			stipend,
+			..
+		} = match self.frames.pop() {
+			Some(frame) => frame,
+			None => return,
+		};
+
+		frame.gas = match gasometer {
+			Some(gasometer) => gasometer.gas_limit,
+			None => forwarded.saturating_add(stipend),
+		};
+		frame.gas_used = match reason {
+			// Failed frames consume all of their gas.
+			ExitReason::Error(_) | ExitReason::Fatal(_) => frame.gas,
+			_ => gasometer
+				.map(|gasometer| gasometer.gas_limit.saturating_sub(gasometer.gas_left))
+				.unwrap_or(0),
+		};
+
+		match reason {
+			ExitReason::Succeed(_) => frame.output = return_value.to_vec(),
+			ExitReason::Revert(_) => {
+				frame.error = Some("execution reverted".into());
+				frame.output = return_value.to_vec();
+				frame.revert_reason = revert_reason(return_value);
+			}
+			ExitReason::Error(_) | ExitReason::Fatal(_) => frame.error = error_message(reason),
+		}
+		if frame.error.is_some()
+			&& (frame.call_type == CallType::Create || frame.call_type == CallType::Create2)
+		{
+			frame.to = None;
+		}
+
+		match self.frames.last_mut() {
+			Some(parent) => parent.frame.calls.push(frame),
+			None => {
+				if let Some(transaction) = self.transaction.take() {
+					let used_gas = transaction
+						.gas_limit
+						.saturating_sub(transaction.gasometer.gas_left);
+					let refunded_gas = transaction.gasometer.refunded_gas.max(0) as u64;
+
+					frame.gas = transaction.gas_limit;
+					frame.gas_used =
+						used_gas - min(used_gas / self.max_refund_quotient, refunded_gas);
+				}
+				self.traces.push(frame);
+			}
+		}
+	}
+
+	fn record(&mut self, change: GasChange, snapshot: Option<Snapshot>) {
+		let snapshot = match snapshot {
+			Some(snapshot) => snapshot,
+			None => return,
+		};
+
+		// Index `top` stands for the gasometer of the transaction, below all
+		// frames, and `parent` for the gasometer of the parent of the
+		// innermost frame.
+		let top = self.frames.len();
+		let parent = if top >= 2 { top - 2 } else { top };
+		let owner = match change {
+			GasChange::Transaction(_) => top,
+			// Only a parent taking back the gas of the innermost frame, as it
+			// exits, records stipends and refunds.
+			GasChange::Stipend(_) | GasChange::Refund(_) => parent,
+			GasChange::Cost(_) | GasChange::DynamicCost { .. } => match self.frames.last_mut() {
+				// A cost recorded by the parent of a frame that has not
+				// started yet is the gas set aside for that frame.
+				Some(open) if open.pending_costs > 0 => {
+					open.pending_costs -= 1;
+					if let GasChange::Cost(cost) = change {
+						open.forwarded = cost;
+					}
+					parent
+				}
+				Some(_) => top - 1,
+				None => top,
+			},
+		};
+
+		let gasometer = change.apply(&snapshot);
+		if owner == top {
+			if let Some(transaction) = self.transaction.as_mut() {
+				transaction.gasometer = gasometer;
+			}
+		} else {
+			self.frames[owner].gasometer = Some(gasometer);
+		}
+	}
+}
+
+/// Decode the reason of an `Error(string)` revert.
+fn revert_reason(output: &[u8]) -> Option<String> {
+	const SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+	fn read_usize(data: &[u8], offset: usize) -> Option<usize> {
+		let word = data.get(offset..offset.checked_add(32)?)?;
+		let value = U256::from_big_endian(word);
+		if value > U256::from(usize::MAX) {
+			None
+		} else {
+			Some(value.as_usize())
+		}
+	}
+
+	if output.len() < 4 || output[..4] != SELECTOR {
+		return None;
+	}
+	let data = &output[4..];
+	let offset = read_usize(data, 0)?;
+	let len = read_usize(data, offset)?;
+	let start = offset.checked_add(32)?;
+	let reason = data.get(start..start.checked_add(len)?)?;
+
+	String::from_utf8(reason.to_vec()).ok()
+}
+
+struct Listener(Rc<RefCell<State>>);
+
+impl EventListener for Listener {
+	fn event(&mut self, event: Event) {
+		let mut state = self.0.borrow_mut();
+		match event {
+			Event::TransactCall { gas_limit, .. }
+			| Event::TransactCreate { gas_limit, .. }
+			| Event::TransactCreate2 { gas_limit, .. } => state.transact(gas_limit),
+			Event::Call {
+				code_address,
+				input,
+				context,
+				..
+			} => {
+				// The outermost call of a transaction has no opcode.
+				let call_type = match state.opcode.take() {
+					Some(Opcode::CALLCODE) => CallType::CallCode,
+					Some(Opcode::DELEGATECALL) => CallType::DelegateCall,
+					Some(Opcode::STATICCALL) => CallType::StaticCall,
+					_ => CallType::Call,
+				};
+				let value = match call_type {
+					CallType::Call | CallType::CallCode => Some(context.apparent_value),
+					_ => None,
+				};
+				let stipend = match value {
+					Some(value) if value != U256::zero() => state.call_stipend,
+					_ => 0,
+				};
+
+				// A delegate call runs with the context of its caller.
+				let from = match call_type {
+					CallType::DelegateCall => context.address,
+					_ => context.caller,
+				};
+
+				state.enter(
+					CallFrame {
+						call_type,
+						from,
+						to: Some(code_address),
+						value,
+						gas: 0,
+						gas_used: 0,
+						input: input.to_vec(),
+						output: Vec::new(),
+						error: None,
+						revert_reason: None,
+						calls: Vec::new(),
+					},
+					stipend,
+				);
+			}
+			Event::Create {
+				caller,
+				address,
+				scheme,
+				value,
+				init_code,
+				..
+			} => {
+				let call_type = match scheme {
+					CreateScheme::Create2 { .. } => CallType::Create2,
+					_ => CallType::Create,
+				};
+
+				state.enter(
+					CallFrame {
+						call_type,
+						from: caller,
+						to: Some(address),
+						value: Some(value),
+						gas: 0,
+						gas_used: 0,
+						input: init_code.to_vec(),
+						output: Vec::new(),
+						error: None,
+						revert_reason: None,
+						calls: Vec::new(),
+					},
+					0,
+				);
+			}
+			Event::Suicide {
+				address,
+				target,
+				balance,
+			} => {
+				if let Some(open) = state.frames.last_mut() {
+					open.frame.calls.push(CallFrame {
+						call_type: CallType::SelfDestruct,
+						from: address,
+						to: Some(target),
+						value: Some(balance),
+						gas: 0,
+						gas_used: 0,
+						input: Vec::new(),
+						output: Vec::new(),
+						error: None,
+						revert_reason: None,
+						calls: Vec::new(),
+					});
+				}
+			}
+			Event::Exit {
+				reason,
+				return_value,
+			} => state.exit(reason, return_value),
+		}
+	}
+}
+
+impl RuntimeEventListener for Listener {
+	fn event(&mut self, event: RuntimeEvent) {
+		if let RuntimeEvent::Step { opcode, .. } = event {
+			self.0.borrow_mut().opcode = Some(opcode);
+		}
+	}
+}
+
+impl GasometerEventListener for Listener {
+	fn event(&mut self, event: GasometerEvent) {
+		let (change, snapshot) = match event {
+			GasometerEvent::RecordCost { cost, snapshot } => (GasChange::Cost(cost), snapshot),
+			GasometerEvent::RecordTransaction { cost, snapshot } => {
+				(GasChange::Transaction(cost), snapshot)
+			}
+			GasometerEvent::RecordStipend { stipend, snapshot } => {
+				(GasChange::Stipend(stipend), snapshot)
+			}
+			GasometerEvent::RecordRefund { refund, snapshot } => {
+				(GasChange::Refund(refund), snapshot)
+			}
+			GasometerEvent::RecordDynamicCost {
+				gas_cost,
+				memory_gas,
+				gas_refund,
+				snapshot,
+			} => (
+				GasChange::DynamicCost {
+					gas_cost,
+					memory_gas,
+					gas_refund,
+				},
+				snapshot,
+			),
+		};
+
+		self.0.borrow_mut().record(change, snapshot);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::assemble;
+	use crate::backend::{MemoryAccount, MemoryBackend, MemoryVicinity};
+	use crate::executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata};
+	use alloc::collections::BTreeMap;
+	use alloc::string::ToString;
+	use alloc::{format, vec};
+
+	#[test]
+	fn traces_nested_revert() {
+		let config = Config::istanbul();
+		let vicinity = MemoryVicinity::for_tests(100_000);
+		let mut state = BTreeMap::new();
+		// CALL(0xffff, 0x33, 0, 0, 0, 0, 0), STOP
+		state.insert(
+			H160::repeat_byte(0x11),
+			MemoryAccount {
+				nonce: U256::one(),
+				balance: U256::zero(),
+				storage: BTreeMap::new(),
+				code: hex::decode("60006000600060006000603361fffff100").unwrap(),
+			},
+		);
+		// Revert with `Error("boom")`, copied from the end of the code.
+		state.insert(
+			H160::from_low_u64_be(0x33),
+			MemoryAccount {
+				nonce: U256::one(),
+				balance: U256::zero(),
+				storage: BTreeMap::new(),
+				code: hex::decode(
+					"6064600c60003960646000fd\
+					 08c379a0\
+					 0000000000000000000000000000000000000000000000000000000000000020\
+					 0000000000000000000000000000000000000000000000000000000000000004\
+					 626f6f6d00000000000000000000000000000000000000000000000000000000"
+						.replace(' ', ""),
+				)
+				.unwrap(),
+			},
+		);
+		let backend = MemoryBackend::new(&vicinity, state);
+		let metadata = StackSubstateMetadata::new(100_000, &config);
+		let state = MemoryStackState::new(metadata, &backend);
+		let precompiles = BTreeMap::new();
+		let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+
+		let tracer = CallTracer::new(&config);
+		tracer.using(|| {
+			executor.transact_call(
+				H160::repeat_byte(0x22),
+				H160::repeat_byte(0x11),
+				U256::zero(),
+				Vec::new(),
+				100_000,
+				Vec::new(),
+			)
+		});
+		let traces = tracer.take_traces();
+
+		assert_eq!(traces.len(), 1);
+		assert_eq!(traces[0].gas, 100_000);
+		assert_eq!(traces[0].gas_used, executor.used_gas());
+		assert_eq!(traces[0].calls.len(), 1);
+		assert_eq!(traces[0].calls[0].revert_reason, Some("boom".into()));
+		assert_eq!(
+			traces[0].calls[0].to_string(),
+			"{\"type\":\"CALL\",\"from\":\"0x1111111111111111111111111111111111111111\",\
+			 \"gas\":\"0xffff\",\"gasUsed\":\"0x2a\",\
+			 \"to\":\"0x0000000000000000000000000000000000000033\",\"input\":\"0x\",\
+			 \"output\":\"0x08c379a0\
+			 0000000000000000000000000000000000000000000000000000000000000020\
+			 0000000000000000000000000000000000000000000000000000000000000004\
+			 626f6f6d00000000000000000000000000000000000000000000000000000000\",\
+			 \"error\":\"execution reverted\",\"revertReason\":\"boom\",\"value\":\"0x0\"}"
+		);
+	}
+
+	/// Trace a transaction from 0x22 to `address`, or creating a contract
+	/// with `data` if it is `None`, with the given accounts.
+	fn trace_transaction(
+		config: &Config,
+		accounts: Vec<(H160, Vec<u8>)>,
+		address: Option<H160>,
+		data: Vec<u8>,
+	) -> CallFrame {
+		let vicinity = MemoryVicinity::for_tests(1_000_000);
+		let state = accounts
+			.into_iter()
+			.map(|(address, code)| {
+				let account = MemoryAccount {
+					nonce: U256::one(),
+					balance: U256::zero(),
+					storage: BTreeMap::new(),
+					code,
+				};
+				(address, account)
+			})
+			.collect();
+		let backend = MemoryBackend::new(&vicinity, state);
+		let metadata = StackSubstateMetadata::new(1_000_000, config);
+		let state = MemoryStackState::new(metadata, &backend);
+		let precompiles = BTreeMap::new();
+		let mut executor = StackExecutor::new_with_precompiles(state, config, &precompiles);
+
+		let tracer = CallTracer::new(config);
+		let reason = tracer.using(|| match address {
+			Some(address) => {
+				executor
+					.transact_call(
+						H160::repeat_byte(0x22),
+						address,
+						U256::zero(),
+						data,
+						1_000_000,
+						Vec::new(),
+					)
+					.0
+			}
+			None => executor.transact_create(
+				H160::repeat_byte(0x22),
+				U256::zero(),
+				data,
+				1_000_000,
+				Vec::new(),
+			),
+		});
+		assert!(reason.is_succeed(), "{:?}", reason);
+
+		let mut traces = tracer.take_traces();
+		assert_eq!(traces.len(), 1);
+		traces.remove(0)
+	}
+
+	#[test]
+	fn traces_delegate_and_self_calls() {
+		let this = H160::repeat_byte(0x11);
+		let library = H160::from_low_u64_be(0x33);
+		// Without call data, delegate call the library, then delegate call,
+		// call code and call itself with one byte of call data.
+		let code = assemble(
+			"
+			CALLDATASIZE PUSH @done JUMPI
+			PUSH1 0 PUSH1 0 PUSH1 0 PUSH1 0 PUSH1 0x33 GAS DELEGATECALL POP
+			PUSH1 0 PUSH1 0 PUSH1 1 PUSH1 0 ADDRESS GAS DELEGATECALL POP
+			PUSH1 0 PUSH1 0 PUSH1 1 PUSH1 0 PUSH1 0 ADDRESS GAS CALLCODE POP
+			PUSH1 0 PUSH1 0 PUSH1 1 PUSH1 0 PUSH1 0 ADDRESS GAS CALL POP
+			STOP
+			done:
+				STOP
+			",
+		)
+		.unwrap();
+
+		let trace = trace_transaction(
+			&Config::istanbul(),
+			vec![(this, code), (library, vec![0x00])],
+			Some(this),
+			Vec::new(),
+		);
+		assert_eq!(trace.call_type, CallType::Call);
+		assert_eq!(trace.from, H160::repeat_byte(0x22));
+
+		let calls = trace
+			.calls
+			.iter()
+			.map(|call| (call.call_type, call.from, call.to, call.value))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			calls,
+			vec![
+				(CallType::DelegateCall, this, Some(library), None),
+				(CallType::DelegateCall, this, Some(this), None),
+				(CallType::CallCode, this, Some(this), Some(U256::zero())),
+				(CallType::Call, this, Some(this), Some(U256::zero())),
+			]
+		);
+	}
+
+	#[test]
+	fn traces_deployed_code() {
+		// Deploy the code `0x6000`.
+		let init_code = assemble("PUSH2 0x6000 PUSH1 0 MSTORE PUSH1 2 PUSH1 30 RETURN").unwrap();
+
+		let trace = trace_transaction(&Config::istanbul(), Vec::new(), None, init_code.clone());
+		assert_eq!(trace.call_type, CallType::Create);
+		assert!(trace.to.is_some());
+		assert_eq!(trace.output, vec![0x60, 0x00]);
+
+		let factory = H160::repeat_byte(0x11);
+		let code = assemble(&format!(
+			"PUSH{} 0x{} PUSH1 0 MSTORE PUSH1 {} PUSH1 {} PUSH1 0 CREATE POP STOP",
+			init_code.len(),
+			hex::encode(&init_code),
+			init_code.len(),
+			32 - init_code.len(),
+		))
+		.unwrap();
+
+		let trace = trace_transaction(
+			&Config::istanbul(),
+			vec![(factory, code)],
+			Some(factory),
+			Vec::new(),
+		);
+		assert_eq!(trace.calls.len(), 1);
+		assert_eq!(trace.calls[0].call_type, CallType::Create);
+		assert_eq!(trace.calls[0].from, factory);
+		assert_eq!(trace.calls[0].input, init_code);
+		assert_eq!(trace.calls[0].output, vec![0x60, 0x00]);
+	}
+
+	#[test]
+	fn traces_gas_in_estimate_mode() {
+		// CALL(GAS, 0x33, 0, 0, 0, 0, 0), STOP, where 0x33 has no code and
+		// records no gas.
+		let this = H160::repeat_byte(0x11);
+		let accounts = vec![(this, hex::decode("6000600060006000600060335af100").unwrap())];
+
+		// The gas kept by the caller is recorded as a cost in estimate mode,
+		// before the gas given to the callee.
+		let config = Config::istanbul();
+		let estimate = Config {
+			estimate: true,
+			..Config::istanbul()
+		};
+		let trace = trace_transaction(&config, accounts.clone(), Some(this), Vec::new());
+		let estimated = trace_transaction(&estimate, accounts, Some(this), Vec::new());
+		assert_eq!(estimated.calls.len(), 1);
+		assert_eq!(estimated.calls[0].gas, trace.calls[0].gas);
+		assert_eq!(estimated.calls[0].gas_used, 0);
+		assert!(estimated.gas_used > trace.gas_used);
+	}
+}
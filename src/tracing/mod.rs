@@ -0,0 +1,134 @@
+//! Allows to listen to runtime events.
+
+mod call_tracer;
+mod struct_logger;
+
+pub use self::call_tracer::{CallFrame, CallTracer, CallType};
+pub use self::struct_logger::{StructLog, StructLogger, StructLoggerConfig};
+
+use crate::{Context, ExitError};
+use alloc::{format, string::String};
+use core::fmt::{self, Write};
+use evm_runtime::{CreateScheme, ExitReason, Transfer};
+use primitive_types::{H160, H256, U256};
+
+environmental::environmental!(listener: dyn EventListener + 'static);
+
+pub trait EventListener {
+	fn event(&mut self, event: Event);
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum Event<'a> {
+	Call {
+		code_address: H160,
+		transfer: &'a Option<Transfer>,
+		input: &'a [u8],
+		target_gas: Option<u64>,
+		is_static: bool,
+		context: &'a Context,
+	},
+	Create {
+		caller: H160,
+		address: H160,
+		scheme: CreateScheme,
+		value: U256,
+		init_code: &'a [u8],
+		target_gas: Option<u64>,
+	},
+	Suicide {
+		address: H160,
+		target: H160,
+		balance: U256,
+	},
+	Exit {
+		reason: &'a ExitReason,
+		return_value: &'a [u8],
+	},
+	TransactCall {
+		caller: H160,
+		address: H160,
+		value: U256,
+		data: &'a [u8],
+		gas_limit: u64,
+	},
+	TransactCreate {
+		caller: H160,
+		value: U256,
+		init_code: &'a [u8],
+		gas_limit: u64,
+		address: H160,
+	},
+	TransactCreate2 {
+		caller: H160,
+		value: U256,
+		init_code: &'a [u8],
+		salt: H256,
+		gas_limit: u64,
+		address: H160,
+	},
+}
+
+// Expose `listener::with` to the crate only.
+pub(crate) fn with<F: FnOnce(&mut (dyn EventListener + 'static))>(f: F) {
+	listener::with(f);
+}
+
+/// Run closure with provided listener.
+pub fn using<R, F: FnOnce() -> R>(new: &mut (dyn EventListener + 'static), f: F) -> R {
+	listener::using(new, f)
+}
+
+/// Geth's message for an error exit. `None` for successful and reverted
+/// exits.
+fn error_message(reason: &ExitReason) -> Option<String> {
+	let error = match reason {
+		ExitReason::Succeed(_) | ExitReason::Revert(_) => return None,
+		ExitReason::Fatal(fatal) => return Some(format!("{:?}", fatal)),
+		ExitReason::Error(error) => error,
+	};
+
+	Some(
+		match error {
+			ExitError::StackUnderflow => "stack underflow",
+			ExitError::StackOverflow => "stack limit reached",
+			ExitError::InvalidJump => "invalid jump destination",
+			ExitError::InvalidRange => "invalid memory range",
+			ExitError::DesignatedInvalid => "invalid opcode: INVALID",
+			ExitError::CallTooDeep => "max call depth exceeded",
+			ExitError::CreateCollision => "contract address collision",
+			ExitError::CreateContractLimit => "max code size exceeded",
+			ExitError::InvalidCode => "invalid code: must not begin with 0xef",
+			ExitError::OutOfOffset => "return data out of bounds",
+			ExitError::OutOfGas => "out of gas",
+			ExitError::OutOfFund => "insufficient balance for transfer",
+			ExitError::PCUnderflow => "pc underflow",
+			ExitError::CreateEmpty => "create empty",
+			ExitError::Other(reason) => reason,
+		}
+		.into(),
+	)
+}
+
+/// Write bytes as a `0x`-prefixed JSON string.
+fn write_hex(f: &mut fmt::Formatter, bytes: &[u8]) -> fmt::Result {
+	f.write_str("\"0x")?;
+	for byte in bytes {
+		write!(f, "{:02x}", byte)?;
+	}
+	f.write_char('"')
+}
+
+/// Write a JSON string, escaping it as needed.
+fn write_string(f: &mut fmt::Formatter, value: &str) -> fmt::Result {
+	f.write_char('"')?;
+	for c in value.chars() {
+		match c {
+			'"' => f.write_str("\\\"")?,
+			'\\' => f.write_str("\\\\")?,
+			c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+			c => f.write_char(c)?,
+		}
+	}
+	f.write_char('"')
+}
@@ -0,0 +1,381 @@
+//! Structured logger producing [EIP-3155](https://eips.ethereum.org/EIPS/eip-3155)
+//! traces, in the same shape as geth's `evm --json` output.
+
+use super::{error_message, write_hex, write_string, Event, EventListener};
+use crate::{ExitReason, Memory, Opcode, Stack};
+use alloc::{rc::Rc, string::String, vec::Vec};
+use core::cell::RefCell;
+use core::fmt::{self, Write};
+use evm_gasometer::tracing::{Event as GasometerEvent, EventListener as GasometerEventListener};
+use evm_gasometer::Snapshot;
+use evm_runtime::tracing::{Event as RuntimeEvent, EventListener as RuntimeEventListener};
+use primitive_types::{H256, U256};
+
+/// Options of the structured logger.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct StructLoggerConfig {
+	/// Leave out the memory of each step.
+	pub disable_memory: bool,
+	/// Leave out the stack of each step.
+	pub disable_stack: bool,
+}
+
+/// A single step of an EIP-3155 trace. Formatting it with `Display` gives
+/// the JSON line of the step.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StructLog {
+	/// Program counter.
+	pub pc: usize,
+	/// Opcode executed.
+	pub op: Opcode,
+	/// Gas left before executing the opcode.
+	pub gas: u64,
+	/// Gas cost of the opcode, including memory expansion and the gas passed
+	/// on to sub-calls.
+	pub gas_cost: u64,
+	/// Memory before executing the opcode, `None` if disabled.
+	pub memory: Option<Vec<u8>>,
+	/// Size of the memory in bytes.
+	pub mem_size: usize,
+	/// Stack before executing the opcode, bottom first. `None` if disabled.
+	pub stack: Option<Vec<H256>>,
+	/// Call depth, starting at one.
+	pub depth: usize,
+	/// Refund counter before executing the opcode.
+	pub refund: i64,
+	/// Error the execution failed with at this step.
+	pub error: Option<String>,
+}
+
+impl fmt::Display for StructLog {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"{{\"pc\":{},\"op\":{},\"gas\":\"{:#x}\",\"gasCost\":\"{:#x}\"",
+			self.pc, self.op.0, self.gas, self.gas_cost
+		)?;
+		if let Some(memory) = &self.memory {
+			f.write_str(",\"memory\":")?;
+			write_hex(f, memory)?;
+		}
+		write!(f, ",\"memSize\":{}", self.mem_size)?;
+		if let Some(stack) = &self.stack {
+			f.write_str(",\"stack\":[")?;
+			for (i, value) in stack.iter().enumerate() {
+				if i > 0 {
+					f.write_char(',')?;
+				}
+				write!(f, "\"{:#x}\"", U256::from_big_endian(&value[..]))?;
+			}
+			f.write_char(']')?;
+		}
+		write!(
+			f,
+			",\"depth\":{},\"refund\":{},\"opName\":\"{}\"",
+			self.depth, self.refund, self.op
+		)?;
+		if let Some(error) = &self.error {
+			f.write_str(",\"error\":")?;
+			write_string(f, error)?;
+		}
+		f.write_char('}')
+	}
+}
+
+/// Event listener joining runtime, gasometer and executor events into
+/// EIP-3155 steps.
+///
+/// Install it with `using`, and collect the steps with `take_logs`.
+pub struct StructLogger {
+	state: Rc<RefCell<State>>,
+}
+
+impl StructLogger {
+	pub fn new(config: StructLoggerConfig) -> Self {
+		Self {
+			state: Rc::new(RefCell::new(State {
+				config,
+				depth: 0,
+				pending: None,
+				logs: Vec::new(),
+			})),
+		}
+	}
+
+	/// Run closure with the logger listening to runtime, gasometer and
+	/// executor events.
+	pub fn using<R, F: FnOnce() -> R>(&self, f: F) -> R {
+		let mut executor = Listener(self.state.clone());
+		let mut runtime = Listener(self.state.clone());
+		let mut gasometer = Listener(self.state.clone());
+
+		super::using(&mut executor, || {
+			evm_runtime::tracing::using(&mut runtime, || {
+				evm_gasometer::tracing::using(&mut gasometer, f)
+			})
+		})
+	}
+
+	/// Take the steps logged so far.
+	pub fn take_logs(&self) -> Vec<StructLog> {
+		let mut state = self.state.borrow_mut();
+		state.flush();
+		core::mem::take(&mut state.logs)
+	}
+}
+
+struct PendingLog {
+	log: StructLog,
+	/// Gas limit of the gasometer the step is recorded on.
+	gas_limit: Option<u64>,
+}
+
+struct State {
+	config: StructLoggerConfig,
+	depth: usize,
+	pending: Option<PendingLog>,
+	logs: Vec<StructLog>,
+}
+
+impl State {
+	fn flush(&mut self) {
+		if let Some(pending) = self.pending.take() {
+			self.logs.push(pending.log);
+		}
+	}
+
+	fn step(
+		&mut self,
+		opcode: Opcode,
+		position: &Result<usize, ExitReason>,
+		stack: &Stack,
+		memory: &Memory,
+	) {
+		self.flush();
+
+		let pc = match position {
+			Ok(pc) => *pc,
+			Err(_) => return,
+		};
+		let mem_size = memory.effective_len().low_u64() as usize;
+
+		self.pending = Some(PendingLog {
+			log: StructLog {
+				pc,
+				op: opcode,
+				gas: 0,
+				gas_cost: 0,
+				memory: if self.config.disable_memory {
+					None
+				} else {
+					let mut data = memory.data().clone();
+					data.resize(mem_size, 0);
+					Some(data)
+				},
+				mem_size,
+				stack: if self.config.disable_stack {
+					None
+				} else {
+					Some(stack.data().clone())
+				},
+				depth: self.depth,
+				refund: 0,
+				error: None,
+			},
+			gas_limit: None,
+		});
+	}
+
+	fn record_cost(&mut self, cost: u64, snapshot: Option<Snapshot>) {
+		let (pending, snapshot) = match (self.pending.as_mut(), snapshot) {
+			(Some(pending), Some(snapshot)) => (pending, snapshot),
+			_ => return,
+		};
+
+		match pending.gas_limit {
+			// The first cost of a step is always recorded by the gasometer of
+			// the current frame.
+			None => {
+				pending.gas_limit = Some(snapshot.gas_limit);
+				pending.log.gas = snapshot.gas();
+				pending.log.refund = snapshot.refunded_gas;
+			}
+			// Costs recorded by other gasometers, like the one of a
+			// precompile sub-call, are not part of this step.
+			Some(gas_limit) => {
+				let gas_left = pending.log.gas.saturating_sub(pending.log.gas_cost);
+				if snapshot.gas_limit != gas_limit || snapshot.gas() != gas_left {
+					return;
+				}
+			}
+		}
+
+		pending.log.gas_cost = pending.log.gas_cost.saturating_add(cost);
+	}
+
+	fn exit(&mut self, reason: &ExitReason) {
+		let depth = self.depth;
+		if let Some(pending) = self.pending.as_mut() {
+			if pending.log.depth == depth {
+				if pending.log.error.is_none() {
+					pending.log.error = error_message(reason);
+				}
+				self.flush();
+			}
+		}
+		self.depth = depth.saturating_sub(1);
+	}
+}
+
+struct Listener(Rc<RefCell<State>>);
+
+impl EventListener for Listener {
+	fn event(&mut self, event: Event) {
+		let mut state = self.0.borrow_mut();
+		match event {
+			Event::Call { .. } | Event::Create { .. } => state.depth += 1,
+			Event::Exit { reason, .. } => state.exit(reason),
+			_ => (),
+		}
+	}
+}
+
+impl RuntimeEventListener for Listener {
+	fn event(&mut self, event: RuntimeEvent) {
+		if let RuntimeEvent::Step {
+			opcode,
+			position,
+			stack,
+			memory,
+			..
+		} = event
+		{
+			self.0.borrow_mut().step(opcode, position, stack, memory);
+		}
+	}
+}
+
+impl GasometerEventListener for Listener {
+	fn event(&mut self, event: GasometerEvent) {
+		let mut state = self.0.borrow_mut();
+		match event {
+			GasometerEvent::RecordCost { cost, snapshot } => state.record_cost(cost, snapshot),
+			GasometerEvent::RecordDynamicCost {
+				gas_cost,
+				memory_gas,
+				snapshot,
+				..
+			} => {
+				let memory_cost = snapshot
+					.map(|snapshot| memory_gas.saturating_sub(snapshot.memory_gas))
+					.unwrap_or(0);
+				state.record_cost(gas_cost.saturating_add(memory_cost), snapshot);
+			}
+			_ => (),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::backend::{MemoryAccount, MemoryBackend, MemoryVicinity};
+	use crate::executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata};
+	use crate::Config;
+	use alloc::collections::BTreeMap;
+	use alloc::string::ToString;
+	use primitive_types::H160;
+
+	fn trace(code: &str, gas_limit: u64) -> Vec<String> {
+		let config = Config::istanbul();
+		let vicinity = MemoryVicinity::for_tests(gas_limit);
+		let mut state = BTreeMap::new();
+		state.insert(
+			H160::repeat_byte(0x11),
+			MemoryAccount {
+				nonce: U256::one(),
+				balance: U256::zero(),
+				storage: BTreeMap::new(),
+				code: hex::decode(code).unwrap(),
+			},
+		);
+		// PUSH1 0, STOP
+		state.insert(
+			H160::from_low_u64_be(0x33),
+			MemoryAccount {
+				nonce: U256::one(),
+				balance: U256::zero(),
+				storage: BTreeMap::new(),
+				code: hex::decode("600000").unwrap(),
+			},
+		);
+		let backend = MemoryBackend::new(&vicinity, state);
+		let metadata = StackSubstateMetadata::new(gas_limit, &config);
+		let state = MemoryStackState::new(metadata, &backend);
+		let precompiles = BTreeMap::new();
+		let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+
+		let logger = StructLogger::new(StructLoggerConfig::default());
+		logger.using(|| {
+			executor.transact_call(
+				H160::repeat_byte(0x22),
+				H160::repeat_byte(0x11),
+				U256::zero(),
+				Vec::new(),
+				gas_limit,
+				Vec::new(),
+			)
+		});
+
+		logger.take_logs().iter().map(ToString::to_string).collect()
+	}
+
+	#[test]
+	fn logs_steps() {
+		// PUSH1 1, PUSH1 2, ADD, PUSH1 0, MSTORE, STOP
+		let logs = trace("600160020160005200", 100_000);
+
+		assert_eq!(logs.len(), 6);
+		assert_eq!(
+			logs[0],
+			r#"{"pc":0,"op":96,"gas":"0x13498","gasCost":"0x3","memory":"0x","memSize":0,"stack":[],"depth":1,"refund":0,"opName":"PUSH1"}"#
+		);
+		assert_eq!(
+			logs[4],
+			r#"{"pc":7,"op":82,"gas":"0x1348c","gasCost":"0x6","memory":"0x","memSize":0,"stack":["0x3","0x0"],"depth":1,"refund":0,"opName":"MSTORE"}"#
+		);
+		assert_eq!(
+			logs[5],
+			r#"{"pc":8,"op":0,"gas":"0x13486","gasCost":"0x0","memory":"0x0000000000000000000000000000000000000000000000000000000000000003","memSize":32,"stack":[],"depth":1,"refund":0,"opName":"STOP"}"#
+		);
+	}
+
+	#[test]
+	fn logs_errors() {
+		// PUSH1 1, JUMP
+		let logs = trace("600156", 100_000);
+
+		assert_eq!(logs.len(), 2);
+		assert_eq!(
+			logs[1],
+			r#"{"pc":2,"op":86,"gas":"0x13495","gasCost":"0x8","memory":"0x","memSize":0,"stack":["0x1"],"depth":1,"refund":0,"opName":"JUMP","error":"invalid jump destination"}"#
+		);
+	}
+
+	#[test]
+	fn logs_calls() {
+		// CALL(0xffff, 0x33, 0, 0, 0, 0, 0), STOP
+		let logs = trace("60006000600060006000603361fffff100", 100_000);
+
+		let depths = logs
+			.iter()
+			.map(|log| log.contains("\"depth\":2"))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			depths,
+			[false, false, false, false, false, false, false, false, true, true, false]
+		);
+		assert!(logs[7].starts_with(r#"{"pc":15,"op":241,"gas":"0x13483","gasCost":"0x102bb""#));
+		assert!(logs[8].starts_with(r#"{"pc":0,"op":96,"gas":"0xffff","gasCost":"0x3""#));
+	}
+}
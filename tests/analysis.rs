@@ -0,0 +1,79 @@
+use evm::assemble;
+use evm::gasometer::ControlFlowGraph;
+
+#[test]
+fn splits_basic_blocks() {
+	let code = assemble(
+		"
+		PUSH1 1 PUSH @a JUMPI      ; 0
+		PUSH1 2 PUSH1 3 ADD STOP   ; 6
+		PUSH1 0 PUSH1 0 SSTORE     ; 12, dead code
+		a:                         ; 17
+			PUSH1 0xff JUMP
+		b:                         ; 21
+			JUMP
+		",
+	)
+	.unwrap();
+	let graph = ControlFlowGraph::new(&code);
+
+	let starts = graph
+		.blocks
+		.iter()
+		.map(|block| block.start)
+		.collect::<Vec<_>>();
+	assert_eq!(starts, [0, 6, 12, 17, 21]);
+
+	let entry = &graph.blocks[0];
+	assert_eq!(entry.end, 6);
+	assert_eq!(entry.static_gas, 3 + 3 + 10);
+	assert!(!entry.has_dynamic_gas);
+	assert_eq!(
+		(entry.stack_inputs, entry.stack_growth, entry.stack_delta),
+		(0, 2, 0)
+	);
+	assert_eq!(entry.successors, [3, 1]);
+
+	let fallthrough = &graph.blocks[1];
+	assert_eq!(fallthrough.static_gas, 3 + 3 + 3);
+	assert_eq!(fallthrough.stack_delta, 1);
+	assert!(fallthrough.successors.is_empty());
+
+	assert!(graph.blocks[2].has_dynamic_gas);
+	assert_eq!(graph.blocks[3].static_gas, 1 + 3 + 8);
+	assert_eq!(graph.invalid_jumps, [20]);
+
+	let dynamic = &graph.blocks[4];
+	assert!(dynamic.dynamic_jump);
+	assert_eq!(
+		(
+			dynamic.stack_inputs,
+			dynamic.stack_growth,
+			dynamic.stack_delta
+		),
+		(1, 0, -1)
+	);
+
+	let unreachable = graph
+		.unreachable_blocks()
+		.map(|block| block.start)
+		.collect::<Vec<_>>();
+	assert_eq!(unreachable, [12, 21]);
+	assert_eq!(graph.block_at(17), Some(3));
+	assert_eq!(graph.block_at(18), None);
+}
+
+#[test]
+fn dynamic_jumps_reach_all_jumpdests() {
+	let code = assemble("PUSH1 0 CALLDATALOAD JUMP STOP a: STOP").unwrap();
+	let graph = ControlFlowGraph::new(&code);
+
+	assert_eq!(graph.blocks.len(), 3);
+	assert!(graph.blocks[0].dynamic_jump);
+	let reachable = graph
+		.blocks
+		.iter()
+		.map(|block| block.reachable)
+		.collect::<Vec<_>>();
+	assert_eq!(reachable, [true, false, true]);
+}
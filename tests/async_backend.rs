@@ -0,0 +1,207 @@
+use evm::backend::{
+	Apply, AsyncStateProvider, Basic, BoxFuture, Log, MemoryAccount, MemoryBackend, MemoryVicinity,
+};
+use evm::executor::stack::{execute_async, AsyncExecutionError, PrecompileFn};
+use evm::{Config, ExitReason, ExitSucceed};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+mod common;
+
+use common::vicinity;
+
+/// Future that is pending once before resolving, like an I/O request.
+struct Delayed<T>(Option<T>, bool);
+
+impl<T: Unpin> Future for Delayed<T> {
+	type Output = T;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+		if self.1 {
+			Poll::Ready(self.0.take().expect("polled after completion"))
+		} else {
+			self.1 = true;
+			cx.waker().wake_by_ref();
+			Poll::Pending
+		}
+	}
+}
+
+/// Provider counting the values fetched from an upstream memory backend.
+struct SlowProvider<'a> {
+	upstream: MemoryBackend<'a>,
+	fetched: AtomicUsize,
+}
+
+impl<'a> SlowProvider<'a> {
+	fn fetch<T: Send + Unpin + 'static>(&self, value: T) -> BoxFuture<'_, T> {
+		self.fetched.fetch_add(1, Ordering::SeqCst);
+		Box::pin(Delayed(Some(value), false))
+	}
+}
+
+impl<'a> AsyncStateProvider for SlowProvider<'a> {
+	fn basic(&self, address: H160) -> BoxFuture<'_, Basic> {
+		let basic = evm::backend::Backend::basic(&self.upstream, address);
+		self.fetch(basic)
+	}
+	fn code(&self, address: H160) -> BoxFuture<'_, Vec<u8>> {
+		let code = evm::backend::Backend::code(&self.upstream, address);
+		self.fetch(code)
+	}
+	fn storage(&self, address: H160, index: H256) -> BoxFuture<'_, H256> {
+		let value = evm::backend::Backend::storage(&self.upstream, address, index);
+		self.fetch(value)
+	}
+}
+
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+	fn wake(self: Arc<Self>) {}
+}
+
+/// Poll all futures in turn until they are all complete, returning how many
+/// times each one was pending.
+fn run_all<F: Future>(futures: Vec<F>) -> Vec<(F::Output, usize)> {
+	let mut futures = futures.into_iter().map(Box::pin).collect::<Vec<_>>();
+	let waker = Waker::from(Arc::new(NoopWaker));
+	let mut cx = Context::from_waker(&waker);
+	let mut results = futures.iter().map(|_| None).collect::<Vec<_>>();
+	let mut pending = vec![0; futures.len()];
+	while results.iter().any(Option::is_none) {
+		for (i, future) in futures.iter_mut().enumerate() {
+			if results[i].is_none() {
+				match future.as_mut().poll(&mut cx) {
+					Poll::Ready(result) => results[i] = Some(result),
+					Poll::Pending => pending[i] += 1,
+				}
+			}
+		}
+	}
+	results
+		.into_iter()
+		.map(Option::unwrap)
+		.zip(pending)
+		.collect()
+}
+
+fn assert_send<T: Send>(_: &T) {}
+
+fn provider(vicinity: &MemoryVicinity) -> SlowProvider<'_> {
+	let mut state = BTreeMap::new();
+	let mut storage = BTreeMap::new();
+	storage.insert(H256::from_low_u64_be(0), H256::from_low_u64_be(5));
+	storage.insert(H256::from_low_u64_be(5), H256::from_low_u64_be(7));
+	// SSTORE(1, SLOAD(SLOAD(0)))
+	state.insert(
+		H160::repeat_byte(0x11),
+		MemoryAccount {
+			nonce: U256::one(),
+			balance: U256::zero(),
+			storage,
+			code: hex::decode("60005454600155").unwrap(),
+		},
+	);
+	SlowProvider {
+		upstream: MemoryBackend::new(vicinity, state),
+		fetched: AtomicUsize::new(0),
+	}
+}
+
+/// Simulate a call to 0x11..11, fetching missing values at most
+/// `max_rounds` times.
+async fn simulate(
+	provider: &SlowProvider<'_>,
+	vicinity: &MemoryVicinity,
+	config: &Config,
+	precompiles: &BTreeMap<H160, PrecompileFn>,
+	max_rounds: usize,
+) -> Result<(ExitReason, Vec<Apply<Vec<(H256, H256)>>>, Vec<Log>), AsyncExecutionError> {
+	execute_async(
+		provider,
+		vicinity,
+		config,
+		precompiles,
+		100_000,
+		max_rounds,
+		|executor| {
+			executor
+				.transact_call(
+					H160::default(),
+					H160::repeat_byte(0x11),
+					U256::zero(),
+					Vec::new(),
+					100_000,
+					Vec::new(),
+				)
+				.0
+		},
+	)
+	.await
+}
+
+#[test]
+fn suspends_on_state_reads() {
+	let vicinity = vicinity();
+	let provider = provider(&vicinity);
+	let config = Config::istanbul();
+	let precompiles = BTreeMap::new();
+	let contract = H160::repeat_byte(0x11);
+
+	let simulations = (0..3)
+		.map(|_| simulate(&provider, &vicinity, &config, &precompiles, 16))
+		.collect::<Vec<_>>();
+	// Simulations can be spawned on a multi-threaded runtime.
+	assert_send(&simulations);
+
+	for (result, pending) in run_all(simulations) {
+		let (reason, values, logs) = result.unwrap();
+		assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+		assert!(logs.is_empty());
+		let storage = values
+			.into_iter()
+			.find_map(|apply| match apply {
+				Apply::Modify {
+					address, storage, ..
+				} if address == contract => Some(storage),
+				_ => None,
+			})
+			.unwrap();
+		assert_eq!(
+			storage,
+			vec![(H256::from_low_u64_be(1), H256::from_low_u64_be(7))]
+		);
+		// Each of the three dependent rounds of reads suspended the
+		// simulation once, as the values of a round are fetched together.
+		assert_eq!(pending, 3);
+	}
+	// Each simulation fetched the basic information and code of both
+	// accounts, and the two storage values.
+	assert_eq!(provider.fetched.load(Ordering::SeqCst), 3 * 6);
+}
+
+#[test]
+fn caps_fetch_rounds() {
+	let vicinity = vicinity();
+	let provider = provider(&vicinity);
+	let config = Config::istanbul();
+	let precompiles = BTreeMap::new();
+
+	// The simulation needs three rounds.
+	let simulations = vec![
+		simulate(&provider, &vicinity, &config, &precompiles, 2),
+		simulate(&provider, &vicinity, &config, &precompiles, 3),
+	];
+	let results = run_all(simulations);
+	assert_eq!(
+		results[0].0.as_ref().err(),
+		Some(&AsyncExecutionError::TooManyRounds)
+	);
+	assert!(results[1].0.is_ok());
+}
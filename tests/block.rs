@@ -0,0 +1,200 @@
+use ethereum::{
+	EIP1559Transaction, LegacyTransaction, ReceiptV3, TransactionAction, TransactionSignature,
+	TransactionV2,
+};
+use evm::backend::{MemoryAccount, MemoryBackend, MemoryVicinity};
+use evm::executor::block::{BlockEnv, BlockExecutor, InvalidTransaction};
+use evm::{Config, ExitReason, ExitSucceed};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+mod common;
+
+use common::vicinity;
+
+fn env() -> BlockEnv {
+	BlockEnv {
+		number: U256::one(),
+		coinbase: H160::repeat_byte(0xcc),
+		timestamp: U256::zero(),
+		difficulty: U256::zero(),
+		randomness: None,
+		gas_limit: U256::from(100_000),
+		base_fee_per_gas: U256::from(10),
+		blob_base_fee: U256::zero(),
+		chain_id: U256::one(),
+		block_hashes: Vec::new(),
+	}
+}
+
+fn backend(vicinity: &MemoryVicinity) -> MemoryBackend<'_> {
+	let mut state = BTreeMap::new();
+	state.insert(
+		H160::repeat_byte(0xaa),
+		MemoryAccount {
+			nonce: U256::zero(),
+			balance: U256::from(1_000_000_000u64),
+			storage: BTreeMap::new(),
+			code: Vec::new(),
+		},
+	);
+	// LOG1(0, 0, 0x42)
+	state.insert(
+		H160::repeat_byte(0x11),
+		MemoryAccount {
+			nonce: U256::one(),
+			balance: U256::zero(),
+			storage: BTreeMap::new(),
+			code: hex::decode("604260006000a100").unwrap(),
+		},
+	);
+	MemoryBackend::new(vicinity, state)
+}
+
+fn legacy(nonce: u64, gas_limit: u64, to: H160, value: u64) -> TransactionV2 {
+	TransactionV2::Legacy(LegacyTransaction {
+		nonce: U256::from(nonce),
+		gas_price: U256::from(15),
+		gas_limit: U256::from(gas_limit),
+		action: TransactionAction::Call(to),
+		value: U256::from(value),
+		input: Vec::new(),
+		signature: TransactionSignature::new(27, H256::repeat_byte(1), H256::repeat_byte(1))
+			.unwrap(),
+	})
+}
+
+fn receipt_data(receipt: &ReceiptV3) -> (u8, U256, usize, bool) {
+	match receipt {
+		ReceiptV3::Legacy(data) | ReceiptV3::EIP2930(data) | ReceiptV3::EIP1559(data) => (
+			data.status_code,
+			data.used_gas,
+			data.logs.len(),
+			data.logs_bloom != [0u8; 256].into(),
+		),
+	}
+}
+
+#[test]
+fn applies_transactions() {
+	let config = Config::london();
+	let env = env();
+	let vicinity = vicinity();
+	let mut backend = backend(&vicinity);
+	let precompiles = BTreeMap::new();
+	let mut executor = BlockExecutor::new(&env, &config, &precompiles);
+
+	let outcomes = executor
+		.apply_transactions(
+			&mut backend,
+			vec![
+				(
+					H160::repeat_byte(0xaa),
+					TransactionV2::EIP1559(EIP1559Transaction {
+						chain_id: 1,
+						nonce: U256::zero(),
+						max_priority_fee_per_gas: U256::from(2),
+						max_fee_per_gas: U256::from(20),
+						gas_limit: U256::from(50_000),
+						action: TransactionAction::Call(H160::repeat_byte(0x11)),
+						value: U256::zero(),
+						input: Vec::new(),
+						access_list: Vec::new(),
+						odd_y_parity: false,
+						r: H256::repeat_byte(1),
+						s: H256::repeat_byte(1),
+					}),
+				),
+				(
+					H160::repeat_byte(0xaa),
+					legacy(1, 21_000, H160::repeat_byte(0xbb), 1000),
+				),
+			],
+		)
+		.unwrap();
+
+	// 21000 + 3 * PUSH1 + LOG1 with one topic
+	let log_gas = 21_000 + 9 + 750;
+	assert_eq!(
+		outcomes[0].exit_reason,
+		ExitReason::Succeed(ExitSucceed::Stopped)
+	);
+	assert_eq!(outcomes[0].used_gas, U256::from(log_gas));
+	assert!(matches!(outcomes[0].receipt, ReceiptV3::EIP1559(_)));
+	assert_eq!(
+		receipt_data(&outcomes[0].receipt),
+		(1, U256::from(log_gas), 1, true)
+	);
+	assert_eq!(outcomes[1].used_gas, U256::from(21_000));
+	assert!(matches!(outcomes[1].receipt, ReceiptV3::Legacy(_)));
+	assert_eq!(
+		receipt_data(&outcomes[1].receipt),
+		(1, U256::from(log_gas + 21_000), 0, false)
+	);
+	assert_eq!(executor.gas_used(), U256::from(log_gas + 21_000));
+
+	// The sender pays the effective gas price, and the coinbase the part
+	// above the base fee.
+	let state = backend.state();
+	assert_eq!(
+		state[&H160::repeat_byte(0xaa)].balance,
+		U256::from(1_000_000_000u64 - log_gas * 12 - 21_000 * 15 - 1000)
+	);
+	assert_eq!(state[&H160::repeat_byte(0xaa)].nonce, U256::from(2));
+	assert_eq!(state[&H160::repeat_byte(0xbb)].balance, U256::from(1000));
+	assert_eq!(
+		state[&H160::repeat_byte(0xcc)].balance,
+		U256::from(log_gas * 2 + 21_000 * 5)
+	);
+}
+
+#[test]
+fn rejects_invalid_transactions() {
+	let config = Config::london();
+	let env = env();
+	let vicinity = vicinity();
+	let mut backend = backend(&vicinity);
+	let precompiles = BTreeMap::new();
+	let mut executor = BlockExecutor::new(&env, &config, &precompiles);
+	let sender = H160::repeat_byte(0xaa);
+	let to = H160::repeat_byte(0xbb);
+
+	let mut apply = |transaction| executor.apply_transaction(&mut backend, sender, transaction);
+	assert_eq!(
+		apply(legacy(1, 21_000, to, 0)),
+		Err(InvalidTransaction::NonceTooHigh)
+	);
+	assert_eq!(
+		apply(legacy(0, 20_000, to, 0)),
+		Err(InvalidTransaction::IntrinsicGasTooLow)
+	);
+	assert_eq!(
+		apply(legacy(0, 21_000, to, 1_000_000_000)),
+		Err(InvalidTransaction::InsufficientFunds)
+	);
+	assert_eq!(
+		apply(legacy(0, 100_001, to, 0)),
+		Err(InvalidTransaction::GasLimitReached)
+	);
+	assert!(apply(legacy(0, 21_000, to, 0)).is_ok());
+	assert_eq!(
+		apply(legacy(0, 21_000, to, 0)),
+		Err(InvalidTransaction::NonceTooLow)
+	);
+
+	// The first transaction stays applied, and its outcome is returned with
+	// the error.
+	let error = executor
+		.apply_transactions(
+			&mut backend,
+			vec![
+				(sender, legacy(1, 21_000, to, 0)),
+				(sender, legacy(2, 90_000, to, 0)),
+			],
+		)
+		.unwrap_err();
+	assert_eq!(error.outcomes.len(), 1);
+	assert_eq!(error.index, 1);
+	assert_eq!(error.error, InvalidTransaction::GasLimitReached);
+	assert_eq!(executor.gas_used(), U256::from(42_000));
+}
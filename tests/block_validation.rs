@@ -0,0 +1,194 @@
+use evm::backend::{MemoryAccount, MemoryBackend};
+use evm::executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata};
+use evm::gasometer::ControlFlowGraph;
+use evm::{assemble, Config, ExitError, ExitReason, ExitSucceed, StaticBlock};
+use primitive_types::{H160, U256};
+use std::collections::BTreeMap;
+
+mod common;
+
+use common::vicinity;
+
+fn run(code: &[u8], gas_limit: u64, block_validation: bool) -> (ExitReason, Vec<u8>, u64) {
+	let vicinity = vicinity();
+	let mut state = BTreeMap::new();
+	state.insert(
+		H160::repeat_byte(1),
+		MemoryAccount {
+			nonce: U256::one(),
+			balance: U256::zero(),
+			storage: BTreeMap::new(),
+			code: code.to_vec(),
+		},
+	);
+	let backend = MemoryBackend::new(&vicinity, state);
+	let config = Config::istanbul();
+	let precompiles = BTreeMap::new();
+	let metadata = StackSubstateMetadata::new(gas_limit, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+	executor.set_block_validation(block_validation);
+
+	let (reason, output) = executor.transact_call(
+		H160::default(),
+		H160::repeat_byte(1),
+		U256::zero(),
+		Vec::new(),
+		gas_limit,
+		Vec::new(),
+	);
+	(reason, output, executor.used_gas())
+}
+
+/// Run code in both modes, checking that they give the same results.
+fn run_both(code: &[u8], gas_limit: u64) -> (ExitReason, Vec<u8>, u64) {
+	let result = run(code, gas_limit, true);
+	assert_eq!(
+		result,
+		run(code, gas_limit, false),
+		"gas limit {}",
+		gas_limit
+	);
+	result
+}
+
+#[test]
+fn computes_static_blocks() {
+	let code = assemble(
+		"
+		PUSH1 1 PUSH1 2 ADD        ; 0
+		PUSH1 0 SSTORE             ; 5
+		GAS POP                    ; 8
+		a:                         ; 10
+			POP PUSH1 1 PUSH @a JUMP
+		",
+	)
+	.unwrap();
+	let blocks = ControlFlowGraph::new(&code).static_blocks(&code);
+
+	assert_eq!(
+		blocks.blocks(),
+		&[
+			StaticBlock {
+				start: 0,
+				instructions: 4,
+				gas: 12,
+				stack_inputs: 0,
+				stack_growth: 2,
+			},
+			StaticBlock {
+				start: 10,
+				instructions: 5,
+				gas: 1 + 2 + 3 + 3 + 8,
+				stack_inputs: 1,
+				stack_growth: 1,
+			},
+		]
+	);
+	assert!(blocks.get(10).is_some());
+	assert!(blocks.get(11).is_none());
+	assert!(blocks.get(1000).is_none());
+}
+
+#[test]
+fn runs_prevalidated_instructions() {
+	// A single static block using the instructions that skip their stack
+	// checks, then the results are stored one by one.
+	let code = assemble(
+		"
+		PUSH1 3 PUSH1 5 LT
+		PUSH1 3 PUSH1 5 GT
+		PUSH1 5 DUP1 EQ
+		PUSH1 6 PUSH1 3 AND
+		PUSH1 6 PUSH1 3 OR
+		PUSH1 6 PUSH1 3 XOR
+		PUSH1 0 NOT ISZERO
+		PUSH1 2 PUSH1 9 SUB
+		PUSH1 1 PUSH1 2 ADD
+		DUP9 POP SWAP2 SWAP2
+		PUSH1 0 MSTORE PUSH1 32 MSTORE PUSH1 64 MSTORE
+		PUSH1 96 MSTORE PUSH1 128 MSTORE PUSH1 160 MSTORE
+		PUSH1 192 MSTORE PUSH1 224 MSTORE PUSH 256 MSTORE
+		PUSH 288 PUSH1 0 RETURN
+		",
+	)
+	.unwrap();
+
+	let (reason, output, _) = run_both(&code, 100_000);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+	let words = output
+		.chunks(32)
+		.map(U256::from_big_endian)
+		.collect::<Vec<_>>();
+	assert_eq!(
+		words,
+		[3, 7, 0, 5, 7, 2, 1, 1, 0]
+			.iter()
+			.map(|word| U256::from(*word))
+			.collect::<Vec<_>>()
+	);
+}
+
+#[test]
+fn matches_per_instruction_validation() {
+	// Sum of the numbers from 1 to 10.
+	let code = assemble(
+		"
+		PUSH1 0         ; sum
+		PUSH1 10        ; counter
+		loop:
+			DUP1 ISZERO PUSH @end JUMPI
+			SWAP1 DUP2 ADD SWAP1
+			PUSH1 1 SWAP1 SUB
+			PUSH @loop JUMP
+		end:
+			POP PUSH 0 MSTORE
+			PUSH 32 PUSH 0 RETURN
+		",
+	)
+	.unwrap();
+
+	let (reason, output, used_gas) = run_both(&code, 100_000);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+	assert_eq!(output[31], 55);
+
+	// Run out of gas at every point of the execution.
+	for gas_limit in 21_000..used_gas {
+		let (reason, _, _) = run_both(&code, gas_limit);
+		assert_eq!(reason, ExitReason::Error(ExitError::OutOfGas));
+	}
+}
+
+#[test]
+fn reads_gas_left() {
+	let code = assemble(
+		"
+		PUSH1 1 PUSH1 2 ADD POP
+		GAS PUSH1 0 MSTORE
+		PUSH1 32 PUSH1 0 RETURN
+		",
+	)
+	.unwrap();
+
+	let (reason, output, _) = run_both(&code, 100_000);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+	assert_eq!(
+		U256::from_big_endian(&output),
+		U256::from(100_000 - 21_000 - 3 - 3 - 3 - 2 - 2)
+	);
+}
+
+#[test]
+fn checks_stack_bounds() {
+	let underflow = assemble("PUSH1 1 PUSH1 2 ADD ADD STOP").unwrap();
+	assert_eq!(
+		run_both(&underflow, 100_000).0,
+		ExitReason::Error(ExitError::StackUnderflow)
+	);
+
+	let overflow = assemble(&"PUSH1 0\n".repeat(1025)).unwrap();
+	assert_eq!(
+		run_both(&overflow, 100_000).0,
+		ExitReason::Error(ExitError::StackOverflow)
+	);
+}
@@ -0,0 +1,93 @@
+use evm::backend::{Backend, MemoryAccount, MemoryBackend};
+use evm::executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata};
+use evm::{
+	assemble, Capture, Config, Context, ExitFatal, ExitReason, ExitSucceed, Resolve, Runtime,
+};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::thread;
+
+mod common;
+
+use common::vicinity;
+
+#[test]
+fn deep_call_chain_on_small_stack() {
+	// Count the frames in slot 0, then call itself until the call depth
+	// limit makes the call fail.
+	let code = assemble(
+		"
+		PUSH1 0 SLOAD PUSH1 1 ADD PUSH1 0 SSTORE
+		PUSH1 0 PUSH1 0 PUSH1 0 PUSH1 0 PUSH1 0 ADDRESS GAS CALL
+		STOP
+		",
+	)
+	.unwrap();
+
+	let depth = thread::Builder::new()
+		.stack_size(256 * 1024)
+		.spawn(move || {
+			let vicinity = vicinity();
+			let mut state = BTreeMap::new();
+			state.insert(
+				H160::repeat_byte(1),
+				MemoryAccount {
+					nonce: U256::one(),
+					balance: U256::zero(),
+					storage: BTreeMap::new(),
+					code,
+				},
+			);
+			let backend = MemoryBackend::new(&vicinity, state);
+			let config = Config::istanbul();
+			let precompiles = BTreeMap::new();
+			let gas_limit = u64::MAX / 2;
+			let metadata = StackSubstateMetadata::new(gas_limit, &config);
+			let state = MemoryStackState::new(metadata, &backend);
+			let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+
+			let (reason, _) = executor.transact_call(
+				H160::default(),
+				H160::repeat_byte(1),
+				U256::zero(),
+				Vec::new(),
+				gas_limit,
+				Vec::new(),
+			);
+			assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+			executor.state().storage(H160::repeat_byte(1), H256::zero())
+		})
+		.unwrap()
+		.join()
+		.unwrap();
+
+	// The transaction frame and 1024 nested calls.
+	assert_eq!(depth, H256::from_low_u64_be(1025));
+}
+
+#[test]
+fn unresolved_interrupt_exits_runtime() {
+	let code = assemble("PUSH1 0 PUSH1 0 PUSH1 0 PUSH1 0 PUSH1 0 ADDRESS GAS CALL STOP").unwrap();
+	let vicinity = vicinity();
+	let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+	let config = Config::istanbul();
+	let precompiles = BTreeMap::new();
+	let metadata = StackSubstateMetadata::new(100_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+
+	let context = Context {
+		address: H160::repeat_byte(1),
+		caller: H160::default(),
+		apparent_value: U256::zero(),
+	};
+	let mut runtime = Runtime::new(Rc::new(code), Rc::new(Vec::new()), context, &config);
+	match runtime.run(&mut executor) {
+		Capture::Trap(Resolve::Call(_, resolve)) => drop(resolve),
+		_ => panic!("expected a call interrupt"),
+	}
+
+	let fatal = ExitReason::Fatal(ExitFatal::UnhandledInterrupt);
+	assert!(matches!(runtime.run(&mut executor), Capture::Exit(reason) if reason == fatal));
+}
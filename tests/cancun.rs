@@ -0,0 +1,105 @@
+use evm::backend::{Apply, MemoryAccount, MemoryBackend};
+use evm::executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata};
+use evm::{Config, ExitError, ExitReason, ExitSucceed};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+mod common;
+
+use common::vicinity;
+
+fn account(code: &str, balance: u64) -> MemoryAccount {
+	MemoryAccount {
+		nonce: U256::one(),
+		balance: U256::from(balance),
+		storage: BTreeMap::new(),
+		code: hex::decode(code).unwrap(),
+	}
+}
+
+#[test]
+fn cancun_opcodes() {
+	let mut vicinity = vicinity();
+	vicinity.block_difficulty = U256::from(2);
+	vicinity.block_randomness = Some(H256::repeat_byte(0xaa));
+	vicinity.blob_hashes = vec![H256::repeat_byte(0xbb)];
+	let mut state = BTreeMap::new();
+	// TSTORE(0, 42), MSTORE(0, TLOAD(0)), MCOPY(32, 0, 32),
+	// MSTORE(64, BLOBHASH(0)), MSTORE(96, PREVRANDAO), RETURN(0, 128)
+	state.insert(
+		H160::repeat_byte(0x11),
+		account("602a5f5d5f5c5f5260205f60205e5f496040524460605260805ff3", 0),
+	);
+	let backend = MemoryBackend::new(&vicinity, state);
+
+	let transact = |config: &Config| {
+		let metadata = StackSubstateMetadata::new(100_000, config);
+		let state = MemoryStackState::new(metadata, &backend);
+		let precompiles = BTreeMap::new();
+		let mut executor = StackExecutor::new_with_precompiles(state, config, &precompiles);
+		executor.transact_call(
+			H160::repeat_byte(0x22),
+			H160::repeat_byte(0x11),
+			U256::zero(),
+			Vec::new(),
+			100_000,
+			Vec::new(),
+		)
+	};
+
+	let (reason, output) = transact(&Config::cancun());
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+	let mut expected = vec![0u8; 128];
+	expected[31] = 42;
+	expected[63] = 42;
+	expected[64..96].copy_from_slice(&[0xbb; 32]);
+	expected[96..128].copy_from_slice(&[0xaa; 32]);
+	assert_eq!(output, expected);
+
+	// PUSH0 is not available before Shanghai.
+	let (reason, _) = transact(&Config::london());
+	assert_eq!(reason, ExitReason::Error(ExitError::OutOfGas));
+}
+
+#[test]
+fn suicide_only_in_same_tx() {
+	let vicinity = vicinity();
+	let mut state = BTreeMap::new();
+	// SUICIDE(0x33..33)
+	state.insert(
+		H160::repeat_byte(0x11),
+		account("733333333333333333333333333333333333333333ff", 10),
+	);
+	let backend = MemoryBackend::new(&vicinity, state);
+
+	let transact = |config: &Config| {
+		let metadata = StackSubstateMetadata::new(100_000, config);
+		let state = MemoryStackState::new(metadata, &backend);
+		let precompiles = BTreeMap::new();
+		let mut executor = StackExecutor::new_with_precompiles(state, config, &precompiles);
+		let (reason, _) = executor.transact_call(
+			H160::repeat_byte(0x22),
+			H160::repeat_byte(0x11),
+			U256::zero(),
+			Vec::new(),
+			100_000,
+			Vec::new(),
+		);
+		assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Suicided));
+
+		let (values, _) = executor.into_state().deconstruct();
+		values
+			.into_iter()
+			.find_map(|apply| match apply {
+				Apply::Modify { address, basic, .. } if address == H160::repeat_byte(0x11) => {
+					Some(Some(basic.balance))
+				}
+				Apply::Delete { address } if address == H160::repeat_byte(0x11) => Some(None),
+				_ => None,
+			})
+			.unwrap()
+	};
+
+	assert_eq!(transact(&Config::london()), None);
+	assert_eq!(transact(&Config::cancun()), Some(U256::zero()));
+}
@@ -0,0 +1,128 @@
+use evm::backend::{MemoryAccount, MemoryBackend};
+use evm::executor::stack::{CodeCache, MemoryStackState, StackExecutor, StackSubstateMetadata};
+use evm::{assemble, Config, ExitReason, ExitSucceed};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+mod common;
+
+use common::vicinity;
+
+#[test]
+fn shares_analyzed_code() {
+	let cache = CodeCache::new();
+	let hash = H256::repeat_byte(1);
+	let first = cache.get(hash, || vec![0x60, 0x01, 0x5b]);
+	let second = cache.get(hash, || panic!("cached code is loaded again"));
+	assert!(Rc::ptr_eq(&first.code, &second.code));
+	assert!(Rc::ptr_eq(&first.valids, &second.valids));
+	assert!(second.valids.is_valid(2));
+	assert_eq!(cache.len(), 1);
+
+	cache.clear();
+	assert!(cache.is_empty());
+}
+
+#[test]
+fn caches_code_across_executors() {
+	// Both accounts run the same code, calling themselves once.
+	let code = assemble(
+		"
+		ADDRESS SLOAD PUSH @done JUMPI
+		PUSH1 1 ADDRESS SSTORE
+		PUSH1 0 PUSH1 0 PUSH1 0 PUSH1 0 PUSH1 0 ADDRESS GAS CALL POP
+		done:
+			STOP
+		",
+	)
+	.unwrap();
+	let vicinity = vicinity();
+	let mut state = BTreeMap::new();
+	for address in [H160::repeat_byte(1), H160::repeat_byte(2)].iter() {
+		state.insert(
+			*address,
+			MemoryAccount {
+				nonce: U256::one(),
+				balance: U256::zero(),
+				storage: BTreeMap::new(),
+				code: code.clone(),
+			},
+		);
+	}
+	let backend = MemoryBackend::new(&vicinity, state);
+	let config = Config::istanbul();
+	let precompiles = BTreeMap::new();
+	let cache = CodeCache::new();
+
+	for address in [H160::repeat_byte(1), H160::repeat_byte(2)].iter() {
+		let metadata = StackSubstateMetadata::new(1_000_000, &config);
+		let state = MemoryStackState::new(metadata, &backend);
+		let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles)
+			.with_code_cache(cache.clone());
+		let (reason, _) = executor.transact_call(
+			H160::default(),
+			*address,
+			U256::zero(),
+			Vec::new(),
+			1_000_000,
+			Vec::new(),
+		);
+		assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	}
+	assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn evicts_oldest_code() {
+	let cache = CodeCache::with_capacity(2);
+	for byte in 1..=3 {
+		cache.get(H256::repeat_byte(byte), || vec![byte]);
+	}
+	assert_eq!(cache.len(), 2);
+
+	let reloaded = cache.get(H256::repeat_byte(1), || vec![0x5b]);
+	assert!(reloaded.valids.is_valid(0));
+	let kept = cache.get(H256::repeat_byte(3), || {
+		panic!("cached code is loaded again")
+	});
+	assert_eq!(*kept.code, vec![3]);
+}
+
+#[test]
+fn skips_init_code() {
+	let vicinity = vicinity();
+	let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+	let config = Config::istanbul();
+	let precompiles = BTreeMap::new();
+	let cache = CodeCache::new();
+
+	let metadata = StackSubstateMetadata::new(1_000_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles)
+		.with_code_cache(cache.clone());
+	let init_code = assemble("PUSH1 0 PUSH1 0 RETURN").unwrap();
+	let reason = executor.transact_create(
+		H160::default(),
+		U256::zero(),
+		init_code,
+		1_000_000,
+		Vec::new(),
+	);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+	assert!(cache.is_empty());
+}
+
+#[test]
+fn executor_without_cache_is_send() {
+	fn assert_send<T: Send>(_: &T) {}
+
+	let vicinity = vicinity();
+	let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+	let config = Config::istanbul();
+	let precompiles = BTreeMap::new();
+	let metadata = StackSubstateMetadata::new(1_000_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+	assert_send(&executor);
+}
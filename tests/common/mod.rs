@@ -0,0 +1,22 @@
+use evm::backend::MemoryVicinity;
+use primitive_types::{H160, U256};
+
+/// Vicinity of a block on chain 1, with a gas limit of 10 million and
+/// everything else zero.
+pub fn vicinity() -> MemoryVicinity {
+	MemoryVicinity {
+		gas_price: U256::zero(),
+		origin: H160::default(),
+		chain_id: U256::one(),
+		block_hashes: Vec::new(),
+		block_number: U256::zero(),
+		block_coinbase: H160::default(),
+		block_timestamp: U256::zero(),
+		block_difficulty: U256::zero(),
+		block_gas_limit: U256::from(10_000_000),
+		block_base_fee_per_gas: U256::zero(),
+		block_randomness: None,
+		block_blob_base_fee: U256::zero(),
+		blob_hashes: Vec::new(),
+	}
+}
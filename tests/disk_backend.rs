@@ -0,0 +1,219 @@
+use evm::backend::{Apply, ApplyBackend, Backend, Basic, DiskBackend, FileStore};
+use evm::executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata};
+use evm::{Config, ExitReason, ExitSucceed};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+mod common;
+
+use common::vicinity;
+
+fn store_path(name: &str) -> PathBuf {
+	let path =
+		std::env::temp_dir().join(format!("evm-disk-backend-{}-{}", name, std::process::id()));
+	let _ = fs::remove_dir_all(&path);
+	path
+}
+
+fn modify(
+	address: H160,
+	balance: u64,
+	code: Option<Vec<u8>>,
+	storage: Vec<(H256, H256)>,
+	reset_storage: bool,
+) -> Apply<Vec<(H256, H256)>> {
+	Apply::Modify {
+		address,
+		basic: Basic {
+			balance: U256::from(balance),
+			nonce: U256::one(),
+		},
+		code,
+		storage,
+		reset_storage,
+	}
+}
+
+#[test]
+fn persists_state() {
+	let vicinity = vicinity();
+	let path = store_path("persists");
+	let address = H160::repeat_byte(0xaa);
+	let other = H160::repeat_byte(0xbb);
+
+	let mut backend = DiskBackend::new(&vicinity, FileStore::open(&path).unwrap());
+	backend.apply(
+		vec![
+			modify(
+				address,
+				100,
+				Some(vec![0x00]),
+				vec![(H256::repeat_byte(1), H256::repeat_byte(2))],
+				false,
+			),
+			modify(other, 5, Some(vec![0x00]), Vec::new(), false),
+		],
+		Vec::new(),
+		true,
+	);
+	drop(backend);
+
+	// A new backend on the same directory sees the applied state.
+	let mut backend = DiskBackend::new(&vicinity, FileStore::open(&path).unwrap());
+	assert!(backend.exists(address));
+	assert_eq!(backend.basic(address).balance, U256::from(100));
+	assert_eq!(backend.code(address), vec![0x00]);
+	assert_eq!(backend.code(other), vec![0x00]);
+	assert_eq!(
+		backend.storage(address, H256::repeat_byte(1)),
+		H256::repeat_byte(2)
+	);
+
+	// Storage wipes hide earlier slots, and deletes remove the account.
+	backend.apply(
+		vec![
+			modify(
+				address,
+				50,
+				None,
+				vec![(H256::repeat_byte(3), H256::repeat_byte(4))],
+				true,
+			),
+			Apply::Delete { address: other },
+		],
+		Vec::new(),
+		true,
+	);
+	assert_eq!(backend.basic(address).balance, U256::from(50));
+	assert_eq!(backend.code(address), vec![0x00]);
+	assert_eq!(
+		backend.storage(address, H256::repeat_byte(1)),
+		H256::default()
+	);
+	assert_eq!(
+		backend.storage(address, H256::repeat_byte(3)),
+		H256::repeat_byte(4)
+	);
+	assert!(!backend.exists(other));
+	assert!(backend.code(other).is_empty());
+
+	// A recreated account does not see the storage of the deleted one.
+	backend.apply(
+		vec![Apply::Delete::<Vec<(H256, H256)>> { address }],
+		Vec::new(),
+		true,
+	);
+	backend.apply(
+		vec![modify(address, 1, Some(Vec::new()), Vec::new(), false)],
+		Vec::new(),
+		true,
+	);
+	assert!(backend.code(address).is_empty());
+	assert_eq!(
+		backend.storage(address, H256::repeat_byte(3)),
+		H256::default()
+	);
+
+	// An unfinished batch is discarded when the store is opened.
+	fs::write(path.join("journal.tmp"), [0xff; 3]).unwrap();
+	let backend = DiskBackend::new(&vicinity, FileStore::open(&path).unwrap());
+	assert_eq!(backend.basic(address).balance, U256::one());
+	assert!(!path.join("journal.tmp").exists());
+
+	fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn runs_transactions() {
+	let config = Config::istanbul();
+	let vicinity = vicinity();
+	let path = store_path("transactions");
+	let contract = H160::repeat_byte(0x11);
+
+	// SSTORE(0, 0x42)
+	let mut backend = DiskBackend::new(&vicinity, FileStore::open(&path).unwrap());
+	backend.apply(
+		vec![modify(
+			contract,
+			0,
+			Some(hex::decode("604260005500").unwrap()),
+			Vec::new(),
+			false,
+		)],
+		Vec::new(),
+		true,
+	);
+
+	let (reason, values) = {
+		let metadata = StackSubstateMetadata::new(100_000, &config);
+		let state = MemoryStackState::new(metadata, &backend);
+		let precompiles = BTreeMap::new();
+		let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+		let (reason, _) = executor.transact_call(
+			H160::default(),
+			contract,
+			U256::zero(),
+			Vec::new(),
+			100_000,
+			Vec::new(),
+		);
+		let (values, _) = executor.into_state().deconstruct();
+		let values = values
+			.into_iter()
+			.map(|apply| match apply {
+				Apply::Modify {
+					address,
+					basic,
+					code,
+					storage,
+					reset_storage,
+				} => Apply::Modify {
+					address,
+					basic,
+					code,
+					storage: storage.into_iter().collect::<Vec<_>>(),
+					reset_storage,
+				},
+				Apply::Delete { address } => Apply::Delete { address },
+			})
+			.collect::<Vec<_>>();
+		(reason, values)
+	};
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	backend.apply(values, Vec::new(), true);
+
+	let backend = DiskBackend::new(&vicinity, FileStore::open(&path).unwrap());
+	assert_eq!(
+		backend.storage(contract, H256::default()),
+		H256::from_low_u64_be(0x42)
+	);
+	assert_eq!(backend.code(contract), hex::decode("604260005500").unwrap());
+	assert_eq!(backend.basic(contract).nonce, U256::one());
+
+	fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn reports_corrupted_records() {
+	let vicinity = vicinity();
+	let address = H160::repeat_byte(0xaa);
+
+	// An account record is truncated, then a storage generation.
+	let corrupted = [
+		[&b"a"[..], address.as_bytes()].concat(),
+		[&b"g"[..], address.as_bytes()].concat(),
+	];
+	for key in corrupted.iter() {
+		let mut store = BTreeMap::new();
+		store.insert(key.clone(), vec![0x01]);
+		let mut backend = DiskBackend::new(&vicinity, store);
+
+		let error = backend
+			.try_apply(vec![modify(address, 1, None, Vec::new(), false)], true)
+			.unwrap_err();
+		assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+		assert_eq!(backend.into_store().len(), 1);
+	}
+}
@@ -0,0 +1,211 @@
+use evm::backend::{Backend, MemoryAccount, MemoryBackend};
+use evm::executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata};
+use evm::{
+	Config, CreateScheme, EofContainer, EofError, EofTypeSection, ExitError, ExitReason,
+	ExitSucceed, Opcode,
+};
+use primitive_types::{H160, U256};
+use std::collections::BTreeMap;
+
+mod common;
+
+use common::vicinity;
+
+/// Container with two code sections and a two bytes data section. The
+/// first section calls the second one to add 2 and 3, stores the result,
+/// jumps over an `INVALID` and returns the stored word.
+fn container(max_stack_height: &str) -> Vec<u8> {
+	hex::decode(
+		[
+			// Header: types, two code sections, data.
+			"ef0001",
+			"010008",
+			"0200020015",
+			"0002",
+			"040002",
+			"00",
+			// Types.
+			"00000002",
+			"0201",
+			max_stack_height,
+			// PUSH1 2, PUSH1 3, CALLF 1, PUSH1 0, MSTORE, PUSH1 1, RJUMPI 1,
+			// INVALID, PUSH1 32, PUSH1 0, RETURN
+			"60026003e300016000526001e10001fe60206000f3",
+			// ADD, RETF
+			"01e4",
+			// Data.
+			"aabb",
+		]
+		.concat(),
+	)
+	.unwrap()
+}
+
+/// Init code returning the 50 bytes that follow it.
+fn init_code(container: Vec<u8>) -> Vec<u8> {
+	let mut code = hex::decode("6032600c60003960326000f3").unwrap();
+	code.extend(container);
+	code
+}
+
+#[test]
+fn validates_containers() {
+	let code = container("0002");
+	let eof = EofContainer::validate(&code).unwrap();
+	assert_eq!(
+		eof.types,
+		vec![
+			EofTypeSection {
+				inputs: 0,
+				outputs: 0,
+				max_stack_height: 2,
+			},
+			EofTypeSection {
+				inputs: 2,
+				outputs: 1,
+				max_stack_height: 2,
+			},
+		]
+	);
+	assert_eq!(eof.code, vec![25..46, 46..48]);
+	assert_eq!(&code[eof.data], &[0xaa, 0xbb]);
+
+	assert_eq!(
+		EofContainer::validate(&container("0003")),
+		Err(EofError::MaxStackHeightMismatch)
+	);
+	assert_eq!(
+		EofContainer::validate(&code[..20]),
+		Err(EofError::InvalidContainerSize)
+	);
+	assert_eq!(
+		EofContainer::validate(&code[..10]),
+		Err(EofError::InvalidHeader)
+	);
+
+	let invalid = |section: &str| {
+		let size = section.len() / 2;
+		let code = hex::decode(format!(
+			"ef000101000402000100{:02x}0400000000000001{}",
+			size, section
+		))
+		.unwrap();
+		EofContainer::validate(&code)
+	};
+	// PUSH1 0, JUMP
+	assert_eq!(
+		invalid("600056"),
+		Err(EofError::UndefinedInstruction(Opcode::JUMP))
+	);
+	// PUSH1
+	assert_eq!(invalid("60"), Err(EofError::TruncatedImmediate));
+	// RJUMP into its own immediate
+	assert_eq!(invalid("e0fffe"), Err(EofError::InvalidJumpDestination));
+	// PUSH1 0
+	assert_eq!(
+		invalid("6000"),
+		Err(EofError::MissingTerminatingInstruction)
+	);
+	// PUSH1 0, STOP, STOP
+	assert_eq!(invalid("60000000"), Err(EofError::UnreachableCode));
+	// PUSH1 0, RJUMP -5
+	assert_eq!(invalid("6000e0fffb"), Err(EofError::StackHeightMismatch));
+	// POP, STOP
+	assert_eq!(invalid("5000"), Err(EofError::StackUnderflow));
+	// CALLF 1
+	assert_eq!(invalid("e30001"), Err(EofError::InvalidCodeSection));
+}
+
+/// Call an account with the given code.
+fn call(config: &Config, code: Vec<u8>) -> (ExitReason, Vec<u8>) {
+	let vicinity = vicinity();
+	let mut state = BTreeMap::new();
+	state.insert(
+		H160::repeat_byte(0x11),
+		MemoryAccount {
+			nonce: U256::one(),
+			balance: U256::zero(),
+			storage: BTreeMap::new(),
+			code,
+		},
+	);
+	let backend = MemoryBackend::new(&vicinity, state);
+
+	let metadata = StackSubstateMetadata::new(100_000, config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, config, &precompiles);
+	executor.transact_call(
+		H160::repeat_byte(0x22),
+		H160::repeat_byte(0x11),
+		U256::zero(),
+		Vec::new(),
+		100_000,
+		Vec::new(),
+	)
+}
+
+#[test]
+fn runs_containers() {
+	let (reason, output) = call(&Config::cancun_with_eof(), container("0002"));
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+	assert_eq!(U256::from_big_endian(&output), U256::from(5));
+
+	// Before EOF, the container is legacy code starting with an undefined
+	// opcode.
+	let (reason, _) = call(&Config::cancun(), container("0002"));
+	assert_eq!(reason, ExitReason::Error(ExitError::OutOfGas));
+}
+
+#[test]
+fn runs_unvalidated_containers() {
+	// Containers are only validated when they are deployed, so one placed
+	// in the state directly can call a code section that does not exist or
+	// end with a truncated immediate.
+	let header = "ef0001010004020001000304000000";
+	for code in ["e30005", "5fe000"].iter() {
+		let container = hex::decode([header, "00000000", code].concat()).unwrap();
+		assert!(EofContainer::validate(&container).is_err());
+		let (reason, _) = call(&Config::cancun_with_eof(), container);
+		assert_eq!(reason, ExitReason::Error(ExitError::InvalidCode));
+	}
+}
+
+#[test]
+fn deploys_valid_containers() {
+	let vicinity = vicinity();
+	let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+
+	let transact = |config: &Config, init_code: Vec<u8>| {
+		let metadata = StackSubstateMetadata::new(1_000_000, config);
+		let state = MemoryStackState::new(metadata, &backend);
+		let precompiles = BTreeMap::new();
+		let mut executor = StackExecutor::new_with_precompiles(state, config, &precompiles);
+		let caller = H160::repeat_byte(0x22);
+		let address = executor.create_address(CreateScheme::Legacy { caller });
+		let reason =
+			executor.transact_create(caller, U256::zero(), init_code, 1_000_000, Vec::new());
+		(reason, executor.state().code(address))
+	};
+
+	let (reason, code) = transact(&Config::cancun_with_eof(), init_code(container("0002")));
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+	assert_eq!(code, container("0002"));
+
+	let (reason, code) = transact(&Config::cancun_with_eof(), init_code(container("0003")));
+	assert_eq!(reason, ExitReason::Error(ExitError::InvalidCode));
+	assert!(code.is_empty());
+
+	let (reason, _) = transact(&Config::cancun(), init_code(container("0002")));
+	assert_eq!(reason, ExitReason::Error(ExitError::InvalidCode));
+
+	// PUSH0, PUSH0, RETURN would return nothing, but the container declares
+	// a max stack height of zero, so it is rejected before it runs.
+	let initcode = hex::decode("ef0001010004020001000304000000000000005f5ff3").unwrap();
+	assert_eq!(
+		EofContainer::validate(&initcode),
+		Err(EofError::MaxStackHeightMismatch)
+	);
+	let (reason, _) = transact(&Config::cancun_with_eof(), initcode);
+	assert_eq!(reason, ExitReason::Error(ExitError::InvalidCode));
+}
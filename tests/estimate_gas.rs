@@ -0,0 +1,75 @@
+use evm::backend::{MemoryAccount, MemoryBackend};
+use evm::executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata};
+use evm::{Config, ExitReason, ExitSucceed};
+use primitive_types::{H160, U256};
+use std::collections::BTreeMap;
+
+mod common;
+
+use common::vicinity;
+
+fn account(code: &str) -> MemoryAccount {
+	MemoryAccount {
+		nonce: U256::one(),
+		balance: U256::zero(),
+		storage: BTreeMap::new(),
+		code: hex::decode(code).unwrap(),
+	}
+}
+
+#[test]
+fn estimates_nested_call() {
+	let config = Config::berlin();
+	let vicinity = vicinity();
+	let mut state = BTreeMap::new();
+	// Reverts unless CALL(GAS, 0x22..22) succeeds.
+	state.insert(
+		H160::repeat_byte(0x11),
+		account(
+			"600060006000600060007322222222222222222222222222222222222222225af115602657005b600080fd",
+		),
+	);
+	// SSTORE(0, 1)
+	state.insert(H160::repeat_byte(0x22), account("600160005500"));
+	let backend = MemoryBackend::new(&vicinity, state);
+	let precompiles = BTreeMap::new();
+
+	let transact = |gas_limit| {
+		let metadata = StackSubstateMetadata::new(gas_limit, &config);
+		let state = MemoryStackState::new(metadata, &backend);
+		let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+		let (reason, _) = executor.transact_call(
+			H160::repeat_byte(0x33),
+			H160::repeat_byte(0x11),
+			U256::zero(),
+			Vec::new(),
+			gas_limit,
+			Vec::new(),
+		);
+		(reason, executor.used_gas())
+	};
+
+	let metadata = StackSubstateMetadata::new(10_000_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+	let estimate = executor.estimate_gas(
+		H160::repeat_byte(0x33),
+		Some(H160::repeat_byte(0x11)),
+		U256::zero(),
+		Vec::new(),
+		Vec::new(),
+	);
+	assert_eq!(
+		estimate.exit_reason,
+		ExitReason::Succeed(ExitSucceed::Stopped)
+	);
+	// The estimation does not change the state of the executor.
+	assert_eq!(executor.used_gas(), 0);
+	assert_eq!(executor.nonce(H160::repeat_byte(0x33)), U256::zero());
+
+	let (reason, used_gas) = transact(estimate.gas_limit);
+	assert!(reason.is_succeed());
+	// The 63/64 rule requires more gas than is used.
+	assert!(estimate.gas_limit > used_gas);
+	assert!(!transact(estimate.gas_limit - 1).0.is_succeed());
+}
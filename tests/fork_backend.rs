@@ -0,0 +1,201 @@
+use evm::backend::{
+	Apply, ApplyBackend, Backend, Basic, ForkBackend, MemoryAccount, MemoryBackend, MemoryVicinity,
+	StateProvider,
+};
+use evm::executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata};
+use evm::{Config, ExitReason, ExitSucceed};
+use primitive_types::{H160, H256, U256};
+use std::cell::Cell;
+use std::collections::BTreeMap;
+
+mod common;
+
+/// Shared test vicinity at a block number, which knows the hash of the
+/// previous block.
+fn at_block(block_number: u64) -> MemoryVicinity {
+	let mut vicinity = common::vicinity();
+	vicinity.block_hashes = vec![H256::repeat_byte(0xfe)];
+	vicinity.block_number = U256::from(block_number);
+	vicinity
+}
+
+/// Provider counting the values fetched from an upstream memory backend.
+struct CountingProvider<'a> {
+	upstream: MemoryBackend<'a>,
+	fetched: Cell<usize>,
+}
+
+impl<'a> StateProvider for CountingProvider<'a> {
+	fn basic(&self, address: H160) -> Basic {
+		self.fetched.set(self.fetched.get() + 1);
+		StateProvider::basic(&self.upstream, address)
+	}
+	fn code(&self, address: H160) -> Vec<u8> {
+		self.fetched.set(self.fetched.get() + 1);
+		StateProvider::code(&self.upstream, address)
+	}
+	fn storage(&self, address: H160, index: H256) -> H256 {
+		self.fetched.set(self.fetched.get() + 1);
+		StateProvider::storage(&self.upstream, address, index)
+	}
+	fn block_hash(&self, number: U256) -> H256 {
+		self.fetched.set(self.fetched.get() + 1);
+		H256::from_low_u64_be(number.as_u64())
+	}
+}
+
+fn upstream(vicinity: &MemoryVicinity) -> CountingProvider<'_> {
+	let mut state = BTreeMap::new();
+	let mut storage = BTreeMap::new();
+	storage.insert(H256::repeat_byte(1), H256::repeat_byte(2));
+	storage.insert(H256::repeat_byte(3), H256::repeat_byte(4));
+	// SSTORE(1, SLOAD(3))
+	state.insert(
+		H160::repeat_byte(0x11),
+		MemoryAccount {
+			nonce: U256::one(),
+			balance: U256::from(100),
+			storage,
+			code: hex::decode(
+				"7f0303030303030303030303030303030303030303030303030303030303030303547f010101010101010101010101010101010101010101010101010101010101010155",
+			)
+			.unwrap(),
+		},
+	);
+	CountingProvider {
+		upstream: MemoryBackend::new(vicinity, state),
+		fetched: Cell::new(0),
+	}
+}
+
+#[test]
+fn caches_upstream_state() {
+	let upstream_vicinity = at_block(10);
+	let vicinity = at_block(20);
+	let backend = ForkBackend::new(&vicinity, upstream(&upstream_vicinity));
+	let contract = H160::repeat_byte(0x11);
+
+	for _ in 0..2 {
+		assert!(backend.exists(contract));
+		assert_eq!(backend.basic(contract).balance, U256::from(100));
+		assert_eq!(backend.code(contract).len(), 68);
+		assert_eq!(
+			backend.storage(contract, H256::repeat_byte(1)),
+			H256::repeat_byte(2)
+		);
+		assert_eq!(backend.block_hash(U256::from(19)), H256::repeat_byte(0xfe));
+		assert_eq!(backend.block_hash(U256::from(5)), H256::from_low_u64_be(5));
+	}
+	// `exists` fetches the basic information, then each value is fetched
+	// once.
+	assert_eq!(backend.provider().fetched.get(), 5);
+}
+
+#[test]
+fn skips_block_hashes_out_of_range() {
+	let upstream_vicinity = at_block(10);
+	let vicinity = at_block(300);
+	let backend = ForkBackend::new(&vicinity, upstream(&upstream_vicinity));
+
+	// Only the 256 most recent blocks have a hash.
+	assert_eq!(
+		backend.block_hash(U256::from(44)),
+		H256::from_low_u64_be(44)
+	);
+	assert_eq!(backend.block_hash(U256::from(43)), H256::default());
+	assert_eq!(backend.block_hash(U256::from(300)), H256::default());
+	assert_eq!(backend.provider().fetched.get(), 1);
+}
+
+#[test]
+fn applies_local_changes() {
+	let upstream_vicinity = at_block(10);
+	let vicinity = at_block(20);
+	let mut backend = ForkBackend::new(&vicinity, upstream(&upstream_vicinity));
+	let contract = H160::repeat_byte(0x11);
+
+	let (reason, values) = {
+		let config = Config::istanbul();
+		let metadata = StackSubstateMetadata::new(100_000, &config);
+		let state = MemoryStackState::new(metadata, &backend);
+		let precompiles = BTreeMap::new();
+		let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+		let (reason, _) = executor.transact_call(
+			H160::default(),
+			contract,
+			U256::zero(),
+			Vec::new(),
+			100_000,
+			Vec::new(),
+		);
+		let (values, _) = executor.into_state().deconstruct();
+		let values = values
+			.into_iter()
+			.map(|apply| match apply {
+				Apply::Modify {
+					address,
+					basic,
+					code,
+					storage,
+					reset_storage,
+				} => Apply::Modify {
+					address,
+					basic,
+					code,
+					storage: storage.into_iter().collect::<Vec<_>>(),
+					reset_storage,
+				},
+				Apply::Delete { address } => Apply::Delete { address },
+			})
+			.collect::<Vec<_>>();
+		(reason, values)
+	};
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	backend.apply(values, Vec::new(), true);
+
+	assert_eq!(
+		backend.storage(contract, H256::repeat_byte(1)),
+		H256::repeat_byte(4)
+	);
+	assert_eq!(
+		backend.storage(contract, H256::repeat_byte(3)),
+		H256::repeat_byte(4)
+	);
+
+	// Local zero values and deletions hide the upstream state.
+	backend.apply(
+		vec![Apply::Modify {
+			address: contract,
+			basic: backend.basic(contract),
+			code: None,
+			storage: vec![(H256::repeat_byte(3), H256::default())],
+			reset_storage: false,
+		}],
+		Vec::new(),
+		true,
+	);
+	assert_eq!(
+		backend.storage(contract, H256::repeat_byte(3)),
+		H256::default()
+	);
+	assert_eq!(backend.code(contract).len(), 68);
+
+	backend.apply(
+		vec![Apply::Delete::<Vec<(H256, H256)>> { address: contract }],
+		Vec::new(),
+		true,
+	);
+	assert!(!backend.exists(contract));
+	assert_eq!(backend.basic(contract), Basic::default());
+	assert!(backend.code(contract).is_empty());
+	assert_eq!(
+		backend.storage(contract, H256::repeat_byte(1)),
+		H256::default()
+	);
+
+	// The upstream state is left unchanged.
+	assert_eq!(
+		StateProvider::storage(backend.provider(), contract, H256::repeat_byte(1)),
+		H256::repeat_byte(2)
+	);
+}
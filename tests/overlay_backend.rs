@@ -0,0 +1,148 @@
+use evm::backend::{
+	Apply, ApplyBackend, Backend, Basic, MemoryAccount, MemoryBackend, MemoryVicinity,
+	OverlayBackend,
+};
+use evm::executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata};
+use evm::{Config, ExitReason, ExitSucceed};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+mod common;
+
+use common::vicinity;
+
+fn contract() -> H160 {
+	H160::repeat_byte(0x11)
+}
+
+fn backend(vicinity: &MemoryVicinity) -> MemoryBackend<'_> {
+	let mut state = BTreeMap::new();
+	let mut storage = BTreeMap::new();
+	storage.insert(H256::default(), H256::from_low_u64_be(1));
+	// SSTORE(0, SLOAD(0) + 1)
+	state.insert(
+		contract(),
+		MemoryAccount {
+			nonce: U256::one(),
+			balance: U256::zero(),
+			storage,
+			code: hex::decode("600160005401600055").unwrap(),
+		},
+	);
+	MemoryBackend::new(vicinity, state)
+}
+
+/// Call the contract, incrementing its counter.
+fn increment<B: Backend + ApplyBackend>(backend: &mut B) {
+	let config = Config::istanbul();
+	let (reason, values) = {
+		let metadata = StackSubstateMetadata::new(100_000, &config);
+		let state = MemoryStackState::new(metadata, &*backend);
+		let precompiles = BTreeMap::new();
+		let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+		let (reason, _) = executor.transact_call(
+			H160::default(),
+			contract(),
+			U256::zero(),
+			Vec::new(),
+			100_000,
+			Vec::new(),
+		);
+		let (values, _) = executor.into_state().deconstruct();
+		let values = values
+			.into_iter()
+			.map(|apply| match apply {
+				Apply::Modify {
+					address,
+					basic,
+					code,
+					storage,
+					reset_storage,
+				} => Apply::Modify {
+					address,
+					basic,
+					code,
+					storage: storage.into_iter().collect::<Vec<_>>(),
+					reset_storage,
+				},
+				Apply::Delete { address } => Apply::Delete { address },
+			})
+			.collect::<Vec<_>>();
+		(reason, values)
+	};
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	backend.apply(values, Vec::new(), true);
+}
+
+fn counter<B: Backend>(backend: &B) -> H256 {
+	backend.storage(contract(), H256::default())
+}
+
+#[test]
+fn reverts_to_checkpoints() {
+	let vicinity = vicinity();
+	let mut overlay = OverlayBackend::new(backend(&vicinity));
+
+	increment(&mut overlay);
+	assert_eq!(counter(&overlay), H256::from_low_u64_be(2));
+
+	let checkpoint = overlay.checkpoint();
+	increment(&mut overlay);
+	overlay.checkpoint();
+	increment(&mut overlay);
+	assert_eq!(counter(&overlay), H256::from_low_u64_be(4));
+
+	overlay.revert_to(checkpoint);
+	assert_eq!(counter(&overlay), H256::from_low_u64_be(2));
+	assert_eq!(counter(overlay.inner()), H256::from_low_u64_be(1));
+
+	overlay.checkpoint();
+	increment(&mut overlay);
+	overlay.commit();
+	assert_eq!(counter(overlay.inner()), H256::from_low_u64_be(3));
+	assert_eq!(counter(&overlay), H256::from_low_u64_be(3));
+}
+
+#[test]
+fn deletes_and_recreates_accounts() {
+	let vicinity = vicinity();
+	let mut overlay = OverlayBackend::new(backend(&vicinity));
+
+	overlay.checkpoint();
+	overlay.apply(
+		vec![Apply::Delete::<Vec<(H256, H256)>> {
+			address: contract(),
+		}],
+		Vec::new(),
+		true,
+	);
+	assert!(!overlay.exists(contract()));
+	assert!(overlay.code(contract()).is_empty());
+
+	// A recreated account starts with empty code and storage.
+	overlay.checkpoint();
+	overlay.apply(
+		vec![Apply::Modify {
+			address: contract(),
+			basic: Basic {
+				balance: U256::from(10),
+				nonce: U256::zero(),
+			},
+			code: None,
+			storage: vec![(H256::repeat_byte(1), H256::repeat_byte(2))],
+			reset_storage: false,
+		}],
+		Vec::new(),
+		true,
+	);
+	assert!(overlay.exists(contract()));
+	assert!(overlay.code(contract()).is_empty());
+	assert_eq!(counter(&overlay), H256::default());
+
+	overlay.commit();
+	let account = &overlay.inner().state()[&contract()];
+	assert_eq!(account.balance, U256::from(10));
+	assert!(account.code.is_empty());
+	assert_eq!(account.storage.len(), 1);
+	assert_eq!(account.storage[&H256::repeat_byte(1)], H256::repeat_byte(2));
+}
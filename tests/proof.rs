@@ -0,0 +1,81 @@
+use evm::backend::{trie_proof, trie_root, verify_proof, MemoryAccount, MemoryBackend, ProofError};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+mod common;
+
+use common::vicinity;
+
+#[test]
+fn proves_trie_entries() {
+	let entries = vec![
+		(&b"doe"[..], &b"reindeer"[..]),
+		(&b"dog"[..], &b"puppy"[..]),
+		(&b"dogglesworth"[..], &b"cat"[..]),
+	];
+	let root = trie_root(entries.clone());
+
+	for (key, value) in &entries {
+		let proof = trie_proof(entries.clone(), key);
+		assert_eq!(verify_proof(root, key, &proof), Ok(Some(value.to_vec())));
+	}
+	for key in [&b"do"[..], &b"dogs"[..], &b"cat"[..]].iter() {
+		let proof = trie_proof(entries.clone(), key);
+		assert_eq!(verify_proof(root, key, &proof), Ok(None));
+	}
+	assert_eq!(
+		verify_proof(root, b"dog", &[]),
+		Err(ProofError::MissingNode)
+	);
+}
+
+#[test]
+fn proves_accounts_and_storage() {
+	let vicinity = vicinity();
+	let mut state = BTreeMap::new();
+	for i in 1..=50u64 {
+		let storage = (1..=i)
+			.map(|j| (H256::from_low_u64_be(j), H256::from_low_u64_be(i * j)))
+			.collect();
+		state.insert(
+			H160::from_low_u64_be(i),
+			MemoryAccount {
+				nonce: U256::from(i),
+				balance: U256::from(i * 1000),
+				storage,
+				code: vec![i as u8],
+			},
+		);
+	}
+	let backend = MemoryBackend::new(&vicinity, state);
+	let root = backend.state_root();
+
+	for i in [1u64, 17, 50].iter() {
+		let address = H160::from_low_u64_be(*i);
+		let slots = [H256::from_low_u64_be(1), H256::from_low_u64_be(100)];
+		let proof = backend.proof(address, &slots);
+		assert_eq!(proof.nonce, U256::from(*i));
+		assert_eq!(proof.storage_hash, backend.state()[&address].storage_root());
+		assert_eq!(proof.storage_proof[0].value, U256::from(*i));
+		assert_eq!(proof.storage_proof[1].value, U256::zero());
+		assert_eq!(proof.verify(root), Ok(()));
+
+		let mut tampered = proof.clone();
+		tampered.balance += U256::one();
+		assert_eq!(tampered.verify(root), Err(ProofError::ValueMismatch));
+
+		let mut tampered = proof.clone();
+		tampered.storage_proof[0].value = U256::from(7);
+		assert_eq!(tampered.verify(root), Err(ProofError::ValueMismatch));
+
+		let mut tampered = proof;
+		let last = tampered.account_proof.len() - 1;
+		tampered.account_proof[last][3] ^= 1;
+		assert_eq!(tampered.verify(root), Err(ProofError::HashMismatch));
+	}
+
+	// Absent accounts are proven with empty values.
+	let proof = backend.proof(H160::repeat_byte(0xff), &[H256::default()]);
+	assert_eq!(proof.balance, U256::zero());
+	assert_eq!(proof.verify(root), Ok(()));
+}
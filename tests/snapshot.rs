@@ -0,0 +1,208 @@
+use evm::backend::{Backend, MemoryAccount, MemoryBackend, MemoryVicinity};
+use evm::executor::stack::{
+	ExecutionLimit, MemoryStackState, SnapshotError, StackExecutionSnapshot, StackExecutor,
+	StackSubstateMetadata,
+};
+use evm::{Capture, Config, ExitReason, ExitSucceed};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+mod common;
+
+use common::vicinity;
+
+fn caller() -> H160 {
+	H160::repeat_byte(1)
+}
+
+fn callee() -> H160 {
+	H160::repeat_byte(2)
+}
+
+fn backend(vicinity: &MemoryVicinity) -> MemoryBackend<'_> {
+	// The caller stores the success of its call in slot 1 and the returned
+	// value in slot 0. The callee stores 1 in slot 0 and returns 42.
+	// CALL(GAS, 0x02..02, 0, 0, 0, 0, 32), SSTORE(1, success),
+	// SSTORE(0, MLOAD(0)), STOP
+	let caller_code = hex::decode(
+		"6020600060006000600073\
+		 02020202020202020202020202020202020202025af1\
+		 600155600051600055\
+		 00"
+		.replace(' ', ""),
+	)
+	.unwrap();
+	// SSTORE(0, 1), MSTORE(0, 42), RETURN(0, 32)
+	let callee_code = hex::decode("6001600055602a60005260206000f3").unwrap();
+
+	let mut state = BTreeMap::new();
+	for (address, code) in [(caller(), caller_code), (callee(), callee_code)].iter() {
+		state.insert(
+			*address,
+			MemoryAccount {
+				nonce: U256::one(),
+				balance: U256::zero(),
+				storage: BTreeMap::new(),
+				code: code.clone(),
+			},
+		);
+	}
+	MemoryBackend::new(vicinity, state)
+}
+
+/// Pause the transaction inside the sub-call, resuming it with `limit` until
+/// then, and take a snapshot of it.
+fn pause_in_sub_call(
+	backend: &MemoryBackend,
+	config: &Config,
+	limit: ExecutionLimit,
+) -> StackExecutionSnapshot {
+	let precompiles = BTreeMap::new();
+	let metadata = StackSubstateMetadata::new(100_000, config);
+	let state = MemoryStackState::new(metadata, backend);
+	let mut executor = StackExecutor::new_with_precompiles(state, config, &precompiles);
+	executor.set_block_validation(true);
+
+	let mut execution = match executor.begin_call(
+		H160::default(),
+		caller(),
+		U256::zero(),
+		Vec::new(),
+		100_000,
+		Vec::new(),
+	) {
+		Capture::Trap(execution) => execution,
+		Capture::Exit(result) => panic!("transaction exited early: {:?}", result),
+	};
+	while execution.depth() == 0 {
+		execution = match executor.resume(execution, limit) {
+			Capture::Trap(execution) => execution,
+			Capture::Exit(result) => panic!("transaction exited early: {:?}", result),
+		};
+	}
+	executor.snapshot(&execution)
+}
+
+fn resume(
+	snapshot: StackExecutionSnapshot,
+	backend: &MemoryBackend,
+	config: &Config,
+) -> (ExitReason, u64, H256, H256) {
+	let precompiles = BTreeMap::new();
+	let (mut executor, execution) =
+		StackExecutor::from_snapshot(snapshot, backend, config, &precompiles, ()).unwrap();
+	let reason = match executor.resume(execution, ExecutionLimit::unlimited()) {
+		Capture::Exit((reason, _)) => reason,
+		Capture::Trap(_) => panic!("execution without limit paused"),
+	};
+	let state = executor.state();
+	(
+		reason,
+		executor.used_gas(),
+		state.storage(caller(), H256::zero()),
+		state.storage(callee(), H256::zero()),
+	)
+}
+
+fn expected(backend: &MemoryBackend, config: &Config) -> (ExitReason, u64, H256, H256) {
+	let precompiles = BTreeMap::new();
+	let metadata = StackSubstateMetadata::new(100_000, config);
+	let state = MemoryStackState::new(metadata, backend);
+	let mut executor = StackExecutor::new_with_precompiles(state, config, &precompiles);
+	let (reason, _) = executor.transact_call(
+		H160::default(),
+		caller(),
+		U256::zero(),
+		Vec::new(),
+		100_000,
+		Vec::new(),
+	);
+	let state = executor.state();
+	(
+		reason,
+		executor.used_gas(),
+		state.storage(caller(), H256::zero()),
+		state.storage(callee(), H256::zero()),
+	)
+}
+
+#[test]
+fn resumes_across_sub_call() {
+	let vicinity = vicinity();
+	let backend = backend(&vicinity);
+	let config = Config::london();
+
+	let snapshot = pause_in_sub_call(&backend, &config, ExecutionLimit::steps(1));
+	assert_eq!(snapshot.frames.len(), 2);
+	assert!(snapshot.block_validation);
+
+	let result = resume(snapshot, &backend, &config);
+	assert_eq!(result.0, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert_eq!(result.2, H256::from_low_u64_be(42));
+	assert_eq!(result.3, H256::from_low_u64_be(1));
+	assert_eq!(result, expected(&backend, &config));
+}
+
+#[test]
+fn counts_gas_of_call_opcodes() {
+	let vicinity = vicinity();
+	let backend = backend(&vicinity);
+	let config = Config::london();
+
+	// The `CALL` spends gas, so the execution pauses as soon as it enters
+	// the sub-call, before its first step.
+	let snapshot = pause_in_sub_call(&backend, &config, ExecutionLimit::gas(1));
+	assert_eq!(snapshot.frames.len(), 2);
+	assert_eq!(snapshot.frames[1].runtime.machine.position, Ok(0));
+}
+
+#[test]
+fn rejects_invalid_snapshots() {
+	let vicinity = vicinity();
+	let backend = backend(&vicinity);
+	let config = Config::london();
+	let precompiles = BTreeMap::new();
+
+	let mut snapshot = pause_in_sub_call(&backend, &config, ExecutionLimit::steps(1));
+	snapshot.frames[1].runtime.machine.eof = true;
+	let restored =
+		StackExecutor::from_snapshot(snapshot.clone(), &backend, &config, &precompiles, ());
+	assert!(matches!(restored, Err(SnapshotError::InvalidEof(_))));
+
+	snapshot.frames.clear();
+	let restored = StackExecutor::from_snapshot(snapshot, &backend, &config, &precompiles, ());
+	assert_eq!(restored.err(), Some(SnapshotError::NoFrames));
+}
+
+#[cfg(feature = "with-codec")]
+#[test]
+fn codec_round_trip() {
+	use codec::{Decode, Encode};
+
+	let vicinity = vicinity();
+	let backend = backend(&vicinity);
+	let config = Config::london();
+
+	let snapshot = pause_in_sub_call(&backend, &config, ExecutionLimit::steps(1));
+	let decoded = StackExecutionSnapshot::decode(&mut &snapshot.encode()[..]).unwrap();
+	assert_eq!(
+		resume(decoded, &backend, &config),
+		expected(&backend, &config)
+	);
+}
+
+#[cfg(feature = "with-serde")]
+#[test]
+fn serde_round_trip() {
+	let vicinity = vicinity();
+	let backend = backend(&vicinity);
+	let config = Config::london();
+
+	let snapshot = pause_in_sub_call(&backend, &config, ExecutionLimit::steps(1));
+	let json = serde_json::to_string(&snapshot).unwrap();
+	let decoded: StackExecutionSnapshot = serde_json::from_str(&json).unwrap();
+	assert_eq!(
+		resume(decoded, &backend, &config),
+		expected(&backend, &config)
+	);
+}
@@ -0,0 +1,132 @@
+use evm::backend::{sec_trie_root, trie_root, MemoryAccount, MemoryBackend, EMPTY_TRIE_ROOT};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+mod common;
+
+use common::vicinity;
+
+fn hash(s: &str) -> H256 {
+	H256::from_str(s).unwrap()
+}
+
+#[test]
+fn computes_trie_roots() {
+	assert_eq!(trie_root(Vec::<(Vec<u8>, Vec<u8>)>::new()), EMPTY_TRIE_ROOT);
+	assert_eq!(
+		trie_root(vec![
+			(&b"doe"[..], &b"reindeer"[..]),
+			(&b"dog"[..], &b"puppy"[..]),
+			(&b"dogglesworth"[..], &b"cat"[..]),
+		]),
+		hash("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3")
+	);
+	assert_eq!(
+		trie_root(vec![(&b"A"[..], &[b'a'; 50][..])]),
+		hash("d23786fb4a010da3ce639d66d5e904a11dbc02746d1ce25029e53290cabf28ab")
+	);
+}
+
+#[test]
+fn computes_state_roots() {
+	let vicinity = vicinity();
+	assert_eq!(
+		MemoryBackend::new(&vicinity, BTreeMap::new()).state_root(),
+		EMPTY_TRIE_ROOT
+	);
+
+	let mut account = MemoryAccount {
+		nonce: U256::one(),
+		balance: U256::from(1000),
+		storage: BTreeMap::new(),
+		code: vec![0x00],
+	};
+	assert_eq!(account.storage_root(), EMPTY_TRIE_ROOT);
+
+	let mut state = BTreeMap::new();
+	state.insert(H160::repeat_byte(0xaa), account.clone());
+	let root = MemoryBackend::new(&vicinity, state.clone()).state_root();
+
+	// Zero values are not part of the storage trie.
+	account
+		.storage
+		.insert(H256::repeat_byte(1), H256::default());
+	assert_eq!(account.storage_root(), EMPTY_TRIE_ROOT);
+
+	// Values are RLP encoded without leading zeros.
+	account
+		.storage
+		.insert(H256::repeat_byte(2), H256::from_low_u64_be(0x42));
+	assert_eq!(
+		account.storage_root(),
+		sec_trie_root(vec![(H256::repeat_byte(2), vec![0x42])])
+	);
+
+	state.insert(H160::repeat_byte(0xaa), account);
+	let mut backend = MemoryBackend::new(&vicinity, state);
+	assert_ne!(backend.state_root(), root);
+	backend
+		.state_mut()
+		.get_mut(&H160::repeat_byte(0xaa))
+		.unwrap()
+		.storage
+		.clear();
+	assert_eq!(backend.state_root(), root);
+}
+
+#[test]
+fn matches_ethereum_tests_state_trie() {
+	// `test1` of `TrieTests/hex_encoded_securetrie_test.json` in
+	// ethereum/tests, a state trie of five RLP encoded accounts.
+	let accounts = [
+		(
+			"a94f5374fce5edbc8e2a8697c15331677e6ebf0b",
+			"f848018405f446a7a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a0c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+		),
+		(
+			"095e7baea6a6c7c4c2dfeb977efac326af552d87",
+			"f8440101a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a004bccc5d94f4d1f99aab44369a910179931772f2a5c001c3229f57831c102769",
+		),
+		(
+			"d2571607e241ecf590ed94b12d87c94babe36db6",
+			"f8440180a0ba4b47865c55a341a4a78759bb913cd15c3ee8eaf30a62fa8d1c8863113d84e8a0c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+		),
+		(
+			"62c01474f089b07dae603491675dc5b5748f7049",
+			"f8448080a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a0c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+		),
+		(
+			"2adc25665018aa1fe0e6bc666dac8fc2697ff9ba",
+			"f8478083019a59a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a0c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+		),
+	];
+	let accounts = accounts
+		.iter()
+		.map(|(address, account)| {
+			(
+				H160::from_str(address).unwrap(),
+				hex::decode(account).unwrap(),
+			)
+		})
+		.collect::<Vec<_>>();
+	assert_eq!(
+		sec_trie_root(accounts.iter().cloned()),
+		hash("730a444e08ab4b8dee147c9b232fc52d34a223d600031c1e9d25bfc985cbd797")
+	);
+
+	// The accounts without code nor storage are encoded the same way by
+	// `MemoryAccount`.
+	let encoded = |nonce: u64, balance: u64| {
+		MemoryAccount {
+			nonce: U256::from(nonce),
+			balance: U256::from(balance),
+			storage: BTreeMap::new(),
+			code: Vec::new(),
+		}
+		.rlp_bytes()
+	};
+	assert_eq!(encoded(1, 0x05f446a7), accounts[0].1);
+	assert_eq!(encoded(0, 0), accounts[3].1);
+	assert_eq!(encoded(0, 0x019a59), accounts[4].1);
+}
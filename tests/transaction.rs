@@ -0,0 +1,151 @@
+use evm::backend::{Backend, MemoryAccount, MemoryBackend};
+use evm::executor::stack::{
+	InvalidTransaction, MemoryStackState, StackExecutor, StackSubstateMetadata, TransactOutcome,
+	Transaction,
+};
+use evm::{Config, ExitReason, ExitSucceed};
+use primitive_types::{H160, U256};
+use std::collections::BTreeMap;
+
+mod common;
+
+use common::vicinity;
+
+fn account(balance: u64, code: Vec<u8>) -> MemoryAccount {
+	MemoryAccount {
+		nonce: U256::zero(),
+		balance: U256::from(balance),
+		storage: BTreeMap::new(),
+		code,
+	}
+}
+
+fn transaction(caller: H160) -> Transaction {
+	Transaction {
+		caller,
+		chain_id: Some(1),
+		nonce: U256::zero(),
+		gas_limit: U256::from(50_000),
+		max_fee_per_gas: U256::from(20),
+		max_priority_fee_per_gas: U256::from(2),
+		address: Some(H160::repeat_byte(0xbb)),
+		value: U256::from(1000),
+		data: Vec::new(),
+		access_list: Vec::new(),
+	}
+}
+
+/// Run a transaction, returning its outcome and the balances of the sender,
+/// the recipient and the coinbase.
+fn transact(transaction: Transaction) -> (Result<TransactOutcome, InvalidTransaction>, [U256; 3]) {
+	let gas_limit = transaction.gas_limit.as_u64();
+	transact_with_gas_limit(transaction, gas_limit)
+}
+
+/// Run a transaction with an executor of the given gas limit.
+fn transact_with_gas_limit(
+	transaction: Transaction,
+	gas_limit: u64,
+) -> (Result<TransactOutcome, InvalidTransaction>, [U256; 3]) {
+	let config = Config::shanghai();
+	let mut vicinity = vicinity();
+	vicinity.block_coinbase = H160::repeat_byte(0xcc);
+	vicinity.block_base_fee_per_gas = U256::from(10);
+	let mut state = BTreeMap::new();
+	state.insert(H160::repeat_byte(0xaa), account(10_000_000, Vec::new()));
+	state.insert(H160::repeat_byte(0xdd), account(10_000_000, vec![0x00]));
+	let backend = MemoryBackend::new(&vicinity, state);
+
+	let metadata = StackSubstateMetadata::new(gas_limit, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+	let outcome = executor.transact(transaction);
+	let balance = |byte| executor.state().basic(H160::repeat_byte(byte)).balance;
+	let balances = [balance(0xaa), balance(0xbb), balance(0xcc)];
+	(outcome, balances)
+}
+
+#[test]
+fn settles_fees() {
+	let (outcome, balances) = transact(transaction(H160::repeat_byte(0xaa)));
+	assert_eq!(
+		outcome,
+		Ok(TransactOutcome {
+			exit_reason: ExitReason::Succeed(ExitSucceed::Stopped),
+			return_value: Vec::new(),
+			used_gas: 21_000,
+			effective_gas_price: U256::from(12),
+			contract_address: None,
+		})
+	);
+	// The sender is refunded the unused gas, the base fee is burnt and the
+	// coinbase paid the priority fee.
+	assert_eq!(
+		balances,
+		[
+			U256::from(10_000_000 - 21_000 * 12 - 1000),
+			U256::from(1000),
+			U256::from(21_000 * 2),
+		]
+	);
+}
+
+#[test]
+fn rejects_invalid_transactions() {
+	let sender = H160::repeat_byte(0xaa);
+	let invalid = |f: fn(&mut Transaction)| {
+		let mut transaction = transaction(sender);
+		f(&mut transaction);
+		let (outcome, balances) = transact(transaction);
+		assert_eq!(
+			balances,
+			[U256::from(10_000_000), U256::zero(), U256::zero()]
+		);
+		outcome.unwrap_err()
+	};
+
+	assert_eq!(
+		invalid(|t| t.chain_id = Some(2)),
+		InvalidTransaction::InvalidChainId
+	);
+	assert_eq!(
+		invalid(|t| t.max_fee_per_gas = U256::from(9)),
+		InvalidTransaction::FeeCapTooLow
+	);
+	assert_eq!(
+		invalid(|t| t.max_priority_fee_per_gas = U256::from(21)),
+		InvalidTransaction::TipAboveFeeCap
+	);
+	assert_eq!(
+		invalid(|t| t.gas_limit = U256::from(20_999)),
+		InvalidTransaction::IntrinsicGasTooLow
+	);
+	assert_eq!(
+		invalid(|t| t.nonce = U256::one()),
+		InvalidTransaction::NonceTooHigh
+	);
+	assert_eq!(
+		invalid(|t| t.caller = H160::repeat_byte(0xdd)),
+		InvalidTransaction::SenderNotEoa
+	);
+	// The balance covers the gas used, but not the gas limit at the max fee.
+	assert_eq!(
+		invalid(|t| t.gas_limit = U256::from(500_000)),
+		InvalidTransaction::InsufficientFunds
+	);
+	assert_eq!(
+		invalid(|t| {
+			t.address = None;
+			t.data = vec![0; 49_153];
+		}),
+		InvalidTransaction::InitCodeTooLarge
+	);
+
+	let (outcome, balances) = transact_with_gas_limit(transaction(sender), 100_000);
+	assert_eq!(outcome, Err(InvalidTransaction::GasLimitMismatch));
+	assert_eq!(
+		balances,
+		[U256::from(10_000_000), U256::zero(), U256::zero()]
+	);
+}
@@ -0,0 +1,171 @@
+use evm::backend::Backend;
+use evm::backend::{MemoryAccount, MemoryBackend};
+use evm::executor::stack::{
+	MemoryStackState, StackExecutor, StackExitKind, StackState, StackSubstateMetadata,
+};
+use evm::{Config, CreateScheme, ExitReason, ExitSucceed};
+use primitive_types::{H160, H256, U256};
+use std::collections::BTreeMap;
+
+mod common;
+
+use common::vicinity;
+
+#[test]
+fn substates() {
+	let config = Config::cancun();
+	let vicinity = vicinity();
+	let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+	let metadata = StackSubstateMetadata::new(100_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+
+	let address = H160::repeat_byte(0x11);
+	let key = H256::repeat_byte(0x01);
+	let value = H256::repeat_byte(0x02);
+
+	// Reverted changes are dropped.
+	executor.enter_substate(0, false);
+	executor
+		.state_mut()
+		.set_transient_storage(address, key, value);
+	executor.exit_substate(StackExitKind::Reverted).unwrap();
+	assert_eq!(
+		executor.state().transient_storage(address, key),
+		H256::zero()
+	);
+
+	// Committed changes are visible to the parent, until the transaction ends.
+	executor.enter_substate(0, false);
+	executor.enter_substate(0, false);
+	executor
+		.state_mut()
+		.set_transient_storage(address, key, value);
+	executor.exit_substate(StackExitKind::Succeeded).unwrap();
+	assert_eq!(executor.state().transient_storage(address, key), value);
+	executor.exit_substate(StackExitKind::Succeeded).unwrap();
+	assert_eq!(
+		executor.state().transient_storage(address, key),
+		H256::zero()
+	);
+}
+
+/// Accounts of a reentrancy lock at 0x11..11, which calls 0x22..22, which
+/// calls it back.
+fn reentrancy_lock_accounts() -> BTreeMap<H160, MemoryAccount> {
+	let mut state = BTreeMap::new();
+	// Reverts if TLOAD(0) is set, else TSTORE(0, 1), CALL(0x22..22) and
+	// SSTORE(0, <returned word>).
+	state.insert(
+		H160::repeat_byte(0x11),
+		MemoryAccount {
+			nonce: U256::one(),
+			balance: U256::zero(),
+			storage: BTreeMap::new(),
+			code: hex::decode(
+				"5f5c60305760015f5d5f5f5f5f5f7322222222222222222222222222222222222222225af150\
+				 60205f5f3e5f515f55005b5f80fd",
+			)
+			.unwrap(),
+		},
+	);
+	// Returns the status of CALL(0x11..11).
+	state.insert(
+		H160::repeat_byte(0x22),
+		MemoryAccount {
+			nonce: U256::one(),
+			balance: U256::zero(),
+			storage: BTreeMap::new(),
+			code: hex::decode(
+				"5f5f5f5f5f7311111111111111111111111111111111111111115af15f5260205ff3",
+			)
+			.unwrap(),
+		},
+	);
+	state
+}
+
+#[test]
+fn reentrancy_lock() {
+	let config = Config::cancun();
+	let vicinity = vicinity();
+	let state = reentrancy_lock_accounts();
+	let backend = MemoryBackend::new(&vicinity, state);
+	let metadata = StackSubstateMetadata::new(1_000_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+
+	// The lock is never released, but it does not outlive the transaction.
+	for _ in 0..2 {
+		let (reason, _) = executor.transact_call(
+			H160::repeat_byte(0x33),
+			H160::repeat_byte(0x11),
+			U256::zero(),
+			Vec::new(),
+			400_000,
+			Vec::new(),
+		);
+		assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+		// The reentrant call reverted.
+		assert_eq!(
+			executor
+				.state()
+				.storage(H160::repeat_byte(0x11), H256::zero()),
+			H256::zero()
+		);
+	}
+}
+
+#[test]
+fn transactions_in_substate() {
+	let config = Config::cancun();
+	let vicinity = vicinity();
+	let backend = MemoryBackend::new(&vicinity, reentrancy_lock_accounts());
+	let metadata = StackSubstateMetadata::new(1_000_000, &config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let precompiles = BTreeMap::new();
+	let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+
+	// Transactions committing into a substate do not share transient
+	// storage nor created accounts.
+	executor
+		.state_mut()
+		.metadata_mut()
+		.gasometer_mut()
+		.record_cost(1_000_000)
+		.unwrap();
+	executor.enter_substate(1_000_000, false);
+	for _ in 0..2 {
+		let (reason, _) = executor.transact_call(
+			H160::repeat_byte(0x33),
+			H160::repeat_byte(0x11),
+			U256::zero(),
+			Vec::new(),
+			400_000,
+			Vec::new(),
+		);
+		assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	}
+	assert_eq!(
+		executor
+			.state()
+			.transient_storage(H160::repeat_byte(0x11), H256::zero()),
+		H256::zero()
+	);
+
+	let created = executor.create_address(CreateScheme::Legacy {
+		caller: H160::repeat_byte(0x33),
+	});
+	let reason = executor.transact_create(
+		H160::repeat_byte(0x33),
+		U256::zero(),
+		Vec::new(),
+		100_000,
+		Vec::new(),
+	);
+	assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+	assert!(!executor.state().created(created));
+	executor.exit_substate(StackExitKind::Reverted).unwrap();
+}